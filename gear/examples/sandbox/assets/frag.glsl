@@ -1,35 +0,0 @@
-#version 330 core
-
-out vec4 FragColor;
-
-struct Light {
-    vec3 position;
-    vec3 color;
-    float ambient;
-    float diffuse;
-    float specular;
-};
-
-in vec3 Normal;
-in vec3 FragPos;
-
-uniform vec4 material;
-uniform Light light;
-uniform vec3 viewPos;
-
-void main() {
-    vec3 norm = normalize(Normal);
-    vec3 lightDir = normalize(light.position - FragPos);
-    vec3 viewDir = normalize(viewPos - FragPos);
-    vec3 reflectDir = reflect(-lightDir, norm);
-
-    float diff = max(dot(norm, lightDir), 0.0);
-    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.w);
-
-    vec3 ambient = light.ambient * light.color;
-    vec3 diffuse = light.diffuse * diff * light.color;
-    vec3 specular = light.specular * spec * light.color;
-
-    vec3 result = (ambient + diffuse + specular) * material.xyz;
-    FragColor = vec4(result, 1.0);
-}
\ No newline at end of file
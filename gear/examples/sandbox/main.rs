@@ -2,7 +2,7 @@ pub mod layers;
 
 use gear::prelude::*;
 use log::error;
-use nalgebra::Vector3;
+use nalgebra::{UnitQuaternion, Vector3, Vector4};
 
 extern crate nalgebra as na;
 
@@ -25,7 +25,8 @@ impl Application for Sandbox {
     fn post_init(&mut self, ctx: &mut Context) {
         let Context {
             ref mut assets,
-            ref mut entity_buffer,
+            ref mut world,
+            ref mut schedule,
             ..
         } = ctx;
         self.load_objects(assets);
@@ -34,9 +35,26 @@ impl Application for Sandbox {
             error!("Error: {:?}", err);
         }
 
-        if let Err(err) = self.load_entities(assets, entity_buffer) {
+        if let Err(err) = self.load_entities(assets, world) {
             error!("Error: {:?}", err);
         }
+
+        schedule.add_system(Stage::Update, spin_system);
+        schedule.add_system(Stage::Update, hover_system);
+        schedule.add_system(Stage::Update, player_input_system);
+
+        ctx.exit_key = Some(Key::Escape);
+        let map = ctx.input.map_mut();
+        map.bind_axis("move_forward", AxisBinding::keys(Key::S, Key::W));
+        map.bind_axis("move_right", AxisBinding::keys(Key::A, Key::D));
+        map.bind_action("jump", ActionBinding::key(Key::Space));
+
+        ctx.camera_controller = CameraControllerConfig::FirstPerson {
+            target: "player".to_string(),
+            eye_height: 0.6,
+            sensitivity: 0.1,
+        }
+        .build(&ctx.world);
     }
 
     fn get_assets_path() -> &'static str {
@@ -46,56 +64,157 @@ impl Application for Sandbox {
 
 impl Sandbox {
     fn load_shaders(&mut self, assets: &mut AssetsManager) -> Result<(), ShaderError> {
-        assets.load_shader("default", "vert.glsl", "frag.glsl")?;
         assets.load_shader("light", "vert.glsl", "light.glsl")?;
         Ok(())
     }
 
     fn load_objects(&mut self, assets: &mut AssetsManager) {
-        let mut mesh = cube();
-        mesh.init();
-        assets.put_object("cube", mesh);
+        assets.put_object("cube", cube());
     }
 
     fn load_entities(
         &mut self,
         assets: &mut AssetsManager,
-        entities: &mut EntityBuffer,
+        world: &mut World,
     ) -> Result<(), EntityError> {
-        let entity = EntityBuilder::new()
-            .with_shader("default")
+        EntityBuilder::new()
+            .with_shader(PBR_SHADER)
             .with_mesh("cube")
-            .with_material(Material::new([1.0, 0.0, 0.0, 1.0]))
+            .with_material(
+                Material::new([1.0, 0.0, 0.0, 1.0])
+                    .with_metallic(0.0)
+                    .with_roughness(0.4),
+            )
             .with_position(Vector3::from([0.5, -0.5, 0.0]))
             .with_scale(Vector3::from([0.5, 0.5, 0.5]))
-            .with_rotation(Vector3::from([0.0, 0.0, 1.0]) * 15.0f32.to_radians())
-            .build(assets, entities)?;
-
-        entities.add_entity(entity);
-
-        let entity = EntityBuilder::new()
-            .with_shader("default")
+            .with_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                15.0f32.to_radians(),
+            ))
+            .with_component(Spin(0.5))
+            .with_component(Hover::new([1.0, 0.0, 0.0, 1.0]))
+            .build(assets, world)?;
+
+        EntityBuilder::new()
+            .with_shader(PBR_SHADER)
             .with_mesh("cube")
-            .with_material(Material::new([0.0, 0.0, 1.0, 1.0]))
+            .with_material(
+                Material::new([0.0, 0.0, 1.0, 1.0])
+                    .with_metallic(1.0)
+                    .with_roughness(0.2),
+            )
             .with_position(Vector3::from([-0.2, 0.2, 0.0]))
             .with_scale(Vector3::from([0.2, 0.2, 0.2]))
-            .with_rotation(Vector3::from([0.0, 0.0, 1.0]) * 60.0f32.to_radians())
-            .build(assets, entities)?;
-
-        entities.add_entity(entity);
-
-        let light = LightBuilder::new()
+            .with_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                60.0f32.to_radians(),
+            ))
+            .with_component(Spin(0.5))
+            .with_component(Hover::new([0.0, 0.0, 1.0, 1.0]))
+            .build(assets, world)?;
+
+        LightBuilder::new()
             .with_shader("light")
             .with_mesh("cube")
             .with_material(Material::new([1.0, 1.0, 1.0, 1.0]))
             .with_position(Vector3::from([1.2, 1.0, 2.0]))
             .with_scale(Vector3::from([0.1, 0.1, 0.1]))
-            .with_rotation(Vector3::from([0.0, 0.0, 1.0]) * 60.0f32.to_radians())
-            .build(assets, entities)?;
+            .with_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                60.0f32.to_radians(),
+            ))
+            .with_intensity(5.0)
+            .build(assets, world)?;
+
+        LightBuilder::new()
+            .with_shader("light")
+            .with_mesh("cube")
+            .with_color(Vector3::from([1.0, 0.6, 0.3]))
+            .with_position(Vector3::from([-1.5, 0.5, 1.0]))
+            .with_scale(Vector3::from([0.1, 0.1, 0.1]))
+            .with_intensity(3.0)
+            .build(assets, world)?;
 
-        entities.add_light(light);
+        EntityBuilder::new()
+            .with_shader(PBR_SHADER)
+            .with_mesh("cube")
+            .with_material(Material::new([0.5, 0.5, 0.5, 1.0]).with_roughness(0.9))
+            .with_position(Vector3::from([0.0, -1.5, 0.0]))
+            .with_scale(Vector3::from([10.0, 0.1, 10.0]))
+            .with_component(Collider::new(ColliderShape::cuboid(Vector3::new(
+                5.0, 0.05, 5.0,
+            ))))
+            .build(assets, world)?;
+
+        EntityBuilder::new()
+            .with_name("player")
+            .with_position(Vector3::from([0.0, -0.5, 3.0]))
+            .with_component(Player::new().with_size(1.6, 0.3))
+            .with_component(PlayerControls::default())
+            .build(assets, world)?;
         Ok(())
     }
 }
 
 impl EventListener for Sandbox {}
+
+// Rotation speed around the Z axis, in radians per second
+struct Spin(f32);
+
+fn spin_system(ctx: &mut Context) {
+    let delta = match ctx.world.resource::<Time>() {
+        Some(time) => time.delta(),
+        None => return,
+    };
+
+    ctx.world
+        .query::<(&mut Transform, &Spin)>()
+        .for_each(|_, (transform, spin)| {
+            transform.rotate_local(&UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                spin.0 * delta,
+            ))
+        });
+}
+
+// Base color of a hoverable entity, brightened while it is under the cursor
+struct Hover {
+    color: [f32; 4],
+}
+
+impl Hover {
+    fn new(color: [f32; 4]) -> Self {
+        Self { color }
+    }
+}
+
+fn hover_system(ctx: &mut Context) {
+    let (width, height) = ctx.window.get_size();
+    let viewport = ctx.camera.viewport.to_viewport(width as f32, height as f32);
+
+    // a locked cursor drives the camera, pick through the screen center then
+    let (x, y) = if ctx.window.is_mouse_locked() {
+        (
+            viewport.x + viewport.width * 0.5,
+            viewport.y + viewport.height * 0.5,
+        )
+    } else {
+        let (x, y) = ctx.window.get_cursor_pos();
+        (x as f32, y as f32)
+    };
+
+    let ray = ctx.camera.screen_point_to_ray(x, y, &viewport);
+    let hovered = ctx.world.raycast(&ray, &ctx.assets).map(|hit| hit.entity);
+
+    ctx.world
+        .query::<(&mut Renderable, &Hover)>()
+        .for_each(|entity, (renderable, hover)| {
+            let color = Vector4::from(hover.color);
+            let color = if Some(entity) == hovered {
+                color.map(|c| (c + 0.5).min(1.0))
+            } else {
+                color
+            };
+            renderable.material.base_color = MaterialInput::Constant(color);
+        });
+}
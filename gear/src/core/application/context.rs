@@ -1,21 +1,74 @@
 use crate::core::{
-    assets::AssetsManager, entity::EntityBuffer, renderer::camera::Camera, window::Window,
+    assets::AssetsManager,
+    audio::Audio,
+    ecs::{Schedule, Stage, World},
+    event::Key,
+    input::{Input, InputState},
+    physics::Physics,
+    renderer::{
+        camera::{Camera, CameraController, Cameras},
+        environment::Environment,
+    },
+    scene::Prefabs,
+    window::Window,
 };
 
+use super::time::Time;
+
 pub struct Context {
     pub assets: AssetsManager,
-    pub entity_buffer: EntityBuffer,
+    pub prefabs: Prefabs,
+    pub world: World,
+    pub schedule: Schedule,
+    // The main camera, the controller, the audio listener and picking follow
+    // it
     pub camera: Camera,
+    pub cameras: Cameras,
+    // Moves the camera every frame, e.g. a `FreeFlyController`. `None`, the
+    // default, leaves it to the application
+    pub camera_controller: Option<Box<dyn CameraController>>,
+    pub environment: Option<Environment>,
+    pub physics: Physics,
+    pub audio: Audio,
+    // Keyboard and mouse polled per frame
+    pub input_state: InputState,
+    // Actions and axes bound in the input map
+    pub input: Input,
     pub window: Window,
+    // Closes the window when pressed, off by default
+    pub exit_key: Option<Key>,
 }
 
 impl Context {
     pub fn new(assets_root: &'static str, window: Window) -> Self {
+        let mut world = World::new();
+        world.insert_resource(Time::new());
+
         Context {
             assets: AssetsManager::new(assets_root),
-            entity_buffer: EntityBuffer::new(),
+            prefabs: Prefabs::new(assets_root),
+            world,
+            schedule: Schedule::new(),
             camera: Camera::new(),
+            cameras: Cameras::new(),
+            camera_controller: None,
+            environment: None,
+            physics: Physics::new(),
+            audio: Audio::new(),
+            input_state: InputState::new(),
+            input: Input::new(),
             window,
+            exit_key: None,
         }
     }
+
+    pub fn run_systems(&mut self, stage: Stage) {
+        // the schedule is taken out so systems can borrow the whole context
+        let mut schedule = std::mem::take(&mut self.schedule);
+        schedule.run(stage, self);
+
+        // keep systems that were added while running
+        schedule.append(&mut self.schedule);
+        self.schedule = schedule;
+    }
 }
@@ -1,5 +1,6 @@
 pub mod context;
 mod state;
+mod time;
 
 use context::Context;
 use log::{debug, info, trace};
@@ -10,12 +11,16 @@ use crate::core::{
 };
 
 use self::state::AppState;
+pub use self::time::Time;
 
 use super::{
-    event::{propagate_event, Event, EventListener, GenericEventListener, Key},
+    ecs::Stage,
+    event::{
+        propagate_event, Event, EventListener, GenericEventListener, Key, MouseEvent, WindowEvent,
+    },
     layer::{imgui::ImGuiLayer, LayerStack, LayerStackImpl},
     logger,
-    renderer::Renderer,
+    renderer::{camera::play_paths, Renderer},
 };
 
 pub trait Application: EventListener {
@@ -64,8 +69,58 @@ impl<T: Application> Gear<T> {
 
         self.app.post_init(&mut self.context);
 
-        while !self.state.window.should_close() {
+        while !self.state.window.should_close() && !self.context.window.should_close() {
+            let delta = match self.context.world.resource_mut::<Time>() {
+                Some(mut time) => {
+                    time.tick();
+                    time.delta()
+                }
+                None => 0.0,
+            };
+
+            let Context {
+                input_state, input, ..
+            } = &mut self.context;
+            input_state.update();
+            input.update(input_state);
+            self.context.run_systems(Stage::Update);
+
+            let Context { physics, world, .. } = &mut self.context;
+            physics.update(world, delta);
+            for event in physics.drain_events() {
+                dispatcher.dispatch(Event::Physics(event));
+            }
+
+            self.context.run_systems(Stage::LateUpdate);
+            // the controller and the paths move entities too, e.g. the first
+            // person player, propagated with the rest
+            let Context {
+                camera_controller,
+                camera,
+                world,
+                ..
+            } = &mut self.context;
+            if let Some(controller) = camera_controller {
+                controller.update(camera, world, delta);
+            }
+            for event in play_paths(world, camera, delta) {
+                dispatcher.dispatch(Event::Path(event));
+            }
+            self.context.world.propagate_transforms();
             self.context.camera.update();
+            for (_, camera) in self.context.cameras.iter_mut() {
+                camera.update();
+            }
+
+            let Context {
+                audio,
+                world,
+                camera,
+                ..
+            } = &mut self.context;
+            audio.update(world, camera);
+
+            self.context.run_systems(Stage::RenderExtract);
             renderer.render(&mut self.context);
 
             self.context.window.update();
@@ -92,6 +147,30 @@ impl<T: Application> Gear<T> {
 
 impl<T: Application> GenericEventListener for Gear<T> {
     fn on_event(&mut self, event: Event) -> bool {
+        // the cursor moves over other windows too
+        let input = match event {
+            Event::Mouse(MouseEvent::Move(..)) => self.state.window.is_focused(),
+            Event::Keyboard(_) | Event::Mouse(_) => true,
+            _ => false,
+        };
+        if input || matches!(event, Event::Window(_) | Event::Gamepad(_)) {
+            self.context.input_state.on_event(&event);
+        }
+        // a controller tracking its own input lets go of it on focus loss
+        let focus = matches!(event, Event::Window(WindowEvent::Focus(_)));
+        if input || focus {
+            if let Some(controller) = self.context.camera_controller.as_mut() {
+                if controller.on_event(&event) {
+                    return true;
+                }
+            }
+        }
+
+        if let Event::Window(WindowEvent::FileDrop(drop)) = event {
+            let paths = self.context.window.dropped_files(drop).to_vec();
+            return self.app.on_file_drop(&paths) || self.layers.on_file_drop(&paths);
+        }
+
         if propagate_event(event, self) {
             return true;
         }
@@ -111,58 +190,11 @@ impl<T: Application> EventListener for Gear<T> {
         true
     }
 
-    fn on_key_press(&mut self, key: super::event::Key, _mods: super::event::Modifier) -> bool {
-        match key {
-            Key::Escape => {
-                info!(target: "GEAR", "Escape pressed.");
-                self.state.window.close();
-                true
-            }
-            Key::W => {
-                self.context.camera.move_forward(0.1);
-                false
-            }
-            Key::S => {
-                self.context.camera.move_backward(0.1);
-                false
-            }
-            Key::A => {
-                self.context.camera.move_left(0.1);
-                false
-            }
-            Key::D => {
-                self.context.camera.move_right(0.1);
-                false
-            }
-            _ => false,
-        }
-    }
-
-    fn on_key_repeat(&mut self, key: Key, _mods: super::event::Modifier) -> bool {
-        match key {
-            Key::W => {
-                self.context.camera.move_forward(0.1);
-                false
-            }
-            Key::S => {
-                self.context.camera.move_backward(0.1);
-                false
-            }
-            Key::A => {
-                self.context.camera.move_left(0.1);
-                false
-            }
-            Key::D => {
-                self.context.camera.move_right(0.1);
-                false
-            }
-            _ => false,
-        }
-    }
-
-    fn on_mouse_move(&mut self, x: f64, y: f64) -> bool {
-        if self.state.window.is_focused() {
-            self.context.camera.on_mouse_move(x as f32, y as f32);
+    fn on_key_press(&mut self, key: Key, _mods: super::event::Modifier) -> bool {
+        if self.context.exit_key == Some(key) {
+            info!(target: "GEAR", "Exit key pressed.");
+            self.state.window.close();
+            return true;
         }
         false
     }
@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+// Frame timing, stored as a world resource and advanced once per frame. The
+// `Context` inserts it; removing it isn't supported, everything timed,
+// physics included, would stand still.
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+    last_frame: Instant,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame: 0,
+            last_frame: Instant::now(),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.delta = now - self.last_frame;
+        self.elapsed += self.delta;
+        self.frame += 1;
+        self.last_frame = now;
+    }
+
+    // Seconds since the previous frame
+    pub fn delta(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fmt, hash::Hash, marker::PhantomData};
+
+use super::AssetError;
+
+// A generational reference to an asset stored in a `Pool`. Once the asset is
+// removed its slot generation is bumped, so old handles can be told apart
+// from the asset that may later reuse the slot.
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: u32, generation: u32) -> Self {
+        Self {
+            index,
+            generation,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub struct Pool<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+impl<T> Pool<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            return Handle::new(index, slot.generation);
+        }
+
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        Handle::new(self.slots.len() as u32 - 1, 0)
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Result<&T, AssetError> {
+        match self.slots.get(handle.index as usize) {
+            Some(slot) if slot.generation == handle.generation => {
+                slot.value.as_ref().ok_or(AssetError::StaleHandle)
+            }
+            _ => Err(AssetError::StaleHandle),
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Result<&mut T, AssetError> {
+        match self.slots.get_mut(handle.index as usize) {
+            Some(slot) if slot.generation == handle.generation => {
+                slot.value.as_mut().ok_or(AssetError::StaleHandle)
+            }
+            _ => Err(AssetError::StaleHandle),
+        }
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_ok()
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Result<T, AssetError> {
+        let slot = match self.slots.get_mut(handle.index as usize) {
+            Some(slot) if slot.generation == handle.generation => slot,
+            _ => return Err(AssetError::StaleHandle),
+        };
+
+        let value = slot.value.take().ok_or(AssetError::StaleHandle)?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        Ok(value)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
@@ -1,64 +1,226 @@
-use std::collections::HashMap;
+mod handle;
+
+use std::{collections::HashMap, sync::Arc};
 
 use log::warn;
 
+pub use handle::{Handle, Pool};
+
 use super::{
+    audio::{AudioError, Sound},
     entity::Mesh,
-    renderer::shader::{Shader, ShaderError},
+    renderer::{
+        shader::{Shader, ShaderError},
+        texture::{Texture, TextureError},
+    },
 };
 
+pub type MeshHandle = Handle<Mesh>;
+pub type ShaderHandle = Handle<Shader>;
+
+#[derive(Debug)]
+pub enum AssetError {
+    // The asset the handle pointed to was removed or replaced
+    StaleHandle,
+}
+
 pub struct AssetsManager {
     assets_root: &'static str,
-    shaders: HashMap<String, Shader>,
-    objects: HashMap<String, Mesh>,
+    shaders: Pool<Shader>,
+    shader_names: HashMap<String, ShaderHandle>,
+    objects: Pool<Mesh>,
+    object_names: HashMap<String, MeshHandle>,
+    textures: HashMap<String, Texture>,
+    sounds: HashMap<String, Arc<Sound>>,
+
+    // paths, relative to the assets root, of the assets loaded from files
+    shader_sources: HashMap<String, (String, String)>,
+    texture_sources: HashMap<String, String>,
 }
 
 impl AssetsManager {
     pub fn new(assets_root: &'static str) -> Self {
         Self {
             assets_root,
-            shaders: HashMap::new(),
-            objects: HashMap::new(),
+            shaders: Pool::new(),
+            shader_names: HashMap::new(),
+            objects: Pool::new(),
+            object_names: HashMap::new(),
+            textures: HashMap::new(),
+            sounds: HashMap::new(),
+            shader_sources: HashMap::new(),
+            texture_sources: HashMap::new(),
         }
     }
 
+    pub fn get_assets_root(&self) -> &'static str {
+        self.assets_root
+    }
+
     pub fn load_shader(
         &mut self,
         name: &str,
         vertex_path: &str,
         fragment_path: &str,
     ) -> Result<bool, ShaderError> {
-        let vertex_path = format!("{}/{}", self.assets_root, vertex_path);
-        let fragment_path = format!("{}/{}", self.assets_root, fragment_path);
+        let vertex_source = format!("{}/{}", self.assets_root, vertex_path);
+        let fragment_source = format!("{}/{}", self.assets_root, fragment_path);
 
-        let shader = Shader::new(vertex_path.as_str(), fragment_path.as_str())?;
+        let shader = Shader::new(vertex_source.as_str(), fragment_source.as_str())?;
 
-        if self.shaders.contains_key(name) {
+        if self.shader_names.contains_key(name) {
             warn!(target: "GEAR", "Shader with name '{}' already exists", name);
             return Ok(false);
         }
 
-        self.shaders.insert(name.to_string(), shader);
+        self.put_shader(name, shader);
+        self.shader_sources.insert(
+            name.to_string(),
+            (vertex_path.to_string(), fragment_path.to_string()),
+        );
         Ok(true)
     }
 
-    pub fn put_shader(&mut self, name: &str, shader: Shader) {
-        self.shaders.insert(name.to_string(), shader);
+    // Replacing a shader invalidates the handles to the previous one.
+    pub fn put_shader(&mut self, name: &str, shader: Shader) -> ShaderHandle {
+        self.shader_sources.remove(name);
+        let handle = self.shaders.insert(shader);
+        if let Some(old) = self.shader_names.insert(name.to_string(), handle) {
+            let _ = self.shaders.remove(old);
+        }
+        handle
     }
 
     pub fn get_shader(&self, name: &str) -> Option<&Shader> {
-        self.shaders.get(name)
+        let handle = self.shader_names.get(name)?;
+        self.shaders.get(*handle).ok()
+    }
+
+    pub fn shader_handle(&self, name: &str) -> Option<ShaderHandle> {
+        self.shader_names.get(name).copied()
+    }
+
+    pub fn shader(&self, handle: ShaderHandle) -> Result<&Shader, AssetError> {
+        self.shaders.get(handle)
+    }
+
+    pub fn shader_name(&self, handle: ShaderHandle) -> Option<&str> {
+        self.shader_names
+            .iter()
+            .find(|(_, h)| **h == handle)
+            .map(|(name, _)| name.as_str())
     }
 
-    pub fn put_object(&mut self, name: &str, object: Mesh) {
-        self.objects.insert(name.to_string(), object);
+    // Vertex and fragment paths of a shader loaded with `load_shader`.
+    pub fn shader_source(&self, name: &str) -> Option<(&str, &str)> {
+        self.shader_sources
+            .get(name)
+            .map(|(vertex, fragment)| (vertex.as_str(), fragment.as_str()))
+    }
+
+    pub fn remove_shader(&mut self, name: &str) -> Option<Shader> {
+        let handle = self.shader_names.remove(name)?;
+        self.shaders.remove(handle).ok()
+    }
+
+    // Replacing a mesh invalidates the handles to the previous one.
+    pub fn put_object(&mut self, name: &str, object: Mesh) -> MeshHandle {
+        let handle = self.objects.insert(object);
+        if let Some(old) = self.object_names.insert(name.to_string(), handle) {
+            let _ = self.objects.remove(old);
+        }
+        handle
     }
 
     pub fn get_object(&self, name: &str) -> Option<&Mesh> {
-        self.objects.get(name)
+        let handle = self.object_names.get(name)?;
+        self.objects.get(*handle).ok()
     }
 
     pub fn get_mut_object(&mut self, name: &str) -> Option<&mut Mesh> {
-        self.objects.get_mut(name)
+        let handle = self.object_names.get(name)?;
+        self.objects.get_mut(*handle).ok()
+    }
+
+    pub fn mesh_handle(&self, name: &str) -> Option<MeshHandle> {
+        self.object_names.get(name).copied()
+    }
+
+    pub fn mesh(&self, handle: MeshHandle) -> Result<&Mesh, AssetError> {
+        self.objects.get(handle)
+    }
+
+    pub fn mesh_name(&self, handle: MeshHandle) -> Option<&str> {
+        self.object_names
+            .iter()
+            .find(|(_, h)| **h == handle)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn mesh_mut(&mut self, handle: MeshHandle) -> Result<&mut Mesh, AssetError> {
+        self.objects.get_mut(handle)
+    }
+
+    pub fn remove_object(&mut self, name: &str) -> Option<Mesh> {
+        let handle = self.object_names.remove(name)?;
+        self.objects.remove(handle).ok()
+    }
+
+    // Uploads new or modified meshes. Called by the renderer before drawing.
+    pub fn upload_meshes(&mut self) {
+        for mesh in self.objects.iter_mut().filter(|mesh| mesh.is_dirty()) {
+            mesh.upload();
+        }
+    }
+
+    pub fn load_texture(&mut self, name: &str, path: &str) -> Result<bool, TextureError> {
+        let source = format!("{}/{}", self.assets_root, path);
+
+        if self.textures.contains_key(name) {
+            warn!(target: "GEAR", "Texture with name '{}' already exists", name);
+            return Ok(false);
+        }
+
+        let texture = Texture::new(source.as_str())?;
+        self.textures.insert(name.to_string(), texture);
+        self.texture_sources
+            .insert(name.to_string(), path.to_string());
+        Ok(true)
+    }
+
+    pub fn put_texture(&mut self, name: &str, texture: Texture) {
+        self.texture_sources.remove(name);
+        self.textures.insert(name.to_string(), texture);
+    }
+
+    // Path of a texture loaded with `load_texture`.
+    pub fn texture_source(&self, name: &str) -> Option<&str> {
+        self.texture_sources.get(name).map(|path| path.as_str())
+    }
+
+    pub fn get_texture(&self, name: &str) -> Option<&Texture> {
+        self.textures.get(name)
+    }
+
+    pub fn load_sound(&mut self, name: &str, path: &str) -> Result<bool, AudioError> {
+        let source = format!("{}/{}", self.assets_root, path);
+
+        if self.sounds.contains_key(name) {
+            warn!(target: "GEAR", "Sound with name '{}' already exists", name);
+            return Ok(false);
+        }
+
+        let sound = Sound::load(source.as_str())?;
+        self.sounds.insert(name.to_string(), Arc::new(sound));
+        Ok(true)
+    }
+
+    pub fn put_sound(&mut self, name: &str, sound: Sound) {
+        self.sounds.insert(name.to_string(), Arc::new(sound));
+    }
+
+    // Shared with the sources playing it.
+    pub fn get_sound(&self, name: &str) -> Option<Arc<Sound>> {
+        self.sounds.get(name).cloned()
     }
 }
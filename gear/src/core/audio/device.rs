@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "audio-device")]
+use super::AudioError;
+use super::Mixer;
+
+pub(super) enum Output {
+    // Nothing reaches the speakers, the mixer only runs through
+    // `Audio::render`
+    Null,
+    #[cfg(feature = "audio-device")]
+    Device(cpal::Stream),
+}
+
+impl Output {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Output::Null)
+    }
+}
+
+pub(super) fn null(sample_rate: u32) -> (Arc<Mutex<Mixer>>, Output) {
+    (Arc::new(Mutex::new(Mixer::new(sample_rate))), Output::Null)
+}
+
+// Default output device of the system, the mixer runs on its audio thread.
+#[cfg(feature = "audio-device")]
+pub(super) fn open() -> Result<(Arc<Mutex<Mixer>>, Output), AudioError> {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use log::warn;
+
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| AudioError::DeviceUnavailable("no output device".to_string()))?;
+    let config = device
+        .default_output_config()
+        .map_err(|err| AudioError::DeviceUnavailable(err.to_string()))?;
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        return Err(AudioError::DeviceUnavailable(format!(
+            "unsupported sample format {:?}",
+            config.sample_format()
+        )));
+    }
+
+    let mixer = Arc::new(Mutex::new(Mixer::new(config.sample_rate().0)));
+    let channels = config.channels() as usize;
+    let config: cpal::StreamConfig = config.into();
+
+    let callback_mixer = mixer.clone();
+    let mut stereo = Vec::new();
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let frames = data.len() / channels;
+                stereo.resize(frames * 2, 0.0);
+                match callback_mixer.lock() {
+                    Ok(mut mixer) => mixer.render(&mut stereo),
+                    Err(_) => stereo.fill(0.0),
+                }
+
+                for (out, frame) in data.chunks_exact_mut(channels).zip(stereo.chunks_exact(2)) {
+                    if channels == 1 {
+                        out[0] = (frame[0] + frame[1]) * 0.5;
+                    } else {
+                        out[0] = frame[0];
+                        out[1] = frame[1];
+                        out[2..].fill(0.0);
+                    }
+                }
+            },
+            |err| warn!(target: "GEAR", "Audio stream error: {}", err),
+            None,
+        )
+        .map_err(|err| AudioError::DeviceUnavailable(err.to_string()))?;
+    stream
+        .play()
+        .map_err(|err| AudioError::DeviceUnavailable(err.to_string()))?;
+
+    Ok((mixer, Output::Device(stream)))
+}
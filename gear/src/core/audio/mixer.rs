@@ -0,0 +1,486 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use crate::core::ecs::EntityId;
+
+use super::{MusicPlayer, Sound};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Bus {
+    Music,
+    Sfx,
+    Voice,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 3] = [Bus::Music, Bus::Sfx, Bus::Voice];
+
+    fn index(self) -> usize {
+        match self {
+            Bus::Music => 0,
+            Bus::Sfx => 1,
+            Bus::Voice => 2,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceId(u64);
+
+// Lowest playback speed, a stopped source would never finish.
+pub const MIN_PITCH: f32 = 0.01;
+
+// A sound to play. Sources without a position play as is, positioned ones are
+// mixed down to mono, attenuated with the distance to the listener and
+// panned.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub sound: Arc<Sound>,
+    pub volume: f32,
+    // Playback speed, 2.0 is an octave up, played no slower than `MIN_PITCH`
+    pub pitch: f32,
+    pub looping: bool,
+    pub bus: Bus,
+    pub position: Option<Vector3<f32>>,
+    // Follows the entity world position, see `Audio::update`
+    pub entity: Option<EntityId>,
+    // Full volume up to `min_distance`, no further attenuation past
+    // `max_distance`
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub rolloff: f32,
+}
+
+impl Source {
+    pub fn new(sound: Arc<Sound>) -> Self {
+        Self {
+            sound,
+            volume: 1.0,
+            pitch: 1.0,
+            looping: false,
+            bus: Bus::Sfx,
+            position: None,
+            entity: None,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            rolloff: 1.0,
+        }
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch.max(MIN_PITCH);
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_bus(mut self, bus: Bus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    pub fn with_position(mut self, position: Vector3<f32>) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn with_entity(mut self, entity: EntityId) -> Self {
+        self.entity = Some(entity);
+        self
+    }
+
+    pub fn with_distance(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    pub fn with_rolloff(mut self, rolloff: f32) -> Self {
+        self.rolloff = rolloff;
+        self
+    }
+
+    pub fn is_spatial(&self) -> bool {
+        self.position.is_some() || self.entity.is_some()
+    }
+
+    // Inverse distance attenuation, clamped to the source distances.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        let min_distance = self.min_distance.max(f32::EPSILON);
+        let distance = distance.clamp(min_distance, self.max_distance.max(min_distance));
+        min_distance / (min_distance + self.rolloff * (distance - min_distance))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Listener {
+    pub position: Vector3<f32>,
+    pub forward: Vector3<f32>,
+    pub up: Vector3<f32>,
+}
+
+impl Listener {
+    pub fn new() -> Self {
+        Self {
+            position: Vector3::zeros(),
+            forward: -Vector3::z(),
+            up: Vector3::y(),
+        }
+    }
+
+    // Left and right gains of a sound at `position`: equal power panning on
+    // the listener right axis.
+    pub fn pan(&self, position: &Vector3<f32>) -> (f32, f32) {
+        let right = self
+            .forward
+            .cross(&self.up)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::x);
+        let pan = (position - self.position)
+            .try_normalize(f32::EPSILON)
+            .map(|direction| direction.dot(&right))
+            .unwrap_or(0.0);
+
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        (angle.cos(), angle.sin())
+    }
+}
+
+struct Voice {
+    id: SourceId,
+    source: Source,
+    // in frames of the sound, fractional with a pitch
+    cursor: f64,
+    paused: bool,
+}
+
+impl Voice {
+    fn mix(&mut self, out: &mut [f32], gain: f32, listener: &Listener, sample_rate: u32) {
+        let source = &self.source;
+        let spatial = source.is_spatial();
+        let (left, right) = if spatial {
+            // an entity source not updated yet sits on the listener
+            let position = source.position.unwrap_or(listener.position);
+            let distance = (position - listener.position).norm();
+            let (left, right) = listener.pan(&position);
+            let gain = gain * source.attenuation(distance);
+            (left * gain, right * gain)
+        } else {
+            (gain, gain)
+        };
+
+        let frames = source.sound.frames();
+        let step = source.pitch.max(MIN_PITCH) as f64 * source.sound.sample_rate() as f64
+            / sample_rate as f64;
+
+        for frame in out.chunks_exact_mut(2) {
+            if self.cursor >= frames as f64 {
+                if !source.looping || frames == 0 {
+                    break;
+                }
+                self.cursor %= frames as f64;
+            }
+
+            // linear interpolation with the next frame
+            let index = self.cursor as usize;
+            let next = if index + 1 < frames {
+                index + 1
+            } else if source.looping {
+                0
+            } else {
+                index
+            };
+            let t = (self.cursor - index as f64) as f32;
+            let (l0, r0) = source.sound.frame(index);
+            let (l1, r1) = source.sound.frame(next);
+            let (mut l, mut r) = (l0 + (l1 - l0) * t, r0 + (r1 - r0) * t);
+            if spatial {
+                let mono = (l + r) * 0.5;
+                (l, r) = (mono, mono);
+            }
+
+            frame[0] += l * left;
+            frame[1] += r * right;
+            self.cursor += step;
+        }
+    }
+}
+
+// Lowers the music bus while a source plays on the voice bus.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ducking {
+    // Music bus gain under dialogue
+    pub level: f32,
+    // Seconds to go down to `level` and back up
+    pub attack: f32,
+    pub release: f32,
+}
+
+impl Ducking {
+    pub fn new(level: f32, attack: f32, release: f32) -> Self {
+        Self {
+            level,
+            attack,
+            release,
+        }
+    }
+
+    // No ducking at all.
+    pub fn none() -> Self {
+        Self::new(1.0, 0.0, 0.0)
+    }
+
+    fn next_gain(&self, gain: f32, talking: bool, frames: usize, sample_rate: u32) -> f32 {
+        let level = self.level.clamp(0.0, 1.0);
+        let (target, seconds) = if talking {
+            (level, self.attack)
+        } else {
+            (1.0, self.release)
+        };
+        if seconds <= 0.0 {
+            return target;
+        }
+
+        let step = (1.0 - level) * frames as f32 / (seconds * sample_rate as f32);
+        if gain < target {
+            (gain + step).min(target)
+        } else {
+            (gain - step).max(target)
+        }
+    }
+}
+
+// Sums the playing sources and the music into interleaved stereo at
+// `sample_rate`.
+pub struct Mixer {
+    sample_rate: u32,
+    voices: Vec<Voice>,
+    buses: [f32; 3],
+    master: f32,
+    listener: Listener,
+    next_id: u64,
+    music: MusicPlayer,
+    ducking: Ducking,
+    duck_gain: f32,
+    // music bus, mixed apart to be ducked
+    music_buffer: Vec<f32>,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            voices: Vec::new(),
+            buses: [1.0; 3],
+            master: 1.0,
+            listener: Listener::new(),
+            next_id: 0,
+            music: MusicPlayer::new(),
+            ducking: Ducking::new(0.3, 0.2, 0.8),
+            duck_gain: 1.0,
+            music_buffer: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn play(&mut self, source: Source) -> SourceId {
+        let id = SourceId(self.next_id);
+        self.next_id += 1;
+        self.voices.push(Voice {
+            id,
+            source,
+            cursor: 0.0,
+            paused: false,
+        });
+        id
+    }
+
+    pub fn stop(&mut self, id: SourceId) {
+        self.voices.retain(|voice| voice.id != id);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn set_paused(&mut self, id: SourceId, paused: bool) {
+        if let Some(voice) = self.voice_mut(id) {
+            voice.paused = paused;
+        }
+    }
+
+    // False once the source is stopped or finished.
+    pub fn is_playing(&self, id: SourceId) -> bool {
+        self.voices
+            .iter()
+            .any(|voice| voice.id == id && !voice.paused)
+    }
+
+    pub fn source(&self, id: SourceId) -> Option<&Source> {
+        self.voices
+            .iter()
+            .find(|voice| voice.id == id)
+            .map(|voice| &voice.source)
+    }
+
+    pub fn source_mut(&mut self, id: SourceId) -> Option<&mut Source> {
+        self.voice_mut(id).map(|voice| &mut voice.source)
+    }
+
+    // Sources attached to an entity.
+    pub fn attached(&self) -> Vec<(SourceId, EntityId)> {
+        self.voices
+            .iter()
+            .filter_map(|voice| Some((voice.id, voice.source.entity?)))
+            .collect()
+    }
+
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        self.buses[bus.index()]
+    }
+
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: f32) {
+        self.buses[bus.index()] = volume.max(0.0);
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master = volume.max(0.0);
+    }
+
+    pub fn listener(&self) -> &Listener {
+        &self.listener
+    }
+
+    pub fn set_listener(&mut self, listener: Listener) {
+        self.listener = listener;
+    }
+
+    pub fn music(&self) -> &MusicPlayer {
+        &self.music
+    }
+
+    pub fn music_mut(&mut self) -> &mut MusicPlayer {
+        &mut self.music
+    }
+
+    pub fn ducking(&self) -> &Ducking {
+        &self.ducking
+    }
+
+    pub fn set_ducking(&mut self, ducking: Ducking) {
+        self.ducking = ducking;
+    }
+
+    // Overwrites `out`, interleaved stereo, with the next frames of every
+    // source and the music. Finished sources are dropped.
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        self.music_buffer.clear();
+        self.music_buffer.resize(out.len(), 0.0);
+
+        let talking = self
+            .voices
+            .iter()
+            .any(|voice| !voice.paused && voice.source.bus == Bus::Voice);
+
+        for voice in self.voices.iter_mut().filter(|voice| !voice.paused) {
+            let bus = voice.source.bus;
+            let gain = voice.source.volume * self.buses[bus.index()] * self.master;
+            let out = match bus {
+                Bus::Music => &mut self.music_buffer,
+                _ => &mut *out,
+            };
+            voice.mix(out, gain, &self.listener, self.sample_rate);
+        }
+        self.music.render(
+            &mut self.music_buffer,
+            self.buses[Bus::Music.index()] * self.master,
+            self.sample_rate,
+        );
+
+        // the music bus ducks under the voice bus, ramped over the buffer
+        let frames = out.len() / 2;
+        let start = self.duck_gain;
+        self.duck_gain = self
+            .ducking
+            .next_gain(start, talking, frames, self.sample_rate);
+        for (index, (frame, music)) in out
+            .chunks_exact_mut(2)
+            .zip(self.music_buffer.chunks_exact(2))
+            .enumerate()
+        {
+            let gain = start + (self.duck_gain - start) * (index + 1) as f32 / frames as f32;
+            frame[0] += music[0] * gain;
+            frame[1] += music[1] * gain;
+        }
+
+        self.voices.retain(|voice| {
+            let frames = voice.source.sound.frames();
+            frames > 0 && (voice.source.looping || voice.cursor < frames as f64)
+        });
+    }
+
+    fn voice_mut(&mut self, id: SourceId) -> Option<&mut Voice> {
+        self.voices.iter_mut().find(|voice| voice.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-5, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn ducking_ramps_down_and_back_up() {
+        // 0.5s down to 0.2, 1s back up
+        let ducking = Ducking::new(0.2, 0.5, 1.0);
+
+        let mut gain = 1.0;
+        for expected in [0.84, 0.68, 0.52, 0.36, 0.2, 0.2] {
+            gain = ducking.next_gain(gain, true, 100, RATE);
+            assert_near(gain, expected);
+        }
+
+        gain = ducking.next_gain(gain, false, 500, RATE);
+        assert_near(gain, 0.6);
+        gain = ducking.next_gain(gain, false, 1000, RATE);
+        assert_near(gain, 1.0);
+    }
+
+    #[test]
+    fn ducking_without_ramps() {
+        let ducking = Ducking::new(0.3, 0.0, 0.0);
+        assert_near(ducking.next_gain(1.0, true, 1, RATE), 0.3);
+        assert_near(ducking.next_gain(0.3, false, 1, RATE), 1.0);
+
+        let none = Ducking::none();
+        assert_near(none.next_gain(1.0, true, 100, RATE), 1.0);
+    }
+
+    #[test]
+    fn ducking_level_is_clamped() {
+        let ducking = Ducking::new(-1.0, 0.0, 0.0);
+        assert_near(ducking.next_gain(1.0, true, 1, RATE), 0.0);
+    }
+}
@@ -0,0 +1,475 @@
+mod device;
+mod mixer;
+mod music;
+mod sound;
+mod stream;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(feature = "audio-device")]
+use log::warn;
+use nalgebra::Vector3;
+
+pub use mixer::{Bus, Ducking, Listener, Mixer, Source, SourceId, MIN_PITCH};
+pub use music::MusicPlayer;
+pub use sound::Sound;
+pub use stream::MusicStream;
+
+use self::device::Output;
+
+use super::{ecs::World, renderer::camera::Camera};
+
+// Sample rate of the null output.
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+
+#[derive(Debug)]
+pub enum AudioError {
+    FileNotFound(String),
+    UnsupportedFormat(String),
+    DecodingFailed(String),
+    DeviceUnavailable(String),
+}
+
+// Audio engine: the mixer plays on the default output device when built with
+// the `audio-device` feature and one is available, and on a null output
+// otherwise. The null output renders on demand into a buffer.
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    output: Output,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        #[cfg(feature = "audio-device")]
+        match device::open() {
+            Ok((mixer, output)) => return Self { mixer, output },
+            Err(err) => {
+                warn!(target: "GEAR", "No audio output, sounds are muted: {:?}", err)
+            }
+        }
+
+        Self::offline(DEFAULT_SAMPLE_RATE)
+    }
+
+    // Mixes only when `render` is called, e.g. to test without a sound card.
+    pub fn offline(sample_rate: u32) -> Self {
+        let (mixer, output) = device::null(sample_rate);
+        Self { mixer, output }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.output.is_null()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.mixer().sample_rate()
+    }
+
+    // Next `frames` frames of interleaved stereo, `None` when a device
+    // consumes the mix.
+    pub fn render(&mut self, frames: usize) -> Option<Vec<f32>> {
+        if !self.is_offline() {
+            return None;
+        }
+
+        let mut out = vec![0.0; frames * 2];
+        self.mixer().render(&mut out);
+        Some(out)
+    }
+
+    pub fn play(&mut self, source: Source) -> SourceId {
+        self.mixer().play(source)
+    }
+
+    pub fn stop(&mut self, id: SourceId) {
+        self.mixer().stop(id);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.mixer().stop_all();
+    }
+
+    pub fn pause(&mut self, id: SourceId) {
+        self.mixer().set_paused(id, true);
+    }
+
+    pub fn resume(&mut self, id: SourceId) {
+        self.mixer().set_paused(id, false);
+    }
+
+    pub fn is_playing(&self, id: SourceId) -> bool {
+        self.mixer().is_playing(id)
+    }
+
+    pub fn set_volume(&mut self, id: SourceId, volume: f32) {
+        if let Some(source) = self.mixer().source_mut(id) {
+            source.volume = volume;
+        }
+    }
+
+    pub fn set_pitch(&mut self, id: SourceId, pitch: f32) {
+        if let Some(source) = self.mixer().source_mut(id) {
+            source.pitch = pitch.max(MIN_PITCH);
+        }
+    }
+
+    pub fn set_position(&mut self, id: SourceId, position: Vector3<f32>) {
+        if let Some(source) = self.mixer().source_mut(id) {
+            source.position = Some(position);
+        }
+    }
+
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        self.mixer().bus_volume(bus)
+    }
+
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: f32) {
+        self.mixer().set_bus_volume(bus, volume);
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.mixer().master_volume()
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.mixer().set_master_volume(volume);
+    }
+
+    // Streams a WAV, OGG or FLAC file in place of the current music, fading
+    // over `fade` seconds. Paths are not relative to the assets root.
+    pub fn play_music(&mut self, path: &str, fade: f32) -> Result<(), AudioError> {
+        let stream = MusicStream::open(path, 0.0)?;
+        let mut mixer = self.mixer();
+        let sample_rate = mixer.sample_rate();
+        mixer.music_mut().play(stream, fade, sample_rate);
+        Ok(())
+    }
+
+    // Plays after the current track, decoding starts right away.
+    pub fn queue_music(&mut self, path: &str) -> Result<(), AudioError> {
+        let stream = MusicStream::open(path, 0.0)?;
+        self.mixer().music_mut().queue(stream);
+        Ok(())
+    }
+
+    // Crossfades to the next queued track, or fades out without one.
+    pub fn skip_music(&mut self, fade: f32) {
+        let mut mixer = self.mixer();
+        let sample_rate = mixer.sample_rate();
+        mixer.music_mut().skip(fade, sample_rate);
+    }
+
+    pub fn stop_music(&mut self, fade: f32) {
+        let mut mixer = self.mixer();
+        let sample_rate = mixer.sample_rate();
+        mixer.music_mut().stop(fade, sample_rate);
+    }
+
+    pub fn pause_music(&mut self) {
+        self.mixer().music_mut().set_paused(true);
+    }
+
+    pub fn resume_music(&mut self) {
+        self.mixer().music_mut().set_paused(false);
+    }
+
+    pub fn is_music_playing(&self) -> bool {
+        self.mixer().music().is_playing()
+    }
+
+    // Restarts the decoding of the current track at `seconds`.
+    pub fn seek_music(&mut self, seconds: f32) -> Result<(), AudioError> {
+        let Some(path) = self
+            .mixer()
+            .music()
+            .current()
+            .map(|stream| stream.path().to_string())
+        else {
+            return Ok(());
+        };
+
+        // opened without the lock, the audio thread keeps playing meanwhile
+        let stream = MusicStream::open(path.as_str(), seconds)?;
+        let mut mixer = self.mixer();
+        let sample_rate = mixer.sample_rate();
+        mixer.music_mut().replace(stream, sample_rate);
+        Ok(())
+    }
+
+    // Position and length of the current track in seconds, the length is
+    // unknown for OGG.
+    pub fn music_position(&self) -> Option<f32> {
+        self.mixer()
+            .music()
+            .current()
+            .map(|stream| stream.position())
+    }
+
+    pub fn music_duration(&self) -> Option<f32> {
+        self.mixer().music().current()?.duration()
+    }
+
+    // Crossfade between queued tracks, in seconds.
+    pub fn set_music_crossfade(&mut self, seconds: f32) {
+        self.mixer().music_mut().crossfade = seconds.max(0.0);
+    }
+
+    pub fn ducking(&self) -> Ducking {
+        *self.mixer().ducking()
+    }
+
+    // How the music bus ducks while sources play on the voice bus.
+    pub fn set_ducking(&mut self, ducking: Ducking) {
+        self.mixer().set_ducking(ducking);
+    }
+
+    pub fn listener(&self) -> Listener {
+        *self.mixer().listener()
+    }
+
+    // Called by the application every frame: the listener follows the camera
+    // and attached sources their entity. Sources of despawned entities stop.
+    pub fn update(&mut self, world: &World, camera: &Camera) {
+        let mut mixer = self.mixer();
+        mixer.set_listener(Listener {
+            position: camera.position,
+            forward: camera.front(),
+            up: camera.up(),
+        });
+
+        for (id, entity) in mixer.attached() {
+            if !world.is_alive(entity) {
+                mixer.stop(id);
+            } else if let Some(source) = mixer.source_mut(id) {
+                source.position = Some(world.world_matrix(entity).column(3).xyz());
+            }
+        }
+    }
+
+    // The audio thread keeps running if the lock was poisoned.
+    fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        self.mixer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: usize = 64;
+
+    // Mono sound holding 0.5, long enough for every render below.
+    fn constant() -> Arc<Sound> {
+        Arc::new(Sound::new(vec![0.5; 1024], 1, DEFAULT_SAMPLE_RATE))
+    }
+
+    fn render(source: Source, setup: impl FnOnce(&mut Audio)) -> Vec<f32> {
+        let mut audio = Audio::offline(DEFAULT_SAMPLE_RATE);
+        setup(&mut audio);
+        audio.play(source);
+        audio.render(FRAMES).unwrap()
+    }
+
+    fn assert_frames(out: &[f32], left: f32, right: f32) {
+        for frame in out.chunks_exact(2) {
+            assert!((frame[0] - left).abs() < 1e-5, "{:?}", frame);
+            assert!((frame[1] - right).abs() < 1e-5, "{:?}", frame);
+        }
+    }
+
+    #[test]
+    fn source_volume() {
+        let out = render(Source::new(constant()).with_volume(0.5), |_| {});
+        assert_frames(&out, 0.25, 0.25);
+    }
+
+    #[test]
+    fn bus_and_master_gain() {
+        let out = render(Source::new(constant()).with_bus(Bus::Voice), |audio| {
+            audio.set_bus_volume(Bus::Voice, 0.5);
+            audio.set_bus_volume(Bus::Sfx, 0.0);
+            audio.set_master_volume(0.4);
+        });
+        assert_frames(&out, 0.1, 0.1);
+    }
+
+    #[test]
+    fn pan_to_the_listener_right() {
+        // no attenuation within the min distance
+        let source = Source::new(constant())
+            .with_position(Vector3::new(5.0, 0.0, 0.0))
+            .with_distance(10.0, 100.0);
+        let out = render(source, |_| {});
+        assert_frames(&out, 0.0, 0.5);
+    }
+
+    #[test]
+    fn distance_attenuation() {
+        let center = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        let ahead = |distance: f32| {
+            Source::new(constant())
+                .with_position(Vector3::new(0.0, 0.0, -distance))
+                .with_distance(1.0, 10.0)
+        };
+
+        let out = render(ahead(0.5), |_| {});
+        assert_frames(&out, center, center);
+
+        let out = render(ahead(4.0), |_| {});
+        assert_frames(&out, center / 4.0, center / 4.0);
+
+        // clamped at the max distance
+        let out = render(ahead(50.0), |_| {});
+        assert_frames(&out, center / 10.0, center / 10.0);
+    }
+
+    #[test]
+    fn zero_pitch_still_finishes() {
+        let mut audio = Audio::offline(DEFAULT_SAMPLE_RATE);
+        let sound = Arc::new(Sound::new(vec![0.5; 16], 1, DEFAULT_SAMPLE_RATE));
+        let id = audio.play(Source::new(sound).with_pitch(0.0));
+        audio.render(FRAMES).unwrap();
+        assert!(audio.is_playing(id));
+
+        audio.render(2 * (16.0 / MIN_PITCH) as usize).unwrap();
+        assert!(!audio.is_playing(id));
+    }
+
+    const MUSIC_RATE: u32 = 1000;
+
+    // Mono float WAV holding `value`, in the temp directory.
+    struct Wav(String);
+
+    impl Wav {
+        fn new(name: &str, value: f32, frames: usize) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("gear-{}-{}.wav", std::process::id(), name));
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: MUSIC_RATE,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            for _ in 0..frames {
+                writer.write_sample(value).unwrap();
+            }
+            writer.finalize().unwrap();
+            Self(path.to_string_lossy().into_owned())
+        }
+
+        fn stream(&self) -> MusicStream {
+            let stream = MusicStream::open(&self.0, 0.0).unwrap();
+            stream.wait_decoded();
+            stream
+        }
+    }
+
+    impl Drop for Wav {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn play_music(audio: &mut Audio, wav: &Wav, fade: f32) {
+        audio
+            .mixer()
+            .music_mut()
+            .play(wav.stream(), fade, MUSIC_RATE);
+    }
+
+    fn queue_music(audio: &mut Audio, wav: &Wav) {
+        audio.mixer().music_mut().queue(wav.stream());
+    }
+
+    // Left channel of `frames` frames rendered in blocks of `block`.
+    fn render_music(audio: &mut Audio, frames: usize, block: usize) -> Vec<f32> {
+        (0..frames / block)
+            .flat_map(|_| audio.render(block).unwrap())
+            .step_by(2)
+            .collect()
+    }
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn music_crossfades_into_the_queue() {
+        let first = Wav::new("crossfade-first", 0.5, 1000);
+        let second = Wav::new("crossfade-second", 0.25, 1000);
+        let mut audio = Audio::offline(MUSIC_RATE);
+        audio.set_music_crossfade(0.2);
+        play_music(&mut audio, &first, 0.0);
+        queue_music(&mut audio, &second);
+
+        // the crossfade starts with the first block within 0.2s of the end
+        let out = render_music(&mut audio, 1200, 100);
+        assert_near(out[799], 0.5);
+        assert_near(out[849], 0.5 * 0.75 + 0.25 * 0.25);
+        assert_near(out[899], 0.5 * 0.5 + 0.25 * 0.5);
+        assert_near(out[1050], 0.25);
+        assert_eq!(audio.mixer().music().current().unwrap().path(), second.0);
+        assert_eq!(audio.mixer().music().queued(), 0);
+    }
+
+    #[test]
+    fn queue_moves_on_without_a_gap() {
+        let first = Wav::new("queue-first", 0.5, 100);
+        let second = Wav::new("queue-second", 0.25, 100);
+        let mut audio = Audio::offline(MUSIC_RATE);
+        play_music(&mut audio, &first, 0.0);
+        queue_music(&mut audio, &second);
+
+        let out = render_music(&mut audio, 300, 300);
+        assert!(out[..190].iter().all(|sample| *sample > 0.0));
+        assert_near(out[50], 0.5);
+        assert_near(out[150], 0.25);
+        assert_near(out[250], 0.0);
+        assert!(!audio.is_music_playing());
+    }
+
+    #[test]
+    fn skip_and_stop_fade() {
+        let first = Wav::new("skip-first", 0.5, 1000);
+        let second = Wav::new("skip-second", 0.25, 1000);
+        let mut audio = Audio::offline(MUSIC_RATE);
+        play_music(&mut audio, &first, 0.0);
+        queue_music(&mut audio, &second);
+        render_music(&mut audio, 100, 100);
+
+        audio.skip_music(0.1);
+        let out = render_music(&mut audio, 200, 10);
+        assert_near(out[49], 0.5 * 0.5 + 0.25 * 0.5);
+        assert_near(out[150], 0.25);
+        assert_eq!(audio.mixer().music().current().unwrap().path(), second.0);
+
+        // nothing left to skip to, the music fades out
+        audio.skip_music(0.1);
+        let out = render_music(&mut audio, 200, 10);
+        assert_near(out[49], 0.25 * 0.5);
+        assert_near(out[150], 0.0);
+        assert!(!audio.is_music_playing());
+
+        play_music(&mut audio, &first, 0.0);
+        queue_music(&mut audio, &second);
+        audio.stop_music(0.1);
+        assert_eq!(audio.mixer().music().queued(), 0);
+        let out = render_music(&mut audio, 200, 10);
+        assert_near(out[49], 0.5 * 0.5);
+        assert_near(out[150], 0.0);
+    }
+
+    #[test]
+    fn music_fades_in() {
+        let wav = Wav::new("fade-in", 0.5, 1000);
+        let mut audio = Audio::offline(MUSIC_RATE);
+        play_music(&mut audio, &wav, 0.1);
+
+        let out = render_music(&mut audio, 200, 10);
+        assert_near(out[49], 0.5 * 0.5);
+        assert_near(out[150], 0.5);
+    }
+}
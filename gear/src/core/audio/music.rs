@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+use super::stream::{MusicStream, StreamFrame};
+
+// Gain moving linearly towards a target, one step per output frame.
+#[derive(Debug, Copy, Clone)]
+struct Ramp {
+    gain: f32,
+    target: f32,
+    step: f32,
+}
+
+impl Ramp {
+    fn new(gain: f32) -> Self {
+        Self {
+            gain,
+            target: gain,
+            step: 0.0,
+        }
+    }
+
+    fn fade_to(&mut self, target: f32, seconds: f32, sample_rate: u32) {
+        self.target = target;
+        self.step = if seconds > 0.0 {
+            (target - self.gain).abs() / (seconds * sample_rate as f32)
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    fn next(&mut self) -> f32 {
+        if self.gain < self.target {
+            self.gain = (self.gain + self.step).min(self.target);
+        } else if self.gain > self.target {
+            self.gain = (self.gain - self.step).max(self.target);
+        }
+        self.gain
+    }
+}
+
+struct Track {
+    stream: MusicStream,
+    ramp: Ramp,
+    // frames around the playback position, for resampling
+    previous: (f32, f32),
+    next: (f32, f32),
+    t: f64,
+    ended: bool,
+}
+
+impl Track {
+    fn new(stream: MusicStream, fade: f32, sample_rate: u32) -> Self {
+        let mut ramp = Ramp::new(if fade > 0.0 { 0.0 } else { 1.0 });
+        ramp.fade_to(1.0, fade, sample_rate);
+
+        Self {
+            stream,
+            ramp,
+            previous: (0.0, 0.0),
+            next: (0.0, 0.0),
+            // pulls the first two frames
+            t: 2.0,
+            ended: false,
+        }
+    }
+
+    fn is_silent(&self) -> bool {
+        self.ended || (self.ramp.gain <= 0.0 && self.ramp.target <= 0.0)
+    }
+
+    // Seconds left to play, `None` when the length is unknown.
+    fn remaining(&self) -> Option<f32> {
+        Some(self.stream.duration()? - self.stream.position())
+    }
+
+    // Returns the frames mixed, fewer than asked once the track ends.
+    fn mix(&mut self, out: &mut [f32], gain: f32, sample_rate: u32) -> usize {
+        let step = self.stream.sample_rate() as f64 / sample_rate as f64;
+
+        for (index, frame) in out.chunks_exact_mut(2).enumerate() {
+            while self.t >= 1.0 && !self.ended {
+                match self.stream.next_frame() {
+                    StreamFrame::Frame(left, right) => {
+                        self.previous = self.next;
+                        self.next = (left, right);
+                        self.t -= 1.0;
+                    }
+                    // holds the last frame until the decoder catches up
+                    StreamFrame::Underrun => break,
+                    StreamFrame::End => self.ended = true,
+                }
+            }
+            if self.ended {
+                return index;
+            }
+
+            let t = self.t.min(1.0) as f32;
+            let gain = gain * self.ramp.next();
+            frame[0] += (self.previous.0 + (self.next.0 - self.previous.0) * t) * gain;
+            frame[1] += (self.previous.1 + (self.next.1 - self.previous.1) * t) * gain;
+            self.t += step;
+        }
+        out.len() / 2
+    }
+}
+
+// Streamed music: one track at a time, crossfading into the next one. Runs in
+// the mixer, on the music bus.
+pub struct MusicPlayer {
+    current: Option<Track>,
+    fading_out: Vec<Track>,
+    queue: VecDeque<MusicStream>,
+    paused: bool,
+    // Crossfade when the queue moves on by itself, needs the track length
+    pub crossfade: f32,
+}
+
+impl MusicPlayer {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            fading_out: Vec::new(),
+            queue: VecDeque::new(),
+            paused: false,
+            crossfade: 0.0,
+        }
+    }
+
+    // Replaces the current track, fading over `fade` seconds.
+    pub fn play(&mut self, stream: MusicStream, fade: f32, sample_rate: u32) {
+        self.fade_out(fade, sample_rate);
+        self.current = Some(Track::new(stream, fade, sample_rate));
+        self.paused = false;
+    }
+
+    pub fn queue(&mut self, stream: MusicStream) {
+        self.queue.push_back(stream);
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    // Crossfades to the next queued track, or fades out.
+    pub fn skip(&mut self, fade: f32, sample_rate: u32) {
+        match self.queue.pop_front() {
+            Some(stream) => self.play(stream, fade, sample_rate),
+            None => self.stop(fade, sample_rate),
+        }
+    }
+
+    // Fades out and drops the queue.
+    pub fn stop(&mut self, fade: f32, sample_rate: u32) {
+        self.queue.clear();
+        self.fade_out(fade, sample_rate);
+    }
+
+    // Swaps the stream of the current track without any fade, e.g. after a
+    // seek.
+    pub fn replace(&mut self, stream: MusicStream, sample_rate: u32) {
+        let gain = self.current.as_ref().map(|track| track.ramp);
+        let mut track = Track::new(stream, 0.0, sample_rate);
+        if let Some(ramp) = gain {
+            track.ramp = ramp;
+        }
+        self.current = Some(track);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.paused && self.current.is_some()
+    }
+
+    pub fn current(&self) -> Option<&MusicStream> {
+        self.current.as_ref().map(|track| &track.stream)
+    }
+
+    pub(super) fn render(&mut self, out: &mut [f32], gain: f32, sample_rate: u32) {
+        if self.paused {
+            return;
+        }
+
+        // the queue moves on at the end of the track, or a crossfade before,
+        // the next track starts right where the previous one stopped
+        let mut offset = 0;
+        loop {
+            if !self.queue.is_empty() {
+                let fade = match &self.current {
+                    None => Some(0.0),
+                    Some(track) => track
+                        .remaining()
+                        .filter(|left| *left <= self.crossfade)
+                        .map(|_| self.crossfade),
+                };
+                if let Some(fade) = fade {
+                    self.skip(fade, sample_rate);
+                }
+            }
+
+            let Some(track) = self.current.as_mut() else {
+                break;
+            };
+            offset += track.mix(&mut out[offset * 2..], gain, sample_rate);
+            if !track.ended {
+                break;
+            }
+            self.current = None;
+        }
+
+        for track in self.fading_out.iter_mut() {
+            track.mix(out, gain, sample_rate);
+        }
+        self.fading_out.retain(|track| !track.is_silent());
+    }
+
+    fn fade_out(&mut self, fade: f32, sample_rate: u32) {
+        if let Some(mut track) = self.current.take() {
+            track.ramp.fade_to(0.0, fade, sample_rate);
+            self.fading_out.push(track);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use lewton::inside_ogg::OggStreamReader;
+
+use super::AudioError;
+
+// Decoded sound, kept in memory as interleaved samples in [-1, 1].
+#[derive(Debug, Clone)]
+pub struct Sound {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Sound {
+    pub fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1);
+        let mut samples = samples;
+        samples.truncate(samples.len() - samples.len() % channels as usize);
+
+        Self {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    // WAV or OGG Vorbis file, picked from the extension.
+    pub fn load(path: &str) -> Result<Self, AudioError> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        match extension.as_deref() {
+            Some("wav") => load_wav(path),
+            Some("ogg") => load_ogg(path),
+            _ => Err(AudioError::UnsupportedFormat(path.to_string())),
+        }
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    // In seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    // Left and right samples of a frame, mono sounds play on both sides and
+    // channels past the second are dropped.
+    pub fn frame(&self, frame: usize) -> (f32, f32) {
+        let start = frame * self.channels as usize;
+        match self.samples.get(start..start + self.channels as usize) {
+            Some([mono]) => (*mono, *mono),
+            Some([left, right, ..]) => (*left, *right),
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+fn open(path: &str) -> Result<BufReader<File>, AudioError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|_| AudioError::FileNotFound(path.to_string()))
+}
+
+fn load_wav(path: &str) -> Result<Sound, AudioError> {
+    let reader = hound::WavReader::new(open(path)?)
+        .map_err(|err| AudioError::DecodingFailed(format!("{}: {}", path, err)))?;
+    let spec = reader.spec();
+
+    let samples: Result<Vec<f32>, hound::Error> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect()
+        }
+    };
+    let samples =
+        samples.map_err(|err| AudioError::DecodingFailed(format!("{}: {}", path, err)))?;
+
+    Ok(Sound::new(samples, spec.channels, spec.sample_rate))
+}
+
+fn load_ogg(path: &str) -> Result<Sound, AudioError> {
+    let decoding_failed =
+        |err: lewton::VorbisError| AudioError::DecodingFailed(format!("{}: {}", path, err));
+
+    let mut reader = OggStreamReader::new(open(path)?).map_err(decoding_failed)?;
+    let channels = reader.ident_hdr.audio_channels as u16;
+    let sample_rate = reader.ident_hdr.audio_sample_rate;
+
+    let mut samples = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(decoding_failed)? {
+        samples.extend(packet.into_iter().map(|sample| sample as f32 / 32768.0));
+    }
+
+    Ok(Sound::new(samples, channels, sample_rate))
+}
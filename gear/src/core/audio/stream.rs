@@ -0,0 +1,348 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use claxon::FlacReader;
+use lewton::inside_ogg::OggStreamReader;
+use log::warn;
+use ringbuf::{HeapConsumer, HeapProducer, HeapRb};
+
+use super::AudioError;
+
+// Frames decoded ahead of the playback per stream, about a second.
+const BUFFERED_FRAMES: usize = 48000;
+// Frames decoded at once from a WAV file, other formats go by packet.
+const WAV_CHUNK_FRAMES: usize = 4096;
+
+enum Decoder {
+    Wav {
+        reader: hound::WavReader<BufReader<File>>,
+        float: bool,
+        scale: f32,
+    },
+    Ogg(Box<OggStreamReader<BufReader<File>>>),
+    Flac {
+        reader: FlacReader<File>,
+        buffer: Vec<i32>,
+        scale: f32,
+    },
+}
+
+impl Decoder {
+    // WAV, OGG Vorbis or FLAC file, picked from the extension.
+    fn open(path: &str) -> Result<Self, AudioError> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        let file = || File::open(path).map_err(|_| AudioError::FileNotFound(path.to_string()));
+        let decoding_failed =
+            |err: &dyn std::fmt::Display| AudioError::DecodingFailed(format!("{}: {}", path, err));
+
+        match extension.as_deref() {
+            Some("wav") => {
+                let reader = hound::WavReader::new(BufReader::new(file()?))
+                    .map_err(|err| decoding_failed(&err))?;
+                let spec = reader.spec();
+                Ok(Decoder::Wav {
+                    reader,
+                    float: spec.sample_format == hound::SampleFormat::Float,
+                    scale: 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32,
+                })
+            }
+            Some("ogg") => OggStreamReader::new(BufReader::new(file()?))
+                .map(|reader| Decoder::Ogg(Box::new(reader)))
+                .map_err(|err| decoding_failed(&err)),
+            Some("flac") => {
+                let reader = FlacReader::new(file()?).map_err(|err| decoding_failed(&err))?;
+                let scale = 1.0 / (1u64 << (reader.streaminfo().bits_per_sample - 1)) as f32;
+                Ok(Decoder::Flac {
+                    reader,
+                    buffer: Vec::new(),
+                    scale,
+                })
+            }
+            _ => Err(AudioError::UnsupportedFormat(path.to_string())),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        match self {
+            Decoder::Wav { reader, .. } => reader.spec().channels,
+            Decoder::Ogg(reader) => reader.ident_hdr.audio_channels as u16,
+            Decoder::Flac { reader, .. } => reader.streaminfo().channels as u16,
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Decoder::Wav { reader, .. } => reader.spec().sample_rate,
+            Decoder::Ogg(reader) => reader.ident_hdr.audio_sample_rate,
+            Decoder::Flac { reader, .. } => reader.streaminfo().sample_rate,
+        }
+    }
+
+    // In frames, unknown for OGG without reading the whole file.
+    fn frames(&self) -> Option<u64> {
+        match self {
+            Decoder::Wav { reader, .. } => Some(reader.duration() as u64),
+            Decoder::Ogg(_) => None,
+            Decoder::Flac { reader, .. } => reader.streaminfo().samples,
+        }
+    }
+
+    // Moves near `frame`, returns the frame the next decoded samples start
+    // at. FLAC can only skip by decoding so it stays at the start, OGG lands
+    // on a page boundary that may be on either side of `frame`.
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioError> {
+        if frame == 0 {
+            return Ok(0);
+        }
+
+        let decoding_failed =
+            |err: lewton::VorbisError| AudioError::DecodingFailed(err.to_string());
+        match self {
+            Decoder::Wav { reader, .. } => reader
+                .seek(frame as u32)
+                .map(|_| frame)
+                .map_err(|err| AudioError::DecodingFailed(err.to_string())),
+            Decoder::Ogg(reader) => {
+                reader.seek_absgp_pg(frame).map_err(decoding_failed)?;
+                // the granule position is unknown until a page ends, drop the
+                // packets before it
+                loop {
+                    if reader
+                        .read_dec_packet_itl()
+                        .map_err(decoding_failed)?
+                        .is_none()
+                    {
+                        return Ok(frame);
+                    }
+                    if let Some(position) = reader.get_last_absgp() {
+                        return Ok(position);
+                    }
+                }
+            }
+            Decoder::Flac { .. } => Ok(0),
+        }
+    }
+
+    // Next interleaved samples, `None` at the end of the file.
+    fn decode(&mut self) -> Result<Option<Vec<f32>>, AudioError> {
+        match self {
+            Decoder::Wav {
+                reader,
+                float,
+                scale,
+            } => {
+                let count = WAV_CHUNK_FRAMES * reader.spec().channels as usize;
+                let samples: Result<Vec<f32>, hound::Error> = if *float {
+                    reader.samples::<f32>().take(count).collect()
+                } else {
+                    reader
+                        .samples::<i32>()
+                        .take(count)
+                        .map(|sample| sample.map(|sample| sample as f32 * *scale))
+                        .collect()
+                };
+                let samples = samples.map_err(|err| AudioError::DecodingFailed(err.to_string()))?;
+                Ok((!samples.is_empty()).then_some(samples))
+            }
+            Decoder::Ogg(reader) => {
+                let packet = reader
+                    .read_dec_packet_itl()
+                    .map_err(|err| AudioError::DecodingFailed(err.to_string()))?;
+                Ok(packet.map(|packet| {
+                    packet
+                        .into_iter()
+                        .map(|sample| sample as f32 / 32768.0)
+                        .collect()
+                }))
+            }
+            Decoder::Flac {
+                reader,
+                buffer,
+                scale,
+            } => {
+                let block = reader
+                    .blocks()
+                    .read_next_or_eof(std::mem::take(buffer))
+                    .map_err(|err| AudioError::DecodingFailed(err.to_string()))?;
+                let Some(block) = block else {
+                    return Ok(None);
+                };
+
+                let mut samples =
+                    Vec::with_capacity((block.duration() * block.channels()) as usize);
+                for frame in 0..block.duration() {
+                    for channel in 0..block.channels() {
+                        samples.push(block.sample(channel, frame) as f32 * *scale);
+                    }
+                }
+                *buffer = block.into_buffer();
+                Ok(Some(samples))
+            }
+        }
+    }
+}
+
+pub(super) enum StreamFrame {
+    Frame(f32, f32),
+    // The decoder is behind, nothing to play yet
+    Underrun,
+    End,
+}
+
+// Long sound decoded on a background thread into a ring buffer, so only a
+// second of it is in memory at a time. Dropping the stream stops the thread.
+pub struct MusicStream {
+    path: String,
+    channels: u16,
+    sample_rate: u32,
+    frames: Option<u64>,
+    consumer: HeapConsumer<f32>,
+    finished: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    // frames read so far, from the start of the file
+    position: u64,
+}
+
+impl MusicStream {
+    // Starts decoding at `start` seconds.
+    pub fn open(path: &str, start: f32) -> Result<Self, AudioError> {
+        let mut decoder = Decoder::open(path)?;
+        let channels = decoder.channels().max(1);
+        let sample_rate = decoder.sample_rate();
+        let frames = decoder.frames();
+        let start = (start.max(0.0) as f64 * sample_rate as f64) as u64;
+
+        // seek here to know where the playback starts, the frames short of
+        // `start` are decoded and dropped on the thread
+        let reached = decoder.seek(start)?;
+        let skip = start.saturating_sub(reached) * channels as u64;
+
+        let (producer, consumer) = HeapRb::new(BUFFERED_FRAMES * channels as usize).split();
+        let finished = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_path = path.to_string();
+        let thread_finished = finished.clone();
+        let thread_stop = stop.clone();
+        thread::Builder::new()
+            .name("gear-music".to_string())
+            .spawn(move || {
+                if let Err(err) = decode(&mut decoder, producer, skip as usize, &thread_stop) {
+                    warn!(target: "GEAR", "Streaming '{}' failed: {:?}", thread_path, err);
+                }
+                thread_finished.store(true, Ordering::Release);
+            })
+            .map_err(|err| AudioError::DecodingFailed(err.to_string()))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            channels,
+            sample_rate,
+            frames,
+            consumer,
+            finished,
+            stop,
+            position: start.max(reached),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // In seconds, from the start of the file.
+    pub fn position(&self) -> f32 {
+        self.position as f32 / self.sample_rate as f32
+    }
+
+    // In seconds, `None` when the format doesn't tell.
+    pub fn duration(&self) -> Option<f32> {
+        self.frames
+            .map(|frames| frames as f32 / self.sample_rate as f32)
+    }
+
+    pub(super) fn next_frame(&mut self) -> StreamFrame {
+        // read the flag first, the samples are all pushed once it is set
+        let finished = self.finished.load(Ordering::Acquire);
+        let channels = self.channels as usize;
+        if self.consumer.len() < channels {
+            return if finished {
+                StreamFrame::End
+            } else {
+                StreamFrame::Underrun
+            };
+        }
+
+        // mono plays on both sides, channels past the second are dropped
+        let left = self.consumer.pop().unwrap_or(0.0);
+        let right = match channels {
+            1 => left,
+            _ => self.consumer.pop().unwrap_or(0.0),
+        };
+        if channels > 2 {
+            self.consumer.skip(channels - 2);
+        }
+
+        self.position += 1;
+        StreamFrame::Frame(left, right)
+    }
+
+    // Blocks until a file shorter than the buffer is fully decoded, so the
+    // tests never hit an underrun.
+    #[cfg(test)]
+    pub(super) fn wait_decoded(&self) {
+        while !self.finished.load(Ordering::Acquire) {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+impl Drop for MusicStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+fn decode(
+    decoder: &mut Decoder,
+    mut producer: HeapProducer<f32>,
+    mut skip: usize,
+    stop: &AtomicBool,
+) -> Result<(), AudioError> {
+    while !stop.load(Ordering::Acquire) {
+        let Some(samples) = decoder.decode()? else {
+            return Ok(());
+        };
+        let dropped = skip.min(samples.len());
+        skip -= dropped;
+
+        let mut pending = &samples[dropped..];
+        while !pending.is_empty() {
+            if stop.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            let pushed = producer.push_slice(pending);
+            pending = &pending[pushed..];
+            if !pending.is_empty() {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+    Ok(())
+}
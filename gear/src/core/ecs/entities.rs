@@ -0,0 +1,152 @@
+use std::fmt;
+
+// A generational entity id. The index is reused after the entity is
+// despawned, the generation tells the old id apart from the new entity.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    // Rebuilds an id read back from outside the world, e.g. from the GPU. The
+    // entity may no longer exist, check with `World::is_alive`.
+    pub(crate) fn from_raw(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Debug for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Entity({}v{})", self.index, self.generation)
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+pub(super) struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    count: usize,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            count: 0,
+        }
+    }
+
+    pub fn spawn(&mut self) -> EntityId {
+        self.count += 1;
+
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            return EntityId {
+                index,
+                generation: self.generations[index as usize],
+            };
+        }
+
+        self.generations.push(0);
+        self.alive.push(true);
+        EntityId {
+            index: self.generations.len() as u32 - 1,
+            generation: 0,
+        }
+    }
+
+    pub fn despawn(&mut self, entity: EntityId) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        self.count -= 1;
+        true
+    }
+
+    pub fn is_alive(&self, entity: EntityId) -> bool {
+        let index = entity.index as usize;
+        index < self.alive.len()
+            && self.alive[index]
+            && self.generations[index] == entity.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(index, _)| EntityId {
+                index: index as u32,
+                generation: self.generations[index],
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_reused_with_a_new_generation() {
+        let mut entities = Entities::new();
+        let a = entities.spawn();
+        let b = entities.spawn();
+        assert_eq!((a.index(), b.index()), (0, 1));
+        assert_eq!(entities.len(), 2);
+
+        assert!(entities.despawn(a));
+        assert!(!entities.despawn(a));
+        assert!(!entities.is_alive(a));
+        assert_eq!(entities.len(), 1);
+
+        let c = entities.spawn();
+        assert_eq!(c.index(), a.index());
+        assert_eq!(c.generation(), a.generation() + 1);
+        assert!(entities.is_alive(c));
+        assert!(!entities.is_alive(a));
+    }
+
+    #[test]
+    fn iter_skips_despawned() {
+        let mut entities = Entities::new();
+        let ids: Vec<EntityId> = (0..4).map(|_| entities.spawn()).collect();
+        entities.despawn(ids[1]);
+        entities.despawn(ids[2]);
+        let reused = entities.spawn();
+
+        let alive: Vec<EntityId> = entities.iter().collect();
+        assert_eq!(alive, vec![ids[0], reused, ids[3]]);
+    }
+
+    #[test]
+    fn unknown_ids_are_not_alive() {
+        let entities = Entities::new();
+        assert!(!entities.is_alive(EntityId::from_raw(5, 0)));
+    }
+}
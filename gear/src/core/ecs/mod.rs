@@ -0,0 +1,12 @@
+mod entities;
+mod names;
+mod query;
+mod schedule;
+mod storage;
+mod world;
+
+pub use entities::EntityId;
+pub use query::{Query, QueryBorrow};
+pub use schedule::{Schedule, Stage, System};
+pub use storage::SparseSet;
+pub use world::{Component, World};
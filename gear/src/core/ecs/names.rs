@@ -0,0 +1,226 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::EntityId;
+
+// Unique entity names, indexed both ways so that lookups by name and by
+// entity are O(1).
+pub(super) struct Names {
+    entities: HashMap<String, EntityId>,
+    names: HashMap<EntityId, String>,
+}
+
+impl Names {
+    pub fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    // Returns false when another entity already has the name.
+    pub fn set(&mut self, entity: EntityId, name: &str) -> bool {
+        match self.entities.get(name) {
+            Some(owner) if *owner != entity => return false,
+            Some(_) => return true,
+            None => {}
+        }
+
+        self.remove(entity);
+        self.entities.insert(name.to_string(), entity);
+        self.names.insert(entity, name.to_string());
+        true
+    }
+
+    pub fn remove(&mut self, entity: EntityId) -> Option<String> {
+        let name = self.names.remove(&entity)?;
+        self.entities.remove(&name);
+        Some(name)
+    }
+
+    pub fn name(&self, entity: EntityId) -> Option<&str> {
+        self.names.get(&entity).map(|name| name.as_str())
+    }
+
+    pub fn find(&self, name: &str) -> Option<EntityId> {
+        self.entities.get(name).copied()
+    }
+}
+
+// Tags are free form and shared by any number of entities. Entities of a tag
+// are kept sorted so iteration is deterministic.
+pub(super) struct Tags {
+    entities: HashMap<String, BTreeSet<EntityId>>,
+    tags: HashMap<EntityId, BTreeSet<String>>,
+}
+
+impl Tags {
+    pub fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            tags: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, entity: EntityId, tag: &str) {
+        self.entities
+            .entry(tag.to_string())
+            .or_default()
+            .insert(entity);
+        self.tags.entry(entity).or_default().insert(tag.to_string());
+    }
+
+    pub fn remove(&mut self, entity: EntityId, tag: &str) -> bool {
+        let Some(tags) = self.tags.get_mut(&entity) else {
+            return false;
+        };
+        if !tags.remove(tag) {
+            return false;
+        }
+        if tags.is_empty() {
+            self.tags.remove(&entity);
+        }
+
+        if let Some(entities) = self.entities.get_mut(tag) {
+            entities.remove(&entity);
+            if entities.is_empty() {
+                self.entities.remove(tag);
+            }
+        }
+        true
+    }
+
+    pub fn remove_entity(&mut self, entity: EntityId) {
+        for tag in self.tags.remove(&entity).unwrap_or_default() {
+            if let Some(entities) = self.entities.get_mut(&tag) {
+                entities.remove(&entity);
+                if entities.is_empty() {
+                    self.entities.remove(&tag);
+                }
+            }
+        }
+    }
+
+    pub fn has(&self, entity: EntityId, tag: &str) -> bool {
+        self.tags
+            .get(&entity)
+            .is_some_and(|tags| tags.contains(tag))
+    }
+
+    pub fn tags(&self, entity: EntityId) -> impl Iterator<Item = &str> + '_ {
+        self.tags
+            .get(&entity)
+            .into_iter()
+            .flatten()
+            .map(|tag| tag.as_str())
+    }
+
+    pub fn entities(&self, tag: &str) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.get(tag).into_iter().flatten().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ecs::World, entity::EntityError};
+
+    #[test]
+    fn names_are_unique() {
+        let mut world = World::new();
+        let (a, b) = (world.spawn(), world.spawn());
+        world.set_name(a, "player").unwrap();
+        world.set_name(a, "player").unwrap();
+
+        assert!(matches!(
+            world.set_name(b, "player"),
+            Err(EntityError::NameTaken(name)) if name == "player"
+        ));
+        assert_eq!(world.name(b), None);
+        assert_eq!(world.find_by_name("player"), Some(a));
+    }
+
+    #[test]
+    fn rename_frees_the_old_name() {
+        let mut world = World::new();
+        let (a, b) = (world.spawn(), world.spawn());
+        world.set_name(a, "old").unwrap();
+        world.set_name(a, "new").unwrap();
+
+        assert_eq!(world.name(a), Some("new"));
+        assert_eq!(world.find_by_name("new"), Some(a));
+        assert_eq!(world.find_by_name("old"), None);
+
+        world.set_name(b, "old").unwrap();
+        assert_eq!(world.find_by_name("old"), Some(b));
+        assert_eq!(world.clear_name(a), Some("new".to_string()));
+        assert_eq!(world.find_by_name("new"), None);
+    }
+
+    #[test]
+    fn despawn_drops_the_name_and_tags() {
+        let mut world = World::new();
+        let a = world.spawn();
+        world.set_name(a, "crate").unwrap();
+        world.add_tag(a, "loot").unwrap();
+        world.despawn(a).unwrap();
+
+        assert_eq!(world.find_by_name("crate"), None);
+        assert_eq!(world.iter_with_tag("loot").count(), 0);
+
+        // the index is reused, the new entity doesn't inherit anything
+        let b = world.spawn();
+        assert_eq!(world.name(b), None);
+        assert!(!world.has_tag(b, "loot"));
+        world.set_name(b, "crate").unwrap();
+        assert_eq!(world.find_by_name("crate"), Some(b));
+    }
+
+    #[test]
+    fn tags() {
+        let mut world = World::new();
+        let (a, b, c) = (world.spawn(), world.spawn(), world.spawn());
+        for entity in [c, a] {
+            world.add_tag(entity, "enemy").unwrap();
+        }
+        world.add_tag(a, "boss").unwrap();
+        world.add_tag(a, "boss").unwrap();
+
+        assert_eq!(world.iter_with_tag("enemy").collect::<Vec<_>>(), vec![a, c]);
+        assert_eq!(world.tags(a).collect::<Vec<_>>(), vec!["boss", "enemy"]);
+        assert!(!world.has_tag(b, "enemy"));
+
+        assert!(world.remove_tag(a, "enemy"));
+        assert!(!world.remove_tag(a, "enemy"));
+        assert!(!world.remove_tag(b, "enemy"));
+        assert_eq!(world.iter_with_tag("enemy").collect::<Vec<_>>(), vec![c]);
+        assert_eq!(world.tags(a).collect::<Vec<_>>(), vec!["boss"]);
+    }
+
+    #[test]
+    fn dead_entities_cant_be_named_or_tagged() {
+        let mut world = World::new();
+        let a = world.spawn();
+        world.despawn(a).unwrap();
+        assert!(matches!(
+            world.set_name(a, "ghost"),
+            Err(EntityError::EntityNotFound)
+        ));
+        assert!(matches!(
+            world.add_tag(a, "ghost"),
+            Err(EntityError::EntityNotFound)
+        ));
+    }
+
+    #[test]
+    fn tag_index_drops_empty_entries() {
+        let mut tags = Tags::new();
+        let mut world = World::new();
+        let a = world.spawn();
+        tags.add(a, "one");
+        tags.add(a, "two");
+        tags.remove(a, "one");
+        tags.remove_entity(a);
+        assert!(tags.entities.is_empty());
+        assert!(tags.tags.is_empty());
+    }
+}
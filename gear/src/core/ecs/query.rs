@@ -0,0 +1,134 @@
+use std::cell::{Ref, RefMut};
+
+use super::{Component, EntityId, SparseSet, World};
+
+// A set of component borrows matched against entities: `&T`, `&mut T` or a
+// tuple of those, e.g. `world.query::<(&Transform, &mut Renderable)>()`.
+// Components matched through `&mut T` are flagged as changed.
+pub trait Query {
+    type Fetch<'w>;
+    type Item<'f>;
+
+    // Borrows the storages, `None` when one of them doesn't exist yet.
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+
+    // Candidate entities, the smallest storage of the query drives iteration.
+    fn entities<'a>(fetch: &'a Self::Fetch<'_>) -> &'a [EntityId];
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: EntityId) -> Option<Self::Item<'f>>;
+}
+
+impl<T: Component> Query for &T {
+    type Fetch<'w> = Ref<'w, SparseSet<T>>;
+    type Item<'f> = &'f T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn entities<'e>(fetch: &'e Self::Fetch<'_>) -> &'e [EntityId] {
+        fetch.entities()
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: EntityId) -> Option<Self::Item<'f>> {
+        fetch.get(entity)
+    }
+}
+
+impl<T: Component> Query for &mut T {
+    type Fetch<'w> = RefMut<'w, SparseSet<T>>;
+    type Item<'f> = &'f mut T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage_mut::<T>()
+    }
+
+    fn entities<'e>(fetch: &'e Self::Fetch<'_>) -> &'e [EntityId] {
+        fetch.entities()
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: EntityId) -> Option<Self::Item<'f>> {
+        fetch.get_mut(entity)
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Query),+> Query for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+            type Item<'f> = ($($name::Item<'f>,)+);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($($name::fetch(world)?,)+))
+            }
+
+            fn entities<'e>(fetch: &'e Self::Fetch<'_>) -> &'e [EntityId] {
+                let ($($name,)+) = fetch;
+                let mut smallest: Option<&'e [EntityId]> = None;
+                $(
+                    let entities = $name::entities($name);
+                    if smallest.map_or(true, |smallest| entities.len() < smallest.len()) {
+                        smallest = Some(entities);
+                    }
+                )+
+                smallest.unwrap_or(&[])
+            }
+
+            fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: EntityId) -> Option<Self::Item<'f>> {
+                let ($($name,)+) = fetch;
+                Some(($($name::get($name, entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+
+// The storages borrowed by a query for as long as it is alive.
+pub struct QueryBorrow<'w, Q: Query> {
+    fetch: Option<Q::Fetch<'w>>,
+}
+
+impl<'w, Q: Query> QueryBorrow<'w, Q> {
+    pub(super) fn new(world: &'w World) -> Self {
+        Self {
+            fetch: Q::fetch(world),
+        }
+    }
+
+    // Entities that have every component of the query.
+    pub fn entities(&mut self) -> Vec<EntityId> {
+        let Some(fetch) = &mut self.fetch else {
+            return Vec::new();
+        };
+
+        let candidates = Q::entities(fetch).to_vec();
+        candidates
+            .into_iter()
+            .filter(|entity| Q::get(fetch, *entity).is_some())
+            .collect()
+    }
+
+    pub fn get(&mut self, entity: EntityId) -> Option<Q::Item<'_>> {
+        Q::get(self.fetch.as_mut()?, entity)
+    }
+
+    pub fn for_each(&mut self, mut f: impl FnMut(EntityId, Q::Item<'_>)) {
+        let Some(fetch) = &mut self.fetch else {
+            return;
+        };
+
+        let candidates = Q::entities(fetch).to_vec();
+        for entity in candidates {
+            if let Some(item) = Q::get(fetch, entity) {
+                f(entity, item);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::core::application::context::Context;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Stage {
+    // Game logic
+    Update,
+    // Logic that depends on the results of `Update`, e.g. cameras following entities
+    LateUpdate,
+    // Last chance to prepare components for the renderer, world transforms
+    // are up to date and changes to `Transform` show up on the next frame
+    RenderExtract,
+}
+
+pub trait System {
+    fn run(&mut self, ctx: &mut Context);
+}
+
+impl<F: FnMut(&mut Context)> System for F {
+    fn run(&mut self, ctx: &mut Context) {
+        self(ctx)
+    }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    update: Vec<Box<dyn System>>,
+    late_update: Vec<Box<dyn System>>,
+    render_extract: Vec<Box<dyn System>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Systems of a stage run in the order they were added.
+    pub fn add_system(&mut self, stage: Stage, system: impl System + 'static) {
+        self.stage_mut(stage).push(Box::new(system));
+    }
+
+    pub fn run(&mut self, stage: Stage, ctx: &mut Context) {
+        for system in self.stage_mut(stage).iter_mut() {
+            system.run(ctx);
+        }
+    }
+
+    pub fn append(&mut self, other: &mut Schedule) {
+        self.update.append(&mut other.update);
+        self.late_update.append(&mut other.late_update);
+        self.render_extract.append(&mut other.render_extract);
+    }
+
+    fn stage_mut(&mut self, stage: Stage) -> &mut Vec<Box<dyn System>> {
+        match stage {
+            Stage::Update => &mut self.update,
+            Stage::LateUpdate => &mut self.late_update,
+            Stage::RenderExtract => &mut self.render_extract,
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::any::Any;
+
+use super::EntityId;
+
+// Dense component storage: components are packed in `data`, `sparse` maps an
+// entity index to its position in `dense`/`data`. Components are flagged as
+// changed when inserted or borrowed mutably, until `clear_changed`.
+pub struct SparseSet<T> {
+    sparse: Vec<Option<u32>>,
+    dense: Vec<EntityId>,
+    data: Vec<T>,
+    changed: Vec<bool>,
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            data: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    fn position(&self, entity: EntityId) -> Option<usize> {
+        let position = (*self.sparse.get(entity.index() as usize)?)? as usize;
+        if self.dense[position] == entity {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, entity: EntityId, component: T) -> Option<T> {
+        if let Some(position) = self.position(entity) {
+            self.changed[position] = true;
+            return Some(std::mem::replace(&mut self.data[position], component));
+        }
+
+        let index = entity.index() as usize;
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, None);
+        }
+
+        // a stale entity may still occupy the slot
+        if let Some(position) = self.sparse[index] {
+            self.swap_remove(position as usize);
+        }
+
+        self.sparse[index] = Some(self.dense.len() as u32);
+        self.dense.push(entity);
+        self.data.push(component);
+        self.changed.push(true);
+        None
+    }
+
+    pub fn remove(&mut self, entity: EntityId) -> Option<T> {
+        let position = self.position(entity)?;
+        Some(self.swap_remove(position))
+    }
+
+    fn swap_remove(&mut self, position: usize) -> T {
+        let removed = self.dense.swap_remove(position);
+        self.sparse[removed.index() as usize] = None;
+        if let Some(moved) = self.dense.get(position) {
+            self.sparse[moved.index() as usize] = Some(position as u32);
+        }
+        self.changed.swap_remove(position);
+        self.data.swap_remove(position)
+    }
+
+    pub fn get(&self, entity: EntityId) -> Option<&T> {
+        self.position(entity).map(|position| &self.data[position])
+    }
+
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<&mut T> {
+        let position = self.position(entity)?;
+        self.changed[position] = true;
+        Some(&mut self.data[position])
+    }
+
+    pub fn is_changed(&self, entity: EntityId) -> bool {
+        self.position(entity)
+            .is_some_and(|position| self.changed[position])
+    }
+
+    pub fn mark_changed(&mut self, entity: EntityId) {
+        if let Some(position) = self.position(entity) {
+            self.changed[position] = true;
+        }
+    }
+
+    pub fn clear_changed(&mut self) {
+        self.changed.iter_mut().for_each(|changed| *changed = false);
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.position(entity).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn entities(&self) -> &[EntityId] {
+        &self.dense
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.dense.iter().copied().zip(self.data.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        self.changed.iter_mut().for_each(|changed| *changed = true);
+        self.dense.iter().copied().zip(self.data.iter_mut())
+    }
+}
+
+// Type-erased view of a storage, so the world can despawn entities without
+// knowing every component type.
+pub(super) trait AnyStorage {
+    fn remove_entity(&mut self, entity: EntityId);
+    fn contains_entity(&self, entity: EntityId) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for SparseSet<T> {
+    fn remove_entity(&mut self, entity: EntityId) {
+        self.remove(entity);
+    }
+
+    fn contains_entity(&self, entity: EntityId) -> bool {
+        self.contains(entity)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(index: u32, generation: u32) -> EntityId {
+        EntityId::from_raw(index, generation)
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut set = SparseSet::new();
+        assert_eq!(set.insert(id(3, 0), "a"), None);
+        assert_eq!(set.insert(id(0, 0), "b"), None);
+        assert_eq!(set.insert(id(3, 0), "c"), Some("a"));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(id(3, 0)), Some(&"c"));
+        assert_eq!(set.get(id(1, 0)), None);
+
+        assert_eq!(set.remove(id(3, 0)), Some("c"));
+        assert_eq!(set.remove(id(3, 0)), None);
+        assert!(!set.contains(id(3, 0)));
+        assert_eq!(set.entities(), &[id(0, 0)]);
+    }
+
+    #[test]
+    fn swap_remove_keeps_the_moved_entry() {
+        let mut set = SparseSet::new();
+        for index in 0..4 {
+            set.insert(id(index, 0), index);
+        }
+        set.remove(id(1, 0));
+        for index in [0, 2, 3] {
+            assert_eq!(set.get(id(index, 0)), Some(&index));
+        }
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn stale_generation() {
+        let mut set = SparseSet::new();
+        set.insert(id(2, 0), "old");
+        assert_eq!(set.get(id(2, 1)), None);
+        assert_eq!(set.remove(id(2, 1)), None);
+
+        // the new entity on the same index replaces the stale one
+        assert_eq!(set.insert(id(2, 1), "new"), None);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(id(2, 0)), None);
+        assert_eq!(set.get(id(2, 1)), Some(&"new"));
+    }
+
+    #[test]
+    fn changed_flags() {
+        let mut set = SparseSet::new();
+        set.insert(id(0, 0), 1);
+        set.insert(id(1, 0), 2);
+        assert!(set.is_changed(id(0, 0)));
+
+        set.clear_changed();
+        assert!(!set.is_changed(id(0, 0)) && !set.is_changed(id(1, 0)));
+
+        set.get(id(0, 0));
+        assert!(!set.is_changed(id(0, 0)));
+        *set.get_mut(id(0, 0)).unwrap() += 1;
+        assert!(set.is_changed(id(0, 0)));
+        assert!(!set.is_changed(id(1, 0)));
+
+        set.clear_changed();
+        set.mark_changed(id(1, 0));
+        assert!(set.is_changed(id(1, 0)));
+
+        set.clear_changed();
+        set.iter_mut().for_each(|_| {});
+        assert!(set.is_changed(id(0, 0)) && set.is_changed(id(1, 0)));
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+use crate::core::entity::EntityError;
+
+use super::{
+    entities::Entities,
+    names::{Names, Tags},
+    query::{Query, QueryBorrow},
+    storage::{AnyStorage, SparseSet},
+    EntityId,
+};
+
+// Any `'static` type can be used as a component, game code doesn't need to
+// register anything.
+pub trait Component: 'static {}
+
+impl<T: 'static> Component for T {}
+
+// Entities, their components (one `SparseSet` per component type) and global
+// resources. Storages live behind a `RefCell` so that queries over different
+// component types can be borrowed at the same time from a shared `&World`;
+// borrowing the same storage mutably twice panics.
+pub struct World {
+    entities: Entities,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    names: Names,
+    tags: Tags,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            entities: Entities::new(),
+            storages: HashMap::new(),
+            resources: HashMap::new(),
+            names: Names::new(),
+            tags: Tags::new(),
+        }
+    }
+
+    pub fn spawn(&mut self) -> EntityId {
+        self.entities.spawn()
+    }
+
+    pub fn despawn(&mut self, entity: EntityId) -> Result<(), EntityError> {
+        if !self.entities.despawn(entity) {
+            return Err(EntityError::EntityNotFound);
+        }
+
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+        self.names.remove(entity);
+        self.tags.remove_entity(entity);
+        Ok(())
+    }
+
+    pub fn is_alive(&self, entity: EntityId) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.len() == 0
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.iter()
+    }
+
+    // Names are unique, naming an entity with a name already in use fails.
+    pub fn set_name(&mut self, entity: EntityId, name: &str) -> Result<(), EntityError> {
+        if !self.is_alive(entity) {
+            return Err(EntityError::EntityNotFound);
+        }
+        if !self.names.set(entity, name) {
+            return Err(EntityError::NameTaken(name.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn clear_name(&mut self, entity: EntityId) -> Option<String> {
+        self.names.remove(entity)
+    }
+
+    pub fn name(&self, entity: EntityId) -> Option<&str> {
+        self.names.name(entity)
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<EntityId> {
+        self.names.find(name)
+    }
+
+    pub fn add_tag(&mut self, entity: EntityId, tag: &str) -> Result<(), EntityError> {
+        if !self.is_alive(entity) {
+            return Err(EntityError::EntityNotFound);
+        }
+        self.tags.add(entity, tag);
+        Ok(())
+    }
+
+    pub fn remove_tag(&mut self, entity: EntityId, tag: &str) -> bool {
+        self.tags.remove(entity, tag)
+    }
+
+    pub fn has_tag(&self, entity: EntityId, tag: &str) -> bool {
+        self.tags.has(entity, tag)
+    }
+
+    pub fn tags(&self, entity: EntityId) -> impl Iterator<Item = &str> + '_ {
+        self.tags.tags(entity)
+    }
+
+    pub fn iter_with_tag(&self, tag: &str) -> impl Iterator<Item = EntityId> + '_ {
+        self.tags.entities(tag)
+    }
+
+    // Adds a component to the entity, replacing the previous one of that type.
+    pub fn insert<T: Component>(
+        &mut self,
+        entity: EntityId,
+        component: T,
+    ) -> Result<(), EntityError> {
+        if !self.is_alive(entity) {
+            return Err(EntityError::EntityNotFound);
+        }
+
+        let storage = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(SparseSet::<T>::new())));
+        downcast_mut::<T>(storage.get_mut().as_mut()).insert(entity, component);
+        Ok(())
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: EntityId) -> Option<T> {
+        let storage = self.storages.get_mut(&TypeId::of::<T>())?;
+        downcast_mut::<T>(storage.get_mut().as_mut()).remove(entity)
+    }
+
+    pub fn has<T: Component>(&self, entity: EntityId) -> bool {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .is_some_and(|storage| storage.borrow().contains_entity(entity))
+    }
+
+    pub fn get<T: Component>(&self, entity: EntityId) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>()?, |storage| storage.get(entity)).ok()
+    }
+
+    pub fn get_mut<T: Component>(&self, entity: EntityId) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.storage_mut::<T>()?, |storage| storage.get_mut(entity)).ok()
+    }
+
+    pub fn storage<T: Component>(&self) -> Option<Ref<'_, SparseSet<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        let storage = storage.try_borrow().unwrap_or_else(|_| {
+            panic!(
+                "Storage for '{}' is already mutably borrowed",
+                type_name::<T>()
+            )
+        });
+        Some(Ref::map(storage, |storage| {
+            downcast_ref::<T>(storage.as_ref())
+        }))
+    }
+
+    pub fn storage_mut<T: Component>(&self) -> Option<RefMut<'_, SparseSet<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        let storage = storage
+            .try_borrow_mut()
+            .unwrap_or_else(|_| panic!("Storage for '{}' is already borrowed", type_name::<T>()));
+        Some(RefMut::map(storage, |storage| {
+            downcast_mut::<T>(storage.as_mut())
+        }))
+    }
+
+    pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
+        QueryBorrow::new(self)
+    }
+
+    pub fn insert_resource<R: Component>(&mut self, resource: R) {
+        self.resources
+            .insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)));
+    }
+
+    pub fn remove_resource<R: Component>(&mut self) -> Option<R> {
+        let resource = self.resources.remove(&TypeId::of::<R>())?;
+        resource
+            .into_inner()
+            .downcast::<R>()
+            .ok()
+            .map(|resource| *resource)
+    }
+
+    pub fn resource<R: Component>(&self) -> Option<Ref<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?.borrow();
+        Ref::filter_map(resource, |resource| resource.downcast_ref::<R>()).ok()
+    }
+
+    pub fn resource_mut<R: Component>(&self) -> Option<RefMut<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?.borrow_mut();
+        RefMut::filter_map(resource, |resource| resource.downcast_mut::<R>()).ok()
+    }
+}
+
+fn downcast_ref<T: Component>(storage: &dyn AnyStorage) -> &SparseSet<T> {
+    // storages are keyed by the TypeId of their component
+    storage.as_any().downcast_ref().unwrap()
+}
+
+fn downcast_mut<T: Component>(storage: &mut dyn AnyStorage) -> &mut SparseSet<T> {
+    storage.as_any_mut().downcast_mut().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32);
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+    #[derive(Debug, PartialEq)]
+    struct Frozen;
+
+    fn world() -> (World, [EntityId; 3]) {
+        let mut world = World::new();
+        let moving = world.spawn();
+        let still = world.spawn();
+        let frozen = world.spawn();
+        for (entity, x) in [(moving, 0.0), (still, 5.0), (frozen, 9.0)] {
+            world.insert(entity, Position(x)).unwrap();
+        }
+        world.insert(moving, Velocity(2.0)).unwrap();
+        world.insert(frozen, Velocity(1.0)).unwrap();
+        world.insert(frozen, Frozen).unwrap();
+        (world, [moving, still, frozen])
+    }
+
+    #[test]
+    fn components() {
+        let (mut world, [moving, still, _]) = world();
+        assert!(world.has::<Velocity>(moving));
+        assert!(!world.has::<Velocity>(still));
+        assert_eq!(*world.get::<Position>(still).unwrap(), Position(5.0));
+
+        world.insert(still, Position(6.0)).unwrap();
+        assert_eq!(*world.get::<Position>(still).unwrap(), Position(6.0));
+        assert_eq!(world.remove::<Position>(still), Some(Position(6.0)));
+        assert!(world.get::<Position>(still).is_none());
+        assert!(world.get::<String>(still).is_none());
+    }
+
+    #[test]
+    fn despawn_removes_components() {
+        let (mut world, [moving, ..]) = world();
+        world.despawn(moving).unwrap();
+        assert!(world.despawn(moving).is_err());
+        assert!(world.insert(moving, Frozen).is_err());
+
+        // the reused index doesn't see the old components
+        let reused = world.spawn();
+        assert_eq!(reused.index(), moving.index());
+        assert!(!world.has::<Position>(reused));
+        assert!(world.get::<Velocity>(moving).is_none());
+        assert_eq!(world.len(), 3);
+    }
+
+    #[test]
+    fn tuple_query() {
+        let (world, [moving, _, frozen]) = world();
+        let mut entities = world.query::<(&mut Position, &Velocity)>().entities();
+        entities.sort();
+        assert_eq!(entities, vec![moving, frozen]);
+
+        world
+            .query::<(&mut Position, &Velocity)>()
+            .for_each(|_, (position, velocity)| position.0 += velocity.0);
+        assert_eq!(*world.get::<Position>(moving).unwrap(), Position(2.0));
+        assert_eq!(*world.get::<Position>(frozen).unwrap(), Position(10.0));
+
+        let mut matched = Vec::new();
+        world
+            .query::<(&Position, &Velocity, &Frozen)>()
+            .for_each(|entity, _| matched.push(entity));
+        assert_eq!(matched, vec![frozen]);
+
+        // a storage that was never created matches nothing
+        assert!(world.query::<(&Position, &String)>().entities().is_empty());
+    }
+
+    #[test]
+    fn query_marks_changed() {
+        let (world, [moving, still, _]) = world();
+        world.storage_mut::<Position>().unwrap().clear_changed();
+        world
+            .query::<(&mut Position, &Velocity)>()
+            .for_each(|_, _| {});
+        let positions = world.storage::<Position>().unwrap();
+        assert!(positions.is_changed(moving));
+        assert!(!positions.is_changed(still));
+    }
+
+    #[test]
+    #[should_panic(expected = "already mutably borrowed")]
+    fn conflicting_borrow_panics() {
+        let (world, _) = world();
+        let _positions = world.storage_mut::<Position>();
+        world.query::<&Position>().entities();
+    }
+
+    #[test]
+    fn resources() {
+        let mut world = World::new();
+        assert!(world.resource::<u32>().is_none());
+        world.insert_resource(3u32);
+        *world.resource_mut::<u32>().unwrap() += 1;
+        assert_eq!(*world.resource::<u32>().unwrap(), 4);
+        assert_eq!(world.remove_resource::<u32>(), Some(4));
+        assert!(world.resource::<u32>().is_none());
+    }
+}
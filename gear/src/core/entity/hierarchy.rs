@@ -0,0 +1,367 @@
+use nalgebra::{Matrix4, Vector3};
+
+use crate::core::ecs::{EntityId, World};
+
+use super::{EntityError, Transform};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Parent(pub EntityId);
+
+#[derive(Debug, Clone, Default)]
+pub struct Children(pub Vec<EntityId>);
+
+// World space matrix of an entity, derived from its `Transform` and the ones
+// of its ancestors by `propagate_transforms`.
+#[derive(Debug, Copy, Clone)]
+pub struct GlobalTransform(pub Matrix4<f32>);
+
+impl GlobalTransform {
+    pub fn matrix(&self) -> &Matrix4<f32> {
+        &self.0
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        Vector3::new(self.0[(0, 3)], self.0[(1, 3)], self.0[(2, 3)])
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self(Matrix4::identity())
+    }
+}
+
+pub struct Ancestors<'w> {
+    world: &'w World,
+    current: EntityId,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parent = self.world.parent(self.current)?;
+        self.current = parent;
+        Some(parent)
+    }
+}
+
+impl World {
+    pub fn parent(&self, entity: EntityId) -> Option<EntityId> {
+        let parent = self.get::<Parent>(entity)?.0;
+        self.is_alive(parent).then_some(parent)
+    }
+
+    pub fn children(&self, entity: EntityId) -> Vec<EntityId> {
+        self.get::<Children>(entity)
+            .map(|children| children.0.clone())
+            .unwrap_or_default()
+    }
+
+    // Parent, grand parent, ... up to the root.
+    pub fn ancestors(&self, entity: EntityId) -> Ancestors<'_> {
+        Ancestors {
+            world: self,
+            current: entity,
+        }
+    }
+
+    // Every entity below `entity`, depth first.
+    pub fn descendants(&self, entity: EntityId) -> Vec<EntityId> {
+        let mut descendants = Vec::new();
+        let mut stack = self.children(entity);
+        stack.reverse();
+
+        while let Some(entity) = stack.pop() {
+            descendants.push(entity);
+            stack.extend(self.children(entity).into_iter().rev());
+        }
+        descendants
+    }
+
+    // World matrix computed from the local transforms, ignoring any
+    // `GlobalTransform` that may not have been propagated yet.
+    pub fn world_matrix(&self, entity: EntityId) -> Matrix4<f32> {
+        std::iter::once(entity)
+            .chain(self.ancestors(entity))
+            .filter_map(|entity| self.get::<Transform>(entity).map(|t| t.get_model_matrix()))
+            .fold(Matrix4::identity(), |child, parent| parent * child)
+    }
+
+    // Despawns `entity` and everything below it, and removes it from the
+    // children of its parent.
+    pub fn despawn_recursive(&mut self, entity: EntityId) -> Result<(), EntityError> {
+        if !self.is_alive(entity) {
+            return Err(EntityError::EntityNotFound);
+        }
+
+        self.detach(entity);
+        for descendant in self.descendants(entity) {
+            self.despawn(descendant)?;
+        }
+        self.despawn(entity)
+    }
+
+    // Re-parents `child`, keeping its world transform: its local transform is
+    // rewritten relative to the new parent.
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) -> Result<(), EntityError> {
+        if !self.is_alive(child) || !self.is_alive(parent) {
+            return Err(EntityError::EntityNotFound);
+        }
+        if child == parent || self.ancestors(parent).any(|ancestor| ancestor == child) {
+            return Err(EntityError::InvalidParent);
+        }
+
+        let world = self.world_matrix(child);
+        let parent_world = self.world_matrix(parent);
+        let local = parent_world
+            .try_inverse()
+            .map_or(world, |inverse| inverse * world);
+
+        self.attach(child, parent)?;
+        self.insert(child, Transform::from_matrix(&local))
+    }
+
+    // Makes `child` a root again, keeping its world transform.
+    pub fn remove_parent(&mut self, child: EntityId) -> Result<(), EntityError> {
+        if !self.is_alive(child) {
+            return Err(EntityError::EntityNotFound);
+        }
+
+        let world = self.world_matrix(child);
+        self.detach(child);
+        self.insert(child, Transform::from_matrix(&world))
+    }
+
+    // Links `child` under `parent` without touching its local transform.
+    pub(super) fn attach(&mut self, child: EntityId, parent: EntityId) -> Result<(), EntityError> {
+        self.detach(child);
+
+        let mut children = self.remove::<Children>(parent).unwrap_or_default();
+        children.0.push(child);
+        self.insert(parent, children)?;
+        self.insert(child, Parent(parent))?;
+
+        if let Some(mut transforms) = self.storage_mut::<Transform>() {
+            transforms.mark_changed(child);
+        }
+        Ok(())
+    }
+
+    fn detach(&mut self, child: EntityId) {
+        let Some(Parent(parent)) = self.remove::<Parent>(child) else {
+            return;
+        };
+
+        if let Some(mut children) = self.get_mut::<Children>(parent) {
+            children.0.retain(|entity| *entity != child);
+        }
+        if let Some(mut transforms) = self.storage_mut::<Transform>() {
+            transforms.mark_changed(child);
+        }
+    }
+
+    // Updates the `GlobalTransform` of every entity whose transform, or one of
+    // its ancestors' transforms, changed since the last propagation.
+    pub fn propagate_transforms(&mut self) {
+        let missing: Vec<EntityId> = self
+            .query::<&Transform>()
+            .entities()
+            .into_iter()
+            .filter(|entity| !self.has::<GlobalTransform>(*entity))
+            .collect();
+        for entity in missing {
+            let _ = self.insert(entity, GlobalTransform::default());
+            if let Some(mut transforms) = self.storage_mut::<Transform>() {
+                transforms.mark_changed(entity);
+            }
+        }
+
+        let Some(transforms) = self.storage::<Transform>() else {
+            return;
+        };
+        let Some(mut globals) = self.storage_mut::<GlobalTransform>() else {
+            return;
+        };
+        let children = self.storage::<Children>();
+
+        let mut stack: Vec<(EntityId, Matrix4<f32>, bool)> = transforms
+            .entities()
+            .iter()
+            .filter(|entity| self.parent(**entity).is_none())
+            .map(|entity| (*entity, Matrix4::identity(), false))
+            .collect();
+
+        while let Some((entity, parent, parent_changed)) = stack.pop() {
+            let Some(transform) = transforms.get(entity) else {
+                continue;
+            };
+
+            let changed = parent_changed || transforms.is_changed(entity);
+            let global = if changed {
+                let Some(global) = globals.get_mut(entity) else {
+                    continue;
+                };
+                global.0 = parent * transform.get_model_matrix();
+                global.0
+            } else {
+                let Some(global) = globals.get(entity) else {
+                    continue;
+                };
+                global.0
+            };
+
+            if let Some(children) = children.as_ref().and_then(|c| c.get(entity)) {
+                stack.extend(children.0.iter().map(|child| (*child, global, changed)));
+            }
+        }
+
+        drop(transforms);
+        if let Some(mut transforms) = self.storage_mut::<Transform>() {
+            transforms.clear_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::UnitQuaternion;
+
+    use super::*;
+
+    fn spawn(world: &mut World, transform: Transform) -> EntityId {
+        let entity = world.spawn();
+        world.insert(entity, transform).unwrap();
+        entity
+    }
+
+    fn approx(a: &Matrix4<f32>, b: &Matrix4<f32>) -> bool {
+        (a - b).abs().max() < 1e-4
+    }
+
+    fn global(world: &World, entity: EntityId) -> Matrix4<f32> {
+        world.get::<GlobalTransform>(entity).unwrap().0
+    }
+
+    #[test]
+    fn set_parent_keeps_the_world_transform() {
+        let mut world = World::new();
+        let parent = spawn(
+            &mut world,
+            Transform::new()
+                .with_position(Vector3::new(1.0, 2.0, 3.0))
+                .with_rotation(UnitQuaternion::from_euler_angles(0.3, 0.5, 0.0))
+                .with_scale(Vector3::new(2.0, 2.0, 2.0)),
+        );
+        let child = spawn(
+            &mut world,
+            Transform::new()
+                .with_position(Vector3::new(-4.0, 0.5, 1.0))
+                .with_rotation(UnitQuaternion::from_euler_angles(0.0, 1.0, 0.2)),
+        );
+
+        let before = world.world_matrix(child);
+        world.set_parent(child, parent).unwrap();
+        assert_eq!(world.parent(child), Some(parent));
+        assert_eq!(world.children(parent), vec![child]);
+        assert!(approx(&world.world_matrix(child), &before));
+
+        world.remove_parent(child).unwrap();
+        assert_eq!(world.parent(child), None);
+        assert!(world.children(parent).is_empty());
+        assert!(approx(&world.world_matrix(child), &before));
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut world = World::new();
+        let root = spawn(&mut world, Transform::new());
+        let middle = spawn(&mut world, Transform::new());
+        let leaf = spawn(&mut world, Transform::new());
+        world.set_parent(middle, root).unwrap();
+        world.set_parent(leaf, middle).unwrap();
+
+        assert!(matches!(
+            world.set_parent(root, leaf),
+            Err(EntityError::InvalidParent)
+        ));
+        assert!(matches!(
+            world.set_parent(root, root),
+            Err(EntityError::InvalidParent)
+        ));
+        assert_eq!(world.parent(root), None);
+        assert_eq!(
+            world.ancestors(leaf).collect::<Vec<_>>(),
+            vec![middle, root]
+        );
+        assert_eq!(world.descendants(root), vec![middle, leaf]);
+    }
+
+    #[test]
+    fn despawn_recursive() {
+        let mut world = World::new();
+        let root = spawn(&mut world, Transform::new());
+        let branch = spawn(&mut world, Transform::new());
+        let leaves = [
+            spawn(&mut world, Transform::new()),
+            spawn(&mut world, Transform::new()),
+        ];
+        let sibling = spawn(&mut world, Transform::new());
+        world.set_parent(branch, root).unwrap();
+        world.set_parent(sibling, root).unwrap();
+        for leaf in leaves {
+            world.set_parent(leaf, branch).unwrap();
+        }
+
+        world.despawn_recursive(branch).unwrap();
+        assert!(!world.is_alive(branch));
+        assert!(leaves.iter().all(|leaf| !world.is_alive(*leaf)));
+        assert!(world.is_alive(sibling));
+        assert_eq!(world.children(root), vec![sibling]);
+        assert!(world.despawn_recursive(branch).is_err());
+    }
+
+    #[test]
+    fn propagation() {
+        let mut world = World::new();
+        let parent = spawn(
+            &mut world,
+            Transform::new().with_position(Vector3::new(1.0, 0.0, 0.0)),
+        );
+        let child = spawn(
+            &mut world,
+            Transform::new().with_position(Vector3::new(0.0, 2.0, 0.0)),
+        );
+        world.attach(child, parent).unwrap();
+        world.propagate_transforms();
+
+        assert_eq!(
+            world.get::<GlobalTransform>(child).unwrap().position(),
+            Vector3::new(1.0, 2.0, 0.0)
+        );
+        assert!(!world.storage::<Transform>().unwrap().is_changed(parent));
+
+        // unchanged entities keep their global transform as is
+        world.get_mut::<GlobalTransform>(child).unwrap().0 = Matrix4::zeros();
+        world.propagate_transforms();
+        assert_eq!(global(&world, child), Matrix4::zeros());
+
+        // a parent change reaches the children
+        world.get_mut::<Transform>(parent).unwrap().position = Vector3::new(5.0, 0.0, 0.0);
+        world.propagate_transforms();
+        assert_eq!(
+            world.get::<GlobalTransform>(child).unwrap().position(),
+            Vector3::new(5.0, 2.0, 0.0)
+        );
+        assert!(approx(&global(&world, child), &world.world_matrix(child)));
+
+        // detaching marks the child so it is recomputed as a root
+        world.remove_parent(child).unwrap();
+        world.propagate_transforms();
+        assert!(approx(&global(&world, child), &world.world_matrix(child)));
+        assert_eq!(
+            world.get::<GlobalTransform>(child).unwrap().position(),
+            Vector3::new(5.0, 2.0, 0.0)
+        );
+    }
+}
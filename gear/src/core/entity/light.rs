@@ -1,53 +1,71 @@
-use nalgebra::Vector3;
+use nalgebra::{UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
 
-use crate::core::assets::AssetsManager;
+use crate::core::{
+    assets::AssetsManager,
+    ecs::{EntityId, World},
+};
 
-use super::{Entity, EntityBuffer, EntityBuilder, EntityError, Material, Transform};
+use super::{EntityBuilder, EntityError, Material, Transform};
 
+// Point light component, positioned by the entity `Transform`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Light {
-    pub entity: Entity,
     pub ambient: f32,
     pub diffuse: f32,
     pub specular: f32,
+    pub intensity: f32,
     pub color: Vector3<f32>,
 }
 
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            ambient: 0.1,
+            diffuse: 0.8,
+            specular: 1.0,
+            intensity: 1.0,
+            color: Vector3::from([1.0, 1.0, 1.0]),
+        }
+    }
+}
+
 pub struct LightBuilder {
     entity: EntityBuilder,
-    ambient: f32,
-    diffuse: f32,
-    specular: f32,
-    color: Vector3<f32>,
+    light: Light,
 }
 
 impl LightBuilder {
     pub fn new() -> Self {
         Self {
             entity: EntityBuilder::new(),
-            ambient: 0.1,
-            diffuse: 0.8,
-            specular: 1.0,
-            color: Vector3::from([1.0, 1.0, 1.0]),
+            light: Light::default(),
         }
     }
 
     pub fn with_ambient(mut self, ambient: f32) -> Self {
-        self.ambient = ambient;
+        self.light.ambient = ambient;
         self
     }
 
     pub fn with_diffuse(mut self, diffuse: f32) -> Self {
-        self.diffuse = diffuse;
+        self.light.diffuse = diffuse;
         self
     }
 
     pub fn with_specular(mut self, specular: f32) -> Self {
-        self.specular = specular;
+        self.light.specular = specular;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.light.intensity = intensity;
         self
     }
 
     pub fn with_color(mut self, color: Vector3<f32>) -> Self {
-        self.color = color;
+        self.light.color = color;
         self
     }
 
@@ -66,6 +84,21 @@ impl LightBuilder {
         self
     }
 
+    pub fn with_parent(mut self, parent: EntityId) -> Self {
+        self.entity = self.entity.with_parent(parent);
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.entity = self.entity.with_name(name);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.entity = self.entity.with_tag(tag);
+        self
+    }
+
     pub fn with_transform(mut self, transform: Transform) -> Self {
         self.entity = self.entity.with_transform(transform);
         self
@@ -76,7 +109,7 @@ impl LightBuilder {
         self
     }
 
-    pub fn with_rotation(mut self, rotation: Vector3<f32>) -> Self {
+    pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
         self.entity = self.entity.with_rotation(rotation);
         self
     }
@@ -86,18 +119,7 @@ impl LightBuilder {
         self
     }
 
-    pub fn build(
-        self,
-        assets: &AssetsManager,
-        entities: &mut EntityBuffer,
-    ) -> Result<Light, EntityError> {
-        let light = Light {
-            entity: self.entity.build(assets, entities)?,
-            ambient: self.ambient,
-            diffuse: self.diffuse,
-            specular: self.specular,
-            color: self.color,
-        };
-        Ok(light)
+    pub fn build(self, assets: &AssetsManager, world: &mut World) -> Result<EntityId, EntityError> {
+        self.entity.with_component(self.light).build(assets, world)
     }
 }
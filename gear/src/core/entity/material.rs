@@ -0,0 +1,176 @@
+use nalgebra::{Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+// A material input is either a constant value or the name of a texture
+// registered in the `AssetsManager`. Texture channels follow the glTF
+// conventions: occlusion is read from R, roughness from G and metallic from B,
+// so a single packed "ORM" texture can feed all three inputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MaterialInput<T> {
+    Constant(T),
+    Texture(String),
+}
+
+impl<T> MaterialInput<T> {
+    pub fn texture(&self) -> Option<&str> {
+        match self {
+            MaterialInput::Constant(_) => None,
+            MaterialInput::Texture(name) => Some(name.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    pub base_color: MaterialInput<Vector4<f32>>,
+    pub metallic: MaterialInput<f32>,
+    pub roughness: MaterialInput<f32>,
+    pub ambient_occlusion: MaterialInput<f32>,
+    pub emissive: MaterialInput<Vector3<f32>>,
+}
+
+impl Material {
+    pub fn new(color: [f32; 4]) -> Self {
+        Self {
+            base_color: MaterialInput::Constant(Vector4::from(color)),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_base_color(mut self, color: [f32; 4]) -> Self {
+        self.base_color = MaterialInput::Constant(Vector4::from(color));
+        self
+    }
+
+    pub fn with_base_color_texture(mut self, texture: &str) -> Self {
+        self.base_color = MaterialInput::Texture(texture.to_owned());
+        self
+    }
+
+    pub fn with_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = MaterialInput::Constant(metallic);
+        self
+    }
+
+    pub fn with_metallic_texture(mut self, texture: &str) -> Self {
+        self.metallic = MaterialInput::Texture(texture.to_owned());
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = MaterialInput::Constant(roughness);
+        self
+    }
+
+    pub fn with_roughness_texture(mut self, texture: &str) -> Self {
+        self.roughness = MaterialInput::Texture(texture.to_owned());
+        self
+    }
+
+    pub fn with_ambient_occlusion(mut self, ao: f32) -> Self {
+        self.ambient_occlusion = MaterialInput::Constant(ao);
+        self
+    }
+
+    pub fn with_ambient_occlusion_texture(mut self, texture: &str) -> Self {
+        self.ambient_occlusion = MaterialInput::Texture(texture.to_owned());
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+        self.emissive = MaterialInput::Constant(Vector3::from(emissive));
+        self
+    }
+
+    pub fn with_emissive_texture(mut self, texture: &str) -> Self {
+        self.emissive = MaterialInput::Texture(texture.to_owned());
+        self
+    }
+
+    // Maps a Wavefront MTL material onto the metallic-roughness model.
+    // MTL has no notion of metalness, so it is left at zero and the Phong
+    // exponent is converted to an approximate roughness.
+    #[cfg(feature = "obj")]
+    pub fn from_obj(material: &tobj::Material) -> Self {
+        let base_color = if material.diffuse_texture.is_empty() {
+            let [r, g, b] = material.diffuse;
+            MaterialInput::Constant(Vector4::new(r, g, b, material.dissolve))
+        } else {
+            MaterialInput::Texture(material.diffuse_texture.clone())
+        };
+
+        let roughness = (2.0 / (material.shininess + 2.0)).sqrt().clamp(0.0, 1.0);
+
+        Self {
+            base_color,
+            roughness: MaterialInput::Constant(roughness),
+            ..Self::default()
+        }
+    }
+
+    // Maps a glTF material onto Gear's material. glTF already uses the
+    // metallic-roughness model; textures are referenced by their image name,
+    // or by "<index>" when the image is unnamed.
+    #[cfg(feature = "gltf")]
+    pub fn from_gltf(material: &gltf::Material) -> Self {
+        fn texture_name(texture: gltf::texture::Texture) -> String {
+            let image = texture.source();
+            image
+                .name()
+                .map(|name| name.to_owned())
+                .unwrap_or_else(|| image.index().to_string())
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+
+        let base_color = match pbr.base_color_texture() {
+            Some(info) => MaterialInput::Texture(texture_name(info.texture())),
+            None => MaterialInput::Constant(Vector4::from(pbr.base_color_factor())),
+        };
+
+        let (metallic, roughness) = match pbr.metallic_roughness_texture() {
+            Some(info) => {
+                let name = texture_name(info.texture());
+                (
+                    MaterialInput::Texture(name.clone()),
+                    MaterialInput::Texture(name),
+                )
+            }
+            None => (
+                MaterialInput::Constant(pbr.metallic_factor()),
+                MaterialInput::Constant(pbr.roughness_factor()),
+            ),
+        };
+
+        let ambient_occlusion = match material.occlusion_texture() {
+            Some(info) => MaterialInput::Texture(texture_name(info.texture())),
+            None => MaterialInput::Constant(1.0),
+        };
+
+        let emissive = match material.emissive_texture() {
+            Some(info) => MaterialInput::Texture(texture_name(info.texture())),
+            None => MaterialInput::Constant(Vector3::from(material.emissive_factor())),
+        };
+
+        Self {
+            base_color,
+            metallic,
+            roughness,
+            ambient_occlusion,
+            emissive,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color: MaterialInput::Constant(Vector4::new(1.0, 1.0, 1.0, 1.0)),
+            metallic: MaterialInput::Constant(0.0),
+            roughness: MaterialInput::Constant(0.5),
+            ambient_occlusion: MaterialInput::Constant(1.0),
+            emissive: MaterialInput::Constant(Vector3::new(0.0, 0.0, 0.0)),
+        }
+    }
+}
@@ -1,112 +1,526 @@
-use crate::core::renderer::gl;
+use std::{cell::OnceCell, ops::Range};
+
+use nalgebra::Vector3;
+
+use crate::core::{
+    math::{Aabb, Triangle},
+    renderer::{
+        buffer::{grow_capacity, BufferUsage},
+        gl,
+        resource::{defer_delete, GpuResource},
+    },
+};
+
+// position (3) + normal (3) + texture coordinates (2)
+pub const VERTEX_STRIDE: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Topology {
+    Points,
+    Lines,
+    LineStrip,
+    Triangles,
+    TriangleStrip,
+}
+
+impl Topology {
+    fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            Topology::Points => gl::POINTS,
+            Topology::Lines => gl::LINES,
+            Topology::LineStrip => gl::LINE_STRIP,
+            Topology::Triangles => gl::TRIANGLES,
+            Topology::TriangleStrip => gl::TRIANGLE_STRIP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indices {
+    None,
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::None => 0,
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<u32> {
+        match self {
+            Indices::None => None,
+            Indices::U16(indices) => indices.get(index).map(|i| *i as u32),
+            Indices::U32(indices) => indices.get(index).copied(),
+        }
+    }
+
+    pub fn element_size(&self) -> usize {
+        match self {
+            Indices::None => 0,
+            Indices::U16(_) => std::mem::size_of::<u16>(),
+            Indices::U32(_) => std::mem::size_of::<u32>(),
+        }
+    }
+
+    fn as_ptr(&self) -> *const gl::types::GLvoid {
+        self.offset_ptr(0)
+    }
+
+    fn offset_ptr(&self, offset: usize) -> *const gl::types::GLvoid {
+        match self {
+            Indices::None => std::ptr::null(),
+            Indices::U16(indices) => indices[offset..].as_ptr() as *const gl::types::GLvoid,
+            Indices::U32(indices) => indices[offset..].as_ptr() as *const gl::types::GLvoid,
+        }
+    }
+
+    fn gl_type(&self) -> Option<gl::types::GLenum> {
+        match self {
+            Indices::None => None,
+            Indices::U16(_) => Some(gl::UNSIGNED_SHORT),
+            Indices::U32(_) => Some(gl::UNSIGNED_INT),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Mesh {
     pub vertices: Vec<f32>,
-    pub indices: Vec<u32>,
+    pub indices: Indices,
+    pub topology: Topology,
+    pub usage: BufferUsage,
 
     vao: u32,
     vbo: u32,
     ebo: u32,
+    vertex_capacity: usize,
+    index_capacity: usize,
+
+    // GPU upload state, flushed by `upload` on the render thread
+    dirty: bool,
+    full_upload: bool,
+    vertex_range: Option<Range<usize>>,
+    index_range: Option<Range<usize>>,
+
+    // computed on first use, reset whenever the vertices change
+    bounds: OnceCell<Option<Aabb>>,
 }
 
 impl Mesh {
     pub fn new(vertices: Vec<f32>, indices: Vec<u32>) -> Self {
+        Self::with_indices(vertices, Indices::U32(indices))
+    }
+
+    pub fn with_indices(vertices: Vec<f32>, indices: Indices) -> Self {
         Self {
             vertices,
             indices,
+            topology: Topology::Triangles,
+            usage: BufferUsage::Static,
             vao: 0,
             vbo: 0,
             ebo: 0,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            dirty: true,
+            full_upload: true,
+            vertex_range: None,
+            index_range: None,
+            bounds: OnceCell::new(),
         }
     }
 
-    pub fn get_id(&self) -> u32 {
-        self.vao
+    pub fn non_indexed(vertices: Vec<f32>) -> Self {
+        Self::with_indices(vertices, Indices::None)
     }
 
-    pub fn init(&mut self) {
-        let mut vao = 0;
-        let mut vbo = 0;
-        let mut ebo = 0;
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: BufferUsage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len() / VERTEX_STRIDE
+    }
+
+    pub fn position(&self, vertex: usize) -> Option<Vector3<f32>> {
+        let offset = vertex * VERTEX_STRIDE;
+        self.vertices
+            .get(offset..offset + 3)
+            .map(|position| Vector3::new(position[0], position[1], position[2]))
+    }
+
+    // Local space bounds, `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<Aabb> {
+        *self.bounds.get_or_init(|| {
+            Aabb::from_points((0..self.vertex_count()).filter_map(|vertex| self.position(vertex)))
+        })
+    }
+
+    // Triangles as drawn, in local space. Empty for point and line topologies,
+    // out of range indices are skipped.
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        let count = match self.topology {
+            Topology::Triangles => self.element_count() / 3,
+            Topology::TriangleStrip => self.element_count().saturating_sub(2),
+            _ => 0,
+        };
+
+        (0..count).filter_map(move |i| {
+            let elements = match self.topology {
+                Topology::Triangles => [3 * i, 3 * i + 1, 3 * i + 2],
+                // every other triangle of a strip is flipped to keep the winding
+                _ if i % 2 == 1 => [i + 1, i, i + 2],
+                _ => [i, i + 1, i + 2],
+            };
+            let vertex = |element: usize| match self.indices {
+                Indices::None => self.position(element),
+                _ => self
+                    .indices
+                    .get(element)
+                    .and_then(|index| self.position(index as usize)),
+            };
+
+            Some(Triangle::new(
+                vertex(elements[0])?,
+                vertex(elements[1])?,
+                vertex(elements[2])?,
+            ))
+        })
+    }
+
+    // Number of elements submitted by a draw call: the index count for
+    // indexed meshes, the vertex count otherwise.
+    pub fn element_count(&self) -> usize {
+        match self.indices {
+            Indices::None => self.vertex_count(),
+            _ => self.indices.len(),
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn is_uploaded(&self) -> bool {
+        self.vao != 0
+    }
+
+    // Schedules a full re-upload and recomputes the bounds, needed after
+    // editing `vertices` or `indices` directly instead of through
+    // `update_vertices`/`update_indices`.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.full_upload = true;
+        self.bounds = OnceCell::new();
+    }
+
+    // Overwrites (or appends) vertices starting at vertex `offset`. Only the
+    // touched range is uploaded on the next `upload`, unless the mesh outgrew
+    // its GPU buffer, in which case the buffer is reallocated with room to spare.
+    pub fn update_vertices(&mut self, offset: usize, vertices: &[f32]) -> Result<(), MeshError> {
+        if !vertices.len().is_multiple_of(VERTEX_STRIDE) {
+            return Err(MeshError::InvalidVertexData);
+        }
+
+        let start = offset * VERTEX_STRIDE;
+        if start > self.vertices.len() {
+            return Err(MeshError::RangeOutOfBounds);
+        }
+
+        let end = start + vertices.len();
+        if end > self.vertices.len() {
+            self.vertices.resize(end, 0.0);
+        }
+        self.vertices[start..end].copy_from_slice(vertices);
+
+        self.dirty = true;
+        self.bounds = OnceCell::new();
+        merge_range(&mut self.vertex_range, start..end);
+        Ok(())
+    }
+
+    // Overwrites (or appends) indices starting at index `offset`, converting
+    // them to the mesh index type.
+    pub fn update_indices(&mut self, offset: usize, indices: &[u32]) -> Result<(), MeshError> {
+        if offset > self.indices.len() {
+            return Err(MeshError::RangeOutOfBounds);
+        }
+
+        let end = offset + indices.len();
+        match &mut self.indices {
+            Indices::None => return Err(MeshError::NotIndexed),
+            Indices::U16(current) => {
+                if indices.iter().any(|i| *i > u16::MAX as u32) {
+                    return Err(MeshError::IndexOutOfRange);
+                }
+                if end > current.len() {
+                    current.resize(end, 0);
+                }
+                for (dst, src) in current[offset..end].iter_mut().zip(indices) {
+                    *dst = *src as u16;
+                }
+            }
+            Indices::U32(current) => {
+                if end > current.len() {
+                    current.resize(end, 0);
+                }
+                current[offset..end].copy_from_slice(indices);
+            }
+        }
+
+        self.dirty = true;
+        merge_range(&mut self.index_range, offset..end);
+        Ok(())
+    }
+
+    // Drops vertices past `vertex_count`. The GPU buffer keeps its capacity.
+    pub fn truncate_vertices(&mut self, vertex_count: usize) {
+        self.vertices.truncate(vertex_count * VERTEX_STRIDE);
+        clamp_range(&mut self.vertex_range, self.vertices.len());
+        self.bounds = OnceCell::new();
+    }
+
+    pub fn truncate_indices(&mut self, index_count: usize) {
+        match &mut self.indices {
+            Indices::None => {}
+            Indices::U16(indices) => indices.truncate(index_count),
+            Indices::U32(indices) => indices.truncate(index_count),
+        }
+        clamp_range(&mut self.index_range, self.indices.len());
+    }
+
+    fn vertex_bytes(&self) -> usize {
+        self.vertices.len() * std::mem::size_of::<f32>()
+    }
+
+    fn index_bytes(&self) -> usize {
+        self.indices.len() * self.indices.element_size()
+    }
+
+    // Creates the GPU buffers on first use and flushes pending changes.
+    // Must be called on the render thread with the GL context current.
+    pub fn upload(&mut self) {
+        if !self.dirty {
+            return;
+        }
 
         unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
+            if self.vao == 0 {
+                self.create_buffers();
+                self.full_upload = true;
+            }
+
+            gl::BindVertexArray(self.vao);
+            self.upload_vertices();
+            self.upload_indices();
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
 
-            gl::BindVertexArray(vao);
+        self.dirty = false;
+        self.full_upload = false;
+        self.vertex_range = None;
+        self.index_range = None;
+    }
+
+    unsafe fn create_buffers(&mut self) {
+        gl::GenVertexArrays(1, &mut self.vao);
+        gl::GenBuffers(1, &mut self.vbo);
+
+        gl::BindVertexArray(self.vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+        let stride = VERTEX_STRIDE as i32 * std::mem::size_of::<f32>() as i32;
+
+        // Position attribute
+        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
+        gl::EnableVertexAttribArray(0);
+
+        // Normal attribute
+        gl::VertexAttribPointer(
+            1,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (3 * std::mem::size_of::<f32>()) as *const gl::types::GLvoid,
+        );
+        gl::EnableVertexAttribArray(1);
+
+        // Texture coordinates attribute
+        gl::VertexAttribPointer(
+            2,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (6 * std::mem::size_of::<f32>()) as *const gl::types::GLvoid,
+        );
+        gl::EnableVertexAttribArray(2);
+
+        gl::BindVertexArray(0);
+    }
 
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    unsafe fn upload_vertices(&mut self) {
+        let required = self.vertex_bytes();
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+        if self.full_upload || required > self.vertex_capacity {
+            if required > self.vertex_capacity {
+                self.vertex_capacity = if self.vertex_capacity == 0 {
+                    required
+                } else {
+                    grow_capacity(self.vertex_capacity, required)
+                };
+            }
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (self.vertices.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-                &self.vertices[0] as *const f32 as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
+                self.vertex_capacity as gl::types::GLsizeiptr,
+                std::ptr::null(),
+                self.usage.to_gl(),
+            );
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                required as gl::types::GLsizeiptr,
+                self.vertices.as_ptr() as *const gl::types::GLvoid,
+            );
+        } else if let Some(range) = self.vertex_range.clone() {
+            let size = std::mem::size_of::<f32>();
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                (range.start * size) as gl::types::GLintptr,
+                std::mem::size_of_val(&self.vertices[range.clone()]) as gl::types::GLsizeiptr,
+                self.vertices[range].as_ptr() as *const gl::types::GLvoid,
             );
+        }
+    }
+
+    // Expects the mesh VAO to be bound, the element buffer binding is part of its state.
+    unsafe fn upload_indices(&mut self) {
+        if self.indices == Indices::None {
+            return;
+        }
+
+        if self.ebo == 0 {
+            gl::GenBuffers(1, &mut self.ebo);
+            self.full_upload = true;
+        }
 
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        let required = self.index_bytes();
+        let size = self.indices.element_size();
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+
+        if self.full_upload || required > self.index_capacity {
+            if required > self.index_capacity {
+                self.index_capacity = if self.index_capacity == 0 {
+                    required
+                } else {
+                    grow_capacity(self.index_capacity, required)
+                };
+            }
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
-                (self.indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
-                &self.indices[0] as *const u32 as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
+                self.index_capacity as gl::types::GLsizeiptr,
+                std::ptr::null(),
+                self.usage.to_gl(),
             );
-
-            // Position attribute
-            gl::VertexAttribPointer(
+            gl::BufferSubData(
+                gl::ELEMENT_ARRAY_BUFFER,
                 0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * std::mem::size_of::<f32>() as i32,
-                std::ptr::null(),
+                required as gl::types::GLsizeiptr,
+                self.indices.as_ptr(),
             );
-            gl::EnableVertexAttribArray(0);
-
-            // Normal attribute
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * std::mem::size_of::<f32>() as i32,
-                (3 * std::mem::size_of::<f32>()) as *const gl::types::GLvoid,
+        } else if let Some(range) = self.index_range.clone() {
+            gl::BufferSubData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (range.start * size) as gl::types::GLintptr,
+                (range.len() * size) as gl::types::GLsizeiptr,
+                self.indices.offset_ptr(range.start),
             );
-            gl::EnableVertexAttribArray(1);
+        }
+    }
 
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            gl::BindVertexArray(0);
+    pub fn draw(&self) {
+        let count = self.element_count();
+        if count == 0 || self.vao == 0 {
+            return;
         }
 
-        self.vao = vao;
-        self.vbo = vbo;
-        self.ebo = ebo;
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            match self.indices.gl_type() {
+                Some(index_type) => gl::DrawElements(
+                    self.topology.to_gl(),
+                    count as i32,
+                    index_type,
+                    std::ptr::null(),
+                ),
+                None => gl::DrawArrays(self.topology.to_gl(), 0, count as i32),
+            }
+            gl::BindVertexArray(0);
+        }
     }
 }
 
 impl Drop for Mesh {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteVertexArrays(1, &self.vao);
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
-        }
+        defer_delete(GpuResource::VertexArray(self.vao));
+        defer_delete(GpuResource::Buffer(self.vbo));
+        defer_delete(GpuResource::Buffer(self.ebo));
     }
 }
 
+fn merge_range(range: &mut Option<Range<usize>>, new: Range<usize>) {
+    *range = Some(match range.take() {
+        Some(current) => current.start.min(new.start)..current.end.max(new.end),
+        None => new,
+    });
+}
+
+// Keeps a pending upload range within data that was truncated since.
+fn clamp_range(range: &mut Option<Range<usize>>, len: usize) {
+    *range = match range.take() {
+        Some(current) if current.start < len => Some(current.start..current.end.min(len)),
+        _ => None,
+    };
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    InvalidVertexData,
+    RangeOutOfBounds,
+    IndexOutOfRange,
+    NotIndexed,
+}
+
 // temporary shapes
 
 #[rustfmt::skip]
 pub fn square() -> Mesh {
     let vertices = vec![
-        -0.5,  0.5, 0.0, 0.0, 0.0, 1.0, // bottom left
-        -0.5, -0.5, 0.0, 0.0, 0.0, 1.0, // bottom right
-         0.5,  0.5, 0.0, 0.0, 0.0, 1.0, // top right
-         0.5, -0.5, 0.0, 0.0, 0.0, 1.0, // top left
+        -0.5,  0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, // top left
+        -0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, // bottom left
+         0.5,  0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, // top right
+         0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, // bottom right
     ];
 
-    let indices = vec![0, 1, 2, 2, 3, 1];
+    let indices = vec![0, 1, 2, 2, 1, 3];
 
     Mesh::new(vertices, indices)
 }
@@ -114,24 +528,69 @@ pub fn square() -> Mesh {
 #[rustfmt::skip]
 pub fn cube() -> Mesh {
     let vertices = vec![
-         0.5,  0.5,  0.5, 1.0, 0.0, 0.0,
-         0.5,  0.5, -0.5, 1.0, 0.0, 0.0,
-         0.5, -0.5,  0.5, 1.0, 0.0, 0.0,
-         0.5, -0.5, -0.5, 1.0, 0.0, 0.0,
-        -0.5,  0.5,  0.5, 1.0, 0.0, 0.0,
-        -0.5,  0.5, -0.5, 1.0, 0.0, 0.0,
-        -0.5, -0.5,  0.5, 1.0, 0.0, 0.0,
-        -0.5, -0.5, -0.5, 1.0, 0.0, 0.0
+        // right
+         0.5, -0.5,  0.5,  1.0,  0.0,  0.0, 0.0, 0.0,
+         0.5, -0.5, -0.5,  1.0,  0.0,  0.0, 1.0, 0.0,
+         0.5,  0.5, -0.5,  1.0,  0.0,  0.0, 1.0, 1.0,
+         0.5,  0.5,  0.5,  1.0,  0.0,  0.0, 0.0, 1.0,
+        // left
+        -0.5, -0.5, -0.5, -1.0,  0.0,  0.0, 0.0, 0.0,
+        -0.5, -0.5,  0.5, -1.0,  0.0,  0.0, 1.0, 0.0,
+        -0.5,  0.5,  0.5, -1.0,  0.0,  0.0, 1.0, 1.0,
+        -0.5,  0.5, -0.5, -1.0,  0.0,  0.0, 0.0, 1.0,
+        // top
+        -0.5,  0.5,  0.5,  0.0,  1.0,  0.0, 0.0, 0.0,
+         0.5,  0.5,  0.5,  0.0,  1.0,  0.0, 1.0, 0.0,
+         0.5,  0.5, -0.5,  0.0,  1.0,  0.0, 1.0, 1.0,
+        -0.5,  0.5, -0.5,  0.0,  1.0,  0.0, 0.0, 1.0,
+        // bottom
+        -0.5, -0.5, -0.5,  0.0, -1.0,  0.0, 0.0, 0.0,
+         0.5, -0.5, -0.5,  0.0, -1.0,  0.0, 1.0, 0.0,
+         0.5, -0.5,  0.5,  0.0, -1.0,  0.0, 1.0, 1.0,
+        -0.5, -0.5,  0.5,  0.0, -1.0,  0.0, 0.0, 1.0,
+        // front
+        -0.5, -0.5,  0.5,  0.0,  0.0,  1.0, 0.0, 0.0,
+         0.5, -0.5,  0.5,  0.0,  0.0,  1.0, 1.0, 0.0,
+         0.5,  0.5,  0.5,  0.0,  0.0,  1.0, 1.0, 1.0,
+        -0.5,  0.5,  0.5,  0.0,  0.0,  1.0, 0.0, 1.0,
+        // back
+         0.5, -0.5, -0.5,  0.0,  0.0, -1.0, 0.0, 0.0,
+        -0.5, -0.5, -0.5,  0.0,  0.0, -1.0, 1.0, 0.0,
+        -0.5,  0.5, -0.5,  0.0,  0.0, -1.0, 1.0, 1.0,
+         0.5,  0.5, -0.5,  0.0,  0.0, -1.0, 0.0, 1.0,
     ];
 
     let indices = vec![
-        0, 1, 2, 2, 1, 3,
-        4, 5, 6, 6, 5, 7,
-        0, 2, 4, 4, 2, 6,
-        1, 5, 3, 3, 5, 7,
-        2, 3, 6, 6, 3, 7,
-        0, 4, 1, 1, 4, 5
+         0,  1,  2,  2,  3,  0,
+         4,  5,  6,  6,  7,  4,
+         8,  9, 10, 10, 11,  8,
+        12, 13, 14, 14, 15, 12,
+        16, 17, 18, 18, 19, 16,
+        20, 21, 22, 22, 23, 20,
     ];
 
     Mesh::new(vertices, indices)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_follow_vertex_updates() {
+        let mut mesh = cube();
+        let bounds = mesh.bounds().unwrap();
+        assert_eq!(bounds.min, Vector3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bounds.max, Vector3::new(0.5, 0.5, 0.5));
+
+        let vertex = [2.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        mesh.update_vertices(mesh.vertex_count(), &vertex).unwrap();
+        assert_eq!(mesh.bounds().unwrap().max.x, 2.0);
+
+        mesh.truncate_vertices(24);
+        assert_eq!(mesh.bounds().unwrap().max.x, 0.5);
+
+        mesh.truncate_vertices(0);
+        assert_eq!(mesh.bounds(), None);
+    }
+}
@@ -1,56 +1,29 @@
-mod buffer;
+mod hierarchy;
 mod light;
+mod material;
 mod mesh;
 mod player;
+mod raycast;
 mod renderable;
 mod transform;
 
-use nalgebra::{Vector3, Vector4};
+use nalgebra::{UnitQuaternion, Vector3};
 
-pub use buffer::EntityBuffer;
+pub use hierarchy::{Ancestors, Children, GlobalTransform, Parent};
 pub use light::{Light, LightBuilder};
-pub use mesh::{cube, square, Mesh};
-pub use renderable::Renderable;
-pub use transform::Transform;
+pub use material::{Material, MaterialInput};
+pub use mesh::{cube, square, Indices, Mesh, MeshError, Topology, VERTEX_STRIDE};
+pub use player::{player_input_system, Player, PlayerControls, PlayerInput};
+pub use raycast::RayHit;
+pub use renderable::{RenderLayers, Renderable};
+pub use transform::{EulerOrder, Transform};
 
-use super::assets::AssetsManager;
+use super::{
+    assets::AssetsManager,
+    ecs::{Component, EntityId, World},
+};
 
-#[derive(Debug)]
-pub struct Material {
-    pub color: Vector4<f32>,
-}
-
-impl Material {
-    pub fn new(color: [f32; 4]) -> Self {
-        Self {
-            color: Vector4::from(color),
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct Entity {
-    pub id: u32,
-    pub renderable: Renderable,
-}
-
-impl Entity {
-    pub fn init(&mut self, assets: &mut AssetsManager) {
-        self.renderable.init(assets);
-    }
-
-    pub fn get_id(&self) -> u32 {
-        self.id
-    }
-
-    pub fn get_mesh_id(&self) -> u32 {
-        self.renderable.mesh_id
-    }
-
-    pub fn get_mesh_indices(&self) -> u32 {
-        self.renderable.mesh_indices
-    }
-}
+type ComponentInserter = Box<dyn FnOnce(&mut World, EntityId) -> Result<(), EntityError>>;
 
 pub struct EntityBuilder {
     // pub renderable: Option<Renderable>,
@@ -58,8 +31,12 @@ pub struct EntityBuilder {
     pub shader: Option<String>,
     pub material: Material,
     pub position: Vector3<f32>,
-    pub rotation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
     pub scale: Vector3<f32>,
+    pub parent: Option<EntityId>,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    components: Vec<ComponentInserter>,
 }
 
 impl EntityBuilder {
@@ -68,12 +45,14 @@ impl EntityBuilder {
             // renderable: None,
             mesh: None,
             shader: None,
-            material: Material {
-                color: Vector4::from([1.0, 1.0, 1.0, 1.0]),
-            },
+            material: Material::default(),
             position: Vector3::from([0.0, 0.0, 0.0]),
-            rotation: Vector3::from([0.0, 0.0, 0.0]),
+            rotation: UnitQuaternion::identity(),
             scale: Vector3::from([1.0, 1.0, 1.0]),
+            parent: None,
+            name: None,
+            tags: Vec::new(),
+            components: Vec::new(),
         }
     }
 
@@ -104,7 +83,7 @@ impl EntityBuilder {
         self
     }
 
-    pub fn with_rotation(mut self, rotation: Vector3<f32>) -> Self {
+    pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
         self.rotation = rotation;
         self
     }
@@ -114,20 +93,52 @@ impl EntityBuilder {
         self
     }
 
-    pub fn build(
-        self,
-        assets: &AssetsManager,
-        buffer: &mut EntityBuffer,
-    ) -> Result<Entity, EntityError> {
-        let mesh_id;
-        let mesh_indices;
-        let mesh = self.mesh.expect("Mesh not found");
-        if let Some(mesh_obj) = assets.get_object(mesh.as_str()) {
-            mesh_id = mesh_obj.get_id();
-            mesh_indices = mesh_obj.indices.len() as u32;
-        } else {
-            return Err(EntityError::MeshNotFound);
-        }
+    // The transform of the entity is then relative to `parent`.
+    pub fn with_parent(mut self, parent: EntityId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    // Names are unique within the world, see `World::set_name`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_owned());
+        self
+    }
+
+    // Any extra component, e.g. game specific state, added when the entity is spawned.
+    pub fn with_component<T: Component>(mut self, component: T) -> Self {
+        self.components.push(Box::new(move |world, entity| {
+            world.insert(entity, component)
+        }));
+        self
+    }
+
+    // Spawns the entity with its transform, a `Renderable` when a mesh is set
+    // and the extra components.
+    pub fn build(self, assets: &AssetsManager, world: &mut World) -> Result<EntityId, EntityError> {
+        let renderable = match self.mesh {
+            Some(mesh) => {
+                let mesh = assets
+                    .mesh_handle(mesh.as_str())
+                    .ok_or(EntityError::MeshNotFound)?;
+                let shader = self
+                    .shader
+                    .and_then(|shader| assets.shader_handle(shader.as_str()))
+                    .ok_or(EntityError::ShaderNotFound)?;
+
+                Some(Renderable {
+                    mesh,
+                    material: self.material,
+                    shader,
+                })
+            }
+            None => None,
+        };
 
         let transform = Transform {
             position: self.position,
@@ -135,19 +146,34 @@ impl EntityBuilder {
             scale: self.scale,
         };
 
-        let renderable = Renderable {
-            mesh: mesh,
-            material: self.material,
-            transform,
-            shader: self.shader.expect("A shader is required"),
-            mesh_id,
-            mesh_indices,
-        };
+        if let Some(parent) = self.parent {
+            if !world.is_alive(parent) {
+                return Err(EntityError::EntityNotFound);
+            }
+        }
+        if let Some(name) = &self.name {
+            if world.find_by_name(name).is_some() {
+                return Err(EntityError::NameTaken(name.clone()));
+            }
+        }
 
-        let entity = Entity {
-            id: buffer.get_id(),
-            renderable,
-        };
+        let entity = world.spawn();
+        world.insert(entity, transform)?;
+        if let Some(name) = &self.name {
+            world.set_name(entity, name)?;
+        }
+        for tag in self.tags.iter() {
+            world.add_tag(entity, tag)?;
+        }
+        if let Some(parent) = self.parent {
+            world.attach(entity, parent)?;
+        }
+        if let Some(renderable) = renderable {
+            world.insert(entity, renderable)?;
+        }
+        for insert in self.components {
+            insert(world, entity)?;
+        }
 
         Ok(entity)
     }
@@ -158,4 +184,8 @@ pub enum EntityError {
     MeshNotFound,
     ShaderNotFound,
     EntityNotFound,
+    // The parent is the entity itself or one of its descendants
+    InvalidParent,
+    // Another entity already has the name
+    NameTaken(String),
 }
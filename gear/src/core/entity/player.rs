@@ -0,0 +1,216 @@
+use crate::core::application::context::Context;
+
+// Movement wanted this frame, relative to the player heading. Set by the game
+// or by `player_input_system`, `jump` is cleared once handled.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PlayerInput {
+    pub forward: f32,
+    pub right: f32,
+    pub jump: bool,
+}
+
+// Names of the input map axes and action driving a `Player`, read by
+// `player_input_system`. Leave it off to set `Player::input` yourself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerControls {
+    pub forward: String,
+    pub right: String,
+    pub jump: String,
+}
+
+impl Default for PlayerControls {
+    fn default() -> Self {
+        Self {
+            forward: "move_forward".to_string(),
+            right: "move_right".to_string(),
+            jump: "jump".to_string(),
+        }
+    }
+}
+
+impl PlayerControls {
+    pub fn new(forward: &str, right: &str, jump: &str) -> Self {
+        Self {
+            forward: forward.to_string(),
+            right: right.to_string(),
+            jump: jump.to_string(),
+        }
+    }
+}
+
+// Copies the bound axes and action into the input of every `Player` with
+// `PlayerControls`. Add it to the `Update` stage to opt in.
+pub fn player_input_system(ctx: &mut Context) {
+    let Context { world, input, .. } = ctx;
+    world
+        .query::<(&mut Player, &PlayerControls)>()
+        .for_each(|_, (player, controls)| {
+            player.input.forward = input.axis(&controls.forward).clamp(-1.0, 1.0);
+            player.input.right = input.axis(&controls.right).clamp(-1.0, 1.0);
+            // a press counts until the physics handles it
+            player.input.jump |= input.action_pressed(&controls.jump);
+        });
+}
+
+// Kinematic character controller. The physics gives the entity a capsule
+// collider and moves it against the other colliders: sliding along walls,
+// stepping up small ledges, refusing slopes steeper than `max_slope`. The
+// entity must not also have a `RigidBody` or `Collider`.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub speed: f32,
+    pub jump_speed: f32,
+    // Capsule height including the caps, the transform is at its center
+    pub height: f32,
+    pub radius: f32,
+    pub step_height: f32,
+    // In radians
+    pub max_slope: f32,
+    pub input: PlayerInput,
+
+    vertical_speed: f32,
+    grounded: bool,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self {
+            speed: 3.0,
+            jump_speed: 5.0,
+            height: 1.8,
+            radius: 0.3,
+            step_height: 0.3,
+            max_slope: 45.0f32.to_radians(),
+            input: PlayerInput::default(),
+            vertical_speed: 0.0,
+            grounded: false,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_jump_speed(mut self, jump_speed: f32) -> Self {
+        self.jump_speed = jump_speed;
+        self
+    }
+
+    pub fn with_size(mut self, height: f32, radius: f32) -> Self {
+        self.height = height;
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_step_height(mut self, step_height: f32) -> Self {
+        self.step_height = step_height;
+        self
+    }
+
+    pub fn with_max_slope(mut self, max_slope: f32) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    pub fn vertical_speed(&self) -> f32 {
+        self.vertical_speed
+    }
+
+    // Half height of the capsule segment, without the caps.
+    pub(crate) fn half_height(&self) -> f32 {
+        (self.height * 0.5 - self.radius).max(0.0)
+    }
+
+    // Applies gravity and jumping, returns the velocity wanted along `up`.
+    pub(crate) fn fall(&mut self, gravity: f32, delta: f32) -> f32 {
+        if self.grounded && self.input.jump {
+            self.vertical_speed = self.jump_speed;
+            self.grounded = false;
+        }
+        self.input.jump = false;
+
+        self.vertical_speed -= gravity * delta;
+        self.vertical_speed
+    }
+
+    pub(crate) fn land(&mut self, grounded: bool) {
+        self.grounded = grounded;
+        if grounded && self.vertical_speed < 0.0 {
+            self.vertical_speed = 0.0;
+        }
+    }
+
+    // `rise` is how far the controller moved the player along `up`. Rising
+    // clearly less than wanted means a ceiling stopped the jump, without this
+    // the player would stick to it until gravity used the speed up.
+    pub(crate) fn rise(&mut self, rise: f32, delta: f32) {
+        if self.vertical_speed > 0.0 && rise < self.vertical_speed * delta * 0.5 {
+            self.vertical_speed = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAVITY: f32 = 10.0;
+    const DELTA: f32 = 0.1;
+
+    #[test]
+    fn falls_until_it_lands() {
+        let mut player = Player::new();
+        assert_eq!(player.fall(GRAVITY, DELTA), -1.0);
+        assert_eq!(player.fall(GRAVITY, DELTA), -2.0);
+
+        player.land(false);
+        assert_eq!(player.vertical_speed(), -2.0);
+        player.land(true);
+        assert!(player.is_grounded());
+        assert_eq!(player.vertical_speed(), 0.0);
+    }
+
+    #[test]
+    fn jumps_only_from_the_ground() {
+        let mut player = Player::new().with_jump_speed(5.0);
+        player.input.jump = true;
+        assert_eq!(player.fall(GRAVITY, DELTA), -1.0);
+        assert!(!player.input.jump);
+
+        player.land(true);
+        player.input.jump = true;
+        assert_eq!(player.fall(GRAVITY, DELTA), 4.0);
+        assert!(!player.is_grounded());
+
+        // still on the ground the frame after the jump, rising keeps the speed
+        player.land(true);
+        assert_eq!(player.vertical_speed(), 4.0);
+    }
+
+    #[test]
+    fn ceiling_stops_the_jump() {
+        let mut player = Player::new();
+        player.land(true);
+        player.input.jump = true;
+        let speed = player.fall(GRAVITY, DELTA);
+
+        player.rise(speed * DELTA * 0.9, DELTA);
+        assert_eq!(player.vertical_speed(), speed);
+        player.rise(0.0, DELTA);
+        assert_eq!(player.vertical_speed(), 0.0);
+        assert_eq!(player.fall(GRAVITY, DELTA), -1.0);
+    }
+
+    #[test]
+    fn falling_ignores_the_rise() {
+        let mut player = Player::new();
+        player.fall(GRAVITY, DELTA);
+        player.rise(0.0, DELTA);
+        assert_eq!(player.vertical_speed(), -1.0);
+    }
+}
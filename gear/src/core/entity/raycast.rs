@@ -0,0 +1,86 @@
+use nalgebra::{Matrix4, Vector3};
+
+use crate::core::{
+    assets::AssetsManager,
+    ecs::{EntityId, World},
+    math::Ray,
+};
+
+use super::{GlobalTransform, Renderable};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayHit {
+    pub entity: EntityId,
+    pub distance: f32,
+    pub point: Vector3<f32>,
+    // Faces the ray origin
+    pub normal: Vector3<f32>,
+}
+
+impl World {
+    // Closest renderable hit by the ray, tested against the triangles of its
+    // mesh. Uses the `GlobalTransform`s, so entities spawned or moved since
+    // the last propagation are tested where they were last drawn.
+    pub fn raycast(&self, ray: &Ray, assets: &AssetsManager) -> Option<RayHit> {
+        let ray = Ray::new(ray.origin, ray.direction);
+        let mut closest: Option<RayHit> = None;
+
+        self.query::<(&GlobalTransform, &Renderable)>()
+            .for_each(|entity, (global, renderable)| {
+                let Ok(mesh) = assets.mesh(renderable.mesh) else {
+                    return;
+                };
+                let Some(inverse) = global.matrix().try_inverse() else {
+                    return;
+                };
+                let max_distance = closest.map_or(f32::INFINITY, |hit| hit.distance);
+
+                // the local ray keeps the world parameterization
+                let local = ray.transform(&inverse);
+                match mesh
+                    .bounds()
+                    .and_then(|bounds| bounds.intersect_ray(&local))
+                {
+                    Some(distance) if distance < max_distance => {}
+                    _ => return,
+                }
+
+                let hit = mesh
+                    .triangles()
+                    .filter_map(|triangle| {
+                        triangle
+                            .intersect_ray(&local)
+                            .map(|distance| (distance, triangle))
+                    })
+                    .min_by(|(a, _), (b, _)| a.total_cmp(b));
+
+                if let Some((distance, triangle)) = hit {
+                    if distance < max_distance {
+                        let normal = world_normal(&inverse, &triangle.normal());
+                        closest = Some(RayHit {
+                            entity,
+                            distance,
+                            point: ray.at(distance),
+                            normal: if normal.dot(&ray.direction) > 0.0 {
+                                -normal
+                            } else {
+                                normal
+                            },
+                        });
+                    }
+                }
+            });
+
+        closest
+    }
+}
+
+// Normals follow the inverse transpose to stay perpendicular under
+// non-uniform scales.
+fn world_normal(inverse: &Matrix4<f32>, normal: &Vector3<f32>) -> Vector3<f32> {
+    inverse
+        .transpose()
+        .transform_vector(normal)
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::zeros)
+}
@@ -1,20 +1,17 @@
-use crate::core::assets::AssetsManager;
+use crate::core::assets::{MeshHandle, ShaderHandle};
 
-use super::{Material, Transform};
+use super::Material;
 
+// Component for entities drawn with a mesh, placed by their `Transform`.
 #[derive(Debug)]
 pub struct Renderable {
-    pub mesh: String,
+    pub mesh: MeshHandle,
     pub material: Material,
-    pub transform: Transform,
-    pub shader: String,
-    pub mesh_id: u32,
-    pub mesh_indices: u32,
+    pub shader: ShaderHandle,
 }
 
-impl Renderable {
-    pub fn init(&mut self, assets: &mut AssetsManager) {
-        let mesh = assets.get_mut_object(self.mesh.as_str()).unwrap();
-        mesh.init();
-    }
-}
+// Bit mask of the render layers an entity is on, only cameras whose culling
+// mask shares a bit draw it. Entities without one are on
+// `DEFAULT_RENDER_LAYER`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderLayers(pub u32);
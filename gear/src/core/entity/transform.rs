@@ -1,9 +1,23 @@
-use nalgebra::{Matrix4, Vector3};
+use nalgebra::{Matrix3, Matrix4, Rotation3, Unit, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+// Order in which Euler angles are applied, about the fixed world axes:
+// `XYZ` rotates around X first, then Y, then Z (R = Rz * Ry * Rx).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EulerOrder {
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    ZXY,
+    ZYX,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Transform {
     pub position: Vector3<f32>,
-    pub rotation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
     pub scale: Vector3<f32>,
 }
 
@@ -11,7 +25,7 @@ impl Transform {
     pub fn new() -> Self {
         Self {
             position: Vector3::new(0.0, 0.0, 0.0),
-            rotation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: UnitQuaternion::identity(),
             scale: Vector3::new(1.0, 1.0, 1.0),
         }
     }
@@ -21,21 +35,373 @@ impl Transform {
         self
     }
 
-    pub fn with_rotation(mut self, rotation: Vector3<f32>) -> Self {
+    pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
         self.rotation = rotation;
         self
     }
 
+    // Angles in radians, (x, y, z) regardless of the order they are applied in.
+    pub fn with_euler(mut self, order: EulerOrder, angles: Vector3<f32>) -> Self {
+        self.rotation = euler_to_quaternion(order, angles);
+        self
+    }
+
+    // A zero length axis has no direction and leaves no rotation.
+    pub fn with_axis_angle(mut self, axis: Vector3<f32>, angle: f32) -> Self {
+        self.rotation = Unit::try_new(axis, f32::EPSILON)
+            .map_or(UnitQuaternion::identity(), |axis| {
+                UnitQuaternion::from_axis_angle(&axis, angle)
+            });
+        self
+    }
+
     pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
         self.scale = scale;
         self
     }
 
+    // Decomposes an affine matrix into translation, rotation and scale. Shear,
+    // e.g. from a non-uniformly scaled parent with rotated children, is lost.
+    // A mirrored matrix comes out with a negative X scale.
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Self {
+        let position = Vector3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+
+        let basis: Matrix3<f32> = matrix.fixed_view::<3, 3>(0, 0).into();
+        let mut scale = Vector3::new(
+            basis.column(0).norm(),
+            basis.column(1).norm(),
+            basis.column(2).norm(),
+        );
+        // the reflection goes in the scale, the rotation must stay proper
+        if basis.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let mut rotation = basis;
+        for i in 0..3 {
+            if scale[i].abs() > f32::EPSILON {
+                rotation.column_mut(i).scale_mut(1.0 / scale[i]);
+            }
+        }
+        let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&rotation));
+
+        Self {
+            position,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn euler_angles(&self, order: EulerOrder) -> Vector3<f32> {
+        quaternion_to_euler(order, &self.rotation)
+    }
+
+    pub fn set_euler_angles(&mut self, order: EulerOrder, angles: Vector3<f32>) {
+        self.rotation = euler_to_quaternion(order, angles);
+    }
+
+    // Cameras and lights look down -Z, like OpenGL.
+    pub fn forward(&self) -> Vector3<f32> {
+        self.rotation * -Vector3::z()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.rotation * Vector3::x()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation * Vector3::y()
+    }
+
+    // Turns the transform so that `forward` points at `target`. Nothing
+    // happens when the target is the current position.
+    pub fn look_at(&mut self, target: &Vector3<f32>, up: &Vector3<f32>) {
+        let direction = target - self.position;
+        if direction.norm_squared() <= f32::EPSILON {
+            return;
+        }
+
+        // `face_towards` aligns +Z, pick another up vector when they are parallel
+        let up = if direction.cross(up).norm_squared() <= f32::EPSILON {
+            if direction.cross(&Vector3::z()).norm_squared() <= f32::EPSILON {
+                Vector3::y()
+            } else {
+                Vector3::z()
+            }
+        } else {
+            *up
+        };
+        self.rotation = UnitQuaternion::face_towards(&-direction, &up);
+    }
+
+    // Rotation expressed in world space.
+    pub fn rotate(&mut self, rotation: &UnitQuaternion<f32>) {
+        self.rotation = rotation * self.rotation;
+    }
+
+    // Rotation expressed in the transform's own axes.
+    pub fn rotate_local(&mut self, rotation: &UnitQuaternion<f32>) {
+        self.rotation *= rotation;
+    }
+
+    // Orbits around `point`, turning the transform along.
+    pub fn rotate_around(&mut self, point: &Vector3<f32>, rotation: &UnitQuaternion<f32>) {
+        self.position = point + rotation * (self.position - point);
+        self.rotate(rotation);
+    }
+
+    pub fn translate(&mut self, translation: &Vector3<f32>) {
+        self.position += translation;
+    }
+
+    // Translation expressed in the transform's own axes.
+    pub fn translate_local(&mut self, translation: &Vector3<f32>) {
+        self.position += self.rotation * translation;
+    }
+
+    // Exact for uniform scales, otherwise the shear of the inverse is dropped.
+    pub fn inverse(&self) -> Self {
+        let scale = self
+            .scale
+            .map(|s| if s.abs() > f32::EPSILON { 1.0 / s } else { 0.0 });
+        let rotation = self.rotation.inverse();
+        let position = (rotation * -self.position).component_mul(&scale);
+
+        Self {
+            position,
+            rotation,
+            scale,
+        }
+    }
+
+    // Linear interpolation of position and scale, spherical of the rotation.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(&other.position, t),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+
     pub fn get_model_matrix(&self) -> Matrix4<f32> {
-        let mut model = Matrix4::identity();
-        model = model * Matrix4::new_translation(&self.position);
-        model = model * Matrix4::new_rotation(self.rotation);
-        model = model * Matrix4::new_nonuniform_scaling(&self.scale);
-        model
+        Matrix4::new_translation(&self.position)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn euler_to_quaternion(order: EulerOrder, angles: Vector3<f32>) -> UnitQuaternion<f32> {
+    let x = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), angles.x);
+    let y = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angles.y);
+    let z = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angles.z);
+
+    // the first rotation applied is the rightmost one
+    match order {
+        EulerOrder::XYZ => z * y * x,
+        EulerOrder::XZY => y * z * x,
+        EulerOrder::YXZ => z * x * y,
+        EulerOrder::YZX => x * z * y,
+        EulerOrder::ZXY => y * x * z,
+        EulerOrder::ZYX => x * y * z,
+    }
+}
+
+fn quaternion_to_euler(order: EulerOrder, rotation: &UnitQuaternion<f32>) -> Vector3<f32> {
+    let m = rotation.to_rotation_matrix();
+    let m = |row: usize, col: usize| m[(row - 1, col - 1)];
+    let asin = |value: f32| value.clamp(-1.0, 1.0).asin();
+    // close to gimbal lock one angle is arbitrary and set to zero
+    let locked = |value: f32| value.abs() >= 0.999_999_9;
+
+    match order {
+        // R = Rz * Ry * Rx
+        EulerOrder::XYZ => {
+            let y = asin(-m(3, 1));
+            if locked(m(3, 1)) {
+                Vector3::new(0.0, y, (-m(1, 2)).atan2(m(2, 2)))
+            } else {
+                Vector3::new(m(3, 2).atan2(m(3, 3)), y, m(2, 1).atan2(m(1, 1)))
+            }
+        }
+        // R = Ry * Rz * Rx
+        EulerOrder::XZY => {
+            let z = asin(m(2, 1));
+            if locked(m(2, 1)) {
+                Vector3::new(0.0, m(1, 3).atan2(m(3, 3)), z)
+            } else {
+                Vector3::new((-m(2, 3)).atan2(m(2, 2)), (-m(3, 1)).atan2(m(1, 1)), z)
+            }
+        }
+        // R = Rz * Rx * Ry
+        EulerOrder::YXZ => {
+            let x = asin(m(3, 2));
+            if locked(m(3, 2)) {
+                Vector3::new(x, 0.0, m(2, 1).atan2(m(1, 1)))
+            } else {
+                Vector3::new(x, (-m(3, 1)).atan2(m(3, 3)), (-m(1, 2)).atan2(m(2, 2)))
+            }
+        }
+        // R = Rx * Rz * Ry
+        EulerOrder::YZX => {
+            let z = asin(-m(1, 2));
+            if locked(m(1, 2)) {
+                Vector3::new((-m(2, 3)).atan2(m(3, 3)), 0.0, z)
+            } else {
+                Vector3::new(m(3, 2).atan2(m(2, 2)), m(1, 3).atan2(m(1, 1)), z)
+            }
+        }
+        // R = Ry * Rx * Rz
+        EulerOrder::ZXY => {
+            let x = asin(-m(2, 3));
+            if locked(m(2, 3)) {
+                Vector3::new(x, (-m(3, 1)).atan2(m(1, 1)), 0.0)
+            } else {
+                Vector3::new(x, m(1, 3).atan2(m(3, 3)), m(2, 1).atan2(m(2, 2)))
+            }
+        }
+        // R = Rx * Ry * Rz
+        EulerOrder::ZYX => {
+            let y = asin(m(1, 3));
+            if locked(m(1, 3)) {
+                Vector3::new(m(3, 2).atan2(m(2, 2)), y, 0.0)
+            } else {
+                Vector3::new((-m(2, 3)).atan2(m(3, 3)), y, (-m(1, 2)).atan2(m(1, 1)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const ORDERS: [EulerOrder; 6] = [
+        EulerOrder::XYZ,
+        EulerOrder::XZY,
+        EulerOrder::YXZ,
+        EulerOrder::YZX,
+        EulerOrder::ZXY,
+        EulerOrder::ZYX,
+    ];
+
+    fn same_rotation(a: &UnitQuaternion<f32>, b: &UnitQuaternion<f32>) -> bool {
+        a.angle_to(b) < 1e-3
+    }
+
+    fn approx(a: &Matrix4<f32>, b: &Matrix4<f32>) -> bool {
+        (a - b).abs().max() < 1e-4
+    }
+
+    #[test]
+    fn euler_round_trip() {
+        let angles = Vector3::new(0.3, -0.7, 1.2);
+        for order in ORDERS {
+            let transform = Transform::new().with_euler(order, angles);
+            let back = transform.euler_angles(order);
+            assert!((back - angles).norm() < 1e-4, "{:?}: {:?}", order, back);
+        }
+    }
+
+    #[test]
+    fn euler_order_matters() {
+        let angles = Vector3::new(0.3, -0.7, 1.2);
+        let xyz = Transform::new().with_euler(EulerOrder::XYZ, angles);
+        let zyx = Transform::new().with_euler(EulerOrder::ZYX, angles);
+        assert!(!same_rotation(&xyz.rotation, &zyx.rotation));
+
+        // XYZ applies X first
+        let x = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), angles.x);
+        let y = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angles.y);
+        let z = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angles.z);
+        assert!(same_rotation(&xyz.rotation, &(z * y * x)));
+    }
+
+    #[test]
+    fn euler_gimbal_lock() {
+        for order in ORDERS {
+            for sign in [1.0, -1.0] {
+                // the middle rotation of the order at a quarter turn
+                let mut angles = Vector3::new(0.4, 0.4, 0.4);
+                let middle = match order {
+                    EulerOrder::XYZ | EulerOrder::ZYX => 1,
+                    EulerOrder::XZY | EulerOrder::YZX => 2,
+                    EulerOrder::YXZ | EulerOrder::ZXY => 0,
+                };
+                angles[middle] = sign * FRAC_PI_2;
+
+                let transform = Transform::new().with_euler(order, angles);
+                let back = transform.euler_angles(order);
+                assert!(back.iter().all(|angle| angle.is_finite()));
+                let rebuilt = Transform::new().with_euler(order, back);
+                assert!(
+                    same_rotation(&transform.rotation, &rebuilt.rotation),
+                    "{:?} {}: {:?}",
+                    order,
+                    sign,
+                    back
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let transform = Transform::new()
+            .with_position(Vector3::new(1.0, -2.0, 3.0))
+            .with_euler(EulerOrder::YXZ, Vector3::new(0.2, 0.9, -0.4))
+            .with_scale(Vector3::new(2.0, 0.5, 3.0));
+        let back = Transform::from_matrix(&transform.get_model_matrix());
+        assert!((back.position - transform.position).norm() < 1e-5);
+        assert!((back.scale - transform.scale).norm() < 1e-5);
+        assert!(same_rotation(&back.rotation, &transform.rotation));
+    }
+
+    #[test]
+    fn mirrored_matrix() {
+        let mirrored = Transform::new()
+            .with_euler(EulerOrder::XYZ, Vector3::new(0.5, 0.1, -0.3))
+            .with_scale(Vector3::new(1.0, -2.0, 1.0));
+        let matrix = mirrored.get_model_matrix();
+        let back = Transform::from_matrix(&matrix);
+
+        assert!(back.scale.x < 0.0);
+        assert!(approx(&back.get_model_matrix(), &matrix));
+    }
+
+    #[test]
+    fn inverse() {
+        let transform = Transform::new()
+            .with_position(Vector3::new(4.0, 1.0, -2.0))
+            .with_euler(EulerOrder::ZXY, Vector3::new(0.3, 1.1, 0.7))
+            .with_scale(Vector3::new(2.0, 2.0, 2.0));
+        let product = transform.inverse().get_model_matrix() * transform.get_model_matrix();
+        assert!(approx(&product, &Matrix4::identity()));
+    }
+
+    #[test]
+    fn look_at() {
+        let mut transform = Transform::new().with_position(Vector3::new(1.0, 1.0, 1.0));
+        let target = Vector3::new(4.0, 5.0, 1.0);
+        transform.look_at(&target, &Vector3::y());
+        let direction = (target - transform.position).normalize();
+        assert!((transform.forward() - direction).norm() < 1e-5);
+        assert!(transform.right().dot(&Vector3::y()).abs() < 1e-5);
+
+        // straight up, parallel to the up vector
+        transform.look_at(&Vector3::new(1.0, 9.0, 1.0), &Vector3::y());
+        assert!((transform.forward() - Vector3::y()).norm() < 1e-5);
+
+        // on the target, nothing changes
+        let rotation = transform.rotation;
+        transform.look_at(&transform.position.clone(), &Vector3::y());
+        assert_eq!(transform.rotation, rotation);
     }
 }
@@ -1,4 +1,11 @@
-use super::{AppEvent, Event, Key, KeyboardEvent, Modifier, MouseButton, MouseEvent, WindowEvent};
+use std::{collections::VecDeque, path::PathBuf};
+
+use crate::core::ecs::EntityId;
+
+use super::{
+    AppEvent, Event, GamepadAxis, GamepadButton, GamepadEvent, Key, KeyboardEvent, Modifier,
+    MouseButton, MouseEvent, PathEvent, PhysicsEvent, WindowEvent,
+};
 
 pub trait EventDispatcher {
     fn dispatch(&mut self, event: Event);
@@ -28,21 +35,24 @@ impl EventDispatcher for EventDispatcherImpl {
     }
 }
 
+// First in, first out: listeners see the events in the order they happened.
 pub struct EventQueue {
-    events: Vec<Event>,
+    events: VecDeque<Event>,
 }
 
 impl EventQueue {
     pub fn new() -> EventQueue {
-        EventQueue { events: Vec::new() }
+        EventQueue {
+            events: VecDeque::new(),
+        }
     }
     pub fn push(&mut self, event: Event) {
-        self.events.push(event);
+        self.events.push_back(event);
     }
     pub fn pop(&mut self) -> Option<Event> {
-        self.events.pop()
+        self.events.pop_front()
     }
-    pub fn iter(&self) -> std::slice::Iter<Event> {
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<Event> {
         self.events.iter()
     }
     pub fn len(&self) -> usize {
@@ -78,6 +88,24 @@ pub trait EventListener: AsEventListener {
     fn on_window_focus(&mut self, _focused: bool) -> bool {
         false
     }
+    fn on_window_move(&mut self, _x: i32, _y: i32) -> bool {
+        false
+    }
+    fn on_window_iconify(&mut self, _iconified: bool) -> bool {
+        false
+    }
+    fn on_window_maximize(&mut self, _maximized: bool) -> bool {
+        false
+    }
+    fn on_framebuffer_resize(&mut self, _width: u32, _height: u32) -> bool {
+        false
+    }
+    fn on_content_scale(&mut self, _x: f32, _y: f32) -> bool {
+        false
+    }
+    fn on_file_drop(&mut self, _paths: &[PathBuf]) -> bool {
+        false
+    }
 
     // keyboard
     fn on_key_press(&mut self, _key: Key, _mods: Modifier) -> bool {
@@ -89,6 +117,9 @@ pub trait EventListener: AsEventListener {
     fn on_key_repeat(&mut self, _key: Key, _mods: Modifier) -> bool {
         false
     }
+    fn on_char(&mut self, _character: char, _mods: Modifier) -> bool {
+        false
+    }
 
     // mouse
     fn on_mouse_press(&mut self, _button: MouseButton, _mods: Modifier) -> bool {
@@ -106,6 +137,42 @@ pub trait EventListener: AsEventListener {
     fn on_mouse_scroll(&mut self, _xoffset: f64, _yoffset: f64) -> bool {
         false
     }
+    fn on_cursor_enter(&mut self, _entered: bool) -> bool {
+        false
+    }
+
+    // physics
+    fn on_collision_start(&mut self, _a: EntityId, _b: EntityId) -> bool {
+        false
+    }
+    fn on_collision_stop(&mut self, _a: EntityId, _b: EntityId) -> bool {
+        false
+    }
+
+    // gamepad
+    fn on_gamepad_connected(&mut self, _gamepad: usize) -> bool {
+        false
+    }
+    fn on_gamepad_disconnected(&mut self, _gamepad: usize) -> bool {
+        false
+    }
+    fn on_gamepad_press(&mut self, _gamepad: usize, _button: GamepadButton) -> bool {
+        false
+    }
+    fn on_gamepad_release(&mut self, _gamepad: usize, _button: GamepadButton) -> bool {
+        false
+    }
+    fn on_gamepad_axis(&mut self, _gamepad: usize, _axis: GamepadAxis, _value: f32) -> bool {
+        false
+    }
+
+    // path
+    fn on_path_marker(&mut self, _entity: EntityId, _marker: usize) -> bool {
+        false
+    }
+    fn on_path_finished(&mut self, _entity: EntityId) -> bool {
+        false
+    }
 }
 
 pub fn propagate_event(event: Event, listener: &mut dyn EventListener) -> bool {
@@ -115,11 +182,19 @@ pub fn propagate_event(event: Event, listener: &mut dyn EventListener) -> bool {
             WindowEvent::Close => (*listener).on_window_close(),
             WindowEvent::Resize(w, h) => (*listener).on_window_resize(w, h),
             WindowEvent::Focus(focused) => (*listener).on_window_focus(focused),
+            WindowEvent::Move(x, y) => (*listener).on_window_move(x, y),
+            WindowEvent::Iconify(iconified) => (*listener).on_window_iconify(iconified),
+            WindowEvent::Maximize(maximized) => (*listener).on_window_maximize(maximized),
+            WindowEvent::FramebufferResize(w, h) => (*listener).on_framebuffer_resize(w, h),
+            WindowEvent::ContentScale(x, y) => (*listener).on_content_scale(x, y),
+            // the paths stay in the window, the application passes them on
+            WindowEvent::FileDrop(_) => false,
         },
         Event::Keyboard(event) => match event {
             KeyboardEvent::Press(key, mods) => (*listener).on_key_press(key, mods),
             KeyboardEvent::Release(key, mods) => (*listener).on_key_release(key, mods),
             KeyboardEvent::Repeat(key, mods) => (*listener).on_key_repeat(key, mods),
+            KeyboardEvent::Char(character, mods) => (*listener).on_char(character, mods),
         },
         Event::Mouse(event) => match event {
             MouseEvent::Press(button, mods) => (*listener).on_mouse_press(button, mods),
@@ -127,6 +202,26 @@ pub fn propagate_event(event: Event, listener: &mut dyn EventListener) -> bool {
             MouseEvent::Repeat(button, mods) => (*listener).on_mouse_repeat(button, mods),
             MouseEvent::Move(x, y) => (*listener).on_mouse_move(x, y),
             MouseEvent::Scroll(xoffset, yoffset) => (*listener).on_mouse_scroll(xoffset, yoffset),
+            MouseEvent::Enter(entered) => (*listener).on_cursor_enter(entered),
+        },
+        Event::Physics(event) => match event {
+            PhysicsEvent::CollisionStart(a, b) => (*listener).on_collision_start(a, b),
+            PhysicsEvent::CollisionStop(a, b) => (*listener).on_collision_stop(a, b),
+        },
+        Event::Gamepad(event) => match event {
+            GamepadEvent::Connected(gamepad) => (*listener).on_gamepad_connected(gamepad),
+            GamepadEvent::Disconnected(gamepad) => (*listener).on_gamepad_disconnected(gamepad),
+            GamepadEvent::Press(gamepad, button) => (*listener).on_gamepad_press(gamepad, button),
+            GamepadEvent::Release(gamepad, button) => {
+                (*listener).on_gamepad_release(gamepad, button)
+            }
+            GamepadEvent::Axis(gamepad, axis, value) => {
+                (*listener).on_gamepad_axis(gamepad, axis, value)
+            }
+        },
+        Event::Path(event) => match event {
+            PathEvent::Marker(entity, marker) => (*listener).on_path_marker(entity, marker),
+            PathEvent::Finished(entity) => (*listener).on_path_finished(entity),
         },
         _ => false,
     }
@@ -141,3 +236,97 @@ impl<T: EventListener> AsEventListener for T {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records the hooks called, the way the application propagates events.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl EventListener for Recorder {
+        fn on_char(&mut self, character: char, _mods: Modifier) -> bool {
+            self.0.push(character.to_string());
+            false
+        }
+        fn on_window_move(&mut self, x: i32, y: i32) -> bool {
+            self.0.push(format!("move {} {}", x, y));
+            false
+        }
+        fn on_window_iconify(&mut self, iconified: bool) -> bool {
+            self.0.push(format!("iconify {}", iconified));
+            false
+        }
+        fn on_window_maximize(&mut self, maximized: bool) -> bool {
+            self.0.push(format!("maximize {}", maximized));
+            false
+        }
+        fn on_framebuffer_resize(&mut self, width: u32, height: u32) -> bool {
+            self.0.push(format!("framebuffer {} {}", width, height));
+            false
+        }
+        fn on_content_scale(&mut self, x: f32, y: f32) -> bool {
+            self.0.push(format!("scale {} {}", x, y));
+            false
+        }
+        fn on_file_drop(&mut self, _paths: &[PathBuf]) -> bool {
+            self.0.push("drop".to_string());
+            false
+        }
+        fn on_cursor_enter(&mut self, entered: bool) -> bool {
+            self.0.push(format!("enter {}", entered));
+            false
+        }
+    }
+
+    impl GenericEventListener for Recorder {
+        fn on_event(&mut self, event: Event) -> bool {
+            propagate_event(event, self)
+        }
+    }
+
+    fn consume(events: &[Event]) -> Vec<String> {
+        let mut dispatcher = EventDispatcherImpl::new();
+        for event in events {
+            dispatcher.dispatch(*event);
+        }
+        let mut recorder = Recorder::default();
+        dispatcher.consume(&mut recorder);
+        recorder.0
+    }
+
+    #[test]
+    fn typed_text_keeps_its_order() {
+        let events: Vec<Event> = "abc"
+            .chars()
+            .map(|c| Event::Keyboard(KeyboardEvent::Char(c, 0)))
+            .collect();
+        assert_eq!(consume(&events), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn window_events_reach_their_hooks() {
+        let hooks = consume(&[
+            Event::Window(WindowEvent::Move(10, -20)),
+            Event::Window(WindowEvent::Iconify(true)),
+            Event::Window(WindowEvent::Maximize(false)),
+            Event::Window(WindowEvent::FramebufferResize(1600, 1200)),
+            Event::Window(WindowEvent::ContentScale(2.0, 1.5)),
+            Event::Mouse(MouseEvent::Enter(true)),
+            // the paths are passed on by the application, not here
+            Event::Window(WindowEvent::FileDrop(0)),
+        ]);
+        assert_eq!(
+            hooks,
+            [
+                "move 10 -20",
+                "iconify true",
+                "maximize false",
+                "framebuffer 1600 1200",
+                "scale 2 1.5",
+                "enter true",
+            ]
+        );
+    }
+}
@@ -4,12 +4,81 @@ macro_rules! BIT {
     };
 }
 
+// Enum of named inputs, with `ALL`, `name` and the `FromStr`, `Display` and
+// serde implementations going through the variant name, e.g. "Space".
+macro_rules! named_enum {
+    ($(#[$meta:meta])* pub enum $name:ident { $($variant:ident),* $(,)? }) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant)),*
+                }
+            }
+        }
+
+        // Case insensitive
+        impl std::str::FromStr for $name {
+            type Err = ParseNameError;
+
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                let trimmed = name.trim();
+                $name::ALL
+                    .iter()
+                    .copied()
+                    .find(|value| value.name().eq_ignore_ascii_case(trimmed))
+                    .ok_or_else(|| ParseNameError(name.to_string()))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+                name.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+use crate::core::ecs::EntityId;
+
+// Name that matches no variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNameError(pub String);
+
+impl std::fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown input name '{}'", self.0)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Event {
     App(AppEvent),
     Window(WindowEvent),
     Keyboard(KeyboardEvent),
     Mouse(MouseEvent),
+    Physics(PhysicsEvent),
+    Path(PathEvent),
+    Gamepad(GamepadEvent),
     None,
 }
 
@@ -23,6 +92,16 @@ pub enum WindowEvent {
     Close,
     Resize(u32, u32),
     Focus(bool),
+    // position of the top left corner on the screen
+    Move(i32, i32),
+    Iconify(bool),
+    Maximize(bool),
+    // in pixels, differs from `Resize` on high DPI screens
+    FramebufferResize(u32, u32),
+    ContentScale(f32, f32),
+    // index of the drop in the frame, the hook gets its paths from
+    // `Window::dropped_files`
+    FileDrop(usize),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -30,6 +109,9 @@ pub enum KeyboardEvent {
     Press(Key, Modifier),
     Release(Key, Modifier),
     Repeat(Key, Modifier),
+    // text input, after keyboard layout and dead keys, with the modifiers
+    // held
+    Char(char, Modifier),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -39,19 +121,84 @@ pub enum MouseEvent {
     Repeat(MouseButton, Modifier),
     Move(f64, f64),
     Scroll(f64, f64),
+    // cursor entered or left the window
+    Enter(bool),
 }
 
+// Pairs of entities whose colliders started or stopped touching
 #[derive(Debug, Copy, Clone)]
-pub enum MouseButton {
-    Left,
-    Right,
-    Middle,
-    Button4,
-    Button5,
-    Button6,
-    Button7,
-    Button8,
-    Unknown,
+pub enum PhysicsEvent {
+    CollisionStart(EntityId, EntityId),
+    CollisionStop(EntityId, EntityId),
+}
+
+// Gamepads are numbered by their joystick slot, from 0. Sticks range from -1
+// to 1 with +y down, triggers are axes from 0 to 1.
+#[derive(Debug, Copy, Clone)]
+pub enum GamepadEvent {
+    Connected(usize),
+    Disconnected(usize),
+    Press(usize, GamepadButton),
+    Release(usize, GamepadButton),
+    Axis(usize, GamepadAxis, f32),
+}
+
+// Sent by a `PathPlayer`, markers are indices into its path's markers.
+#[derive(Debug, Copy, Clone)]
+pub enum PathEvent {
+    Marker(EntityId, usize),
+    Finished(EntityId),
+}
+
+named_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub enum MouseButton {
+        Left,
+        Right,
+        Middle,
+        Button4,
+        Button5,
+        Button6,
+        Button7,
+        Button8,
+        Unknown,
+    }
+}
+
+// Buttons of a standard gamepad layout, named after the Xbox controller.
+named_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub enum GamepadButton {
+        A,
+        B,
+        X,
+        Y,
+        LeftBumper,
+        RightBumper,
+        Back,
+        Start,
+        Guide,
+        LeftThumb,
+        RightThumb,
+        DpadUp,
+        DpadRight,
+        DpadDown,
+        DpadLeft,
+    }
+}
+
+// Sticks go from -1 to 1 with y pointing down, triggers from 0 released to 1
+// fully pressed.
+named_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub enum GamepadAxis {
+        LeftX,
+        LeftY,
+        RightX,
+        RightY,
+        LeftTrigger,
+        RightTrigger,
+    }
 }
 
 pub type Modifier = u8;
@@ -66,15 +213,16 @@ pub mod modifiers {
     pub const SUPER: Modifier = BIT!(3);
 }
 
+named_enum! {
 #[rustfmt::skip]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Key {
   // letters
-  A, B, C, D, E, F, G, H, I, J, K, L, M, 
+  A, B, C, D, E, F, G, H, I, J, K, L, M,
   N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
 
   // numbers
-  Num0, Num1, Num2, Num3, Num4, Num5, 
+  Num0, Num1, Num2, Num3, Num4, Num5,
   Num6, Num7, Num8, Num9, NumPad0, NumPad1,
   NumPad2, NumPad3, NumPad4, NumPad5, NumPad6,
   NumPad7, NumPad8, NumPad9,
@@ -87,14 +235,15 @@ pub enum Key {
   RControl, RShift, RAlt, RSuper, Menu,
   LBracket, RBracket, Semicolon, Comma, Period,
   Quote, Slash, Backslash, Tilde, Equal, Minus,
-  Plus, Space, Return, Backspace, Tab, PageUp, 
-  PageDown, End, Home, Insert, Delete, Left, 
+  Plus, Space, Return, Backspace, Tab, PageUp,
+  PageDown, End, Home, Insert, Delete, Left,
   Right, Up, Down, NumpadComma, Pause, Enter,
   Apostrophe, CapsLock, PrintScreen, ScrollLock,
   GraveAccent, NumLock, NumPadDivide, NumPadMultiply,
   NumPadSubtract, NumPadAdd, NumPadEnter, NumPadEqual,
   NumPadDecimal,
-  
+
   // unknown
   Unknown,
 }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::event::{modifiers, GamepadAxis, GamepadButton, Key, Modifier, MouseButton};
+
+// Anything that is either held or not.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ButtonInput {
+    Key(Key),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+// Analog inputs. The mouse reports its motion and scroll since the last
+// frame, in pixels and scroll steps.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AxisInput {
+    MouseX,
+    MouseY,
+    ScrollX,
+    ScrollY,
+    Gamepad(GamepadAxis),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModifierKey {
+    Shift,
+    Ctrl,
+    Alt,
+    Super,
+}
+
+impl ModifierKey {
+    pub fn bit(self) -> Modifier {
+        match self {
+            ModifierKey::Shift => modifiers::SHIFT,
+            ModifierKey::Ctrl => modifiers::CTRL,
+            ModifierKey::Alt => modifiers::ALT,
+            ModifierKey::Super => modifiers::SUPER,
+        }
+    }
+}
+
+// Triggers an action while `input` is held along with every modifier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionBinding {
+    pub input: ButtonInput,
+    #[serde(default)]
+    pub modifiers: Vec<ModifierKey>,
+}
+
+impl ActionBinding {
+    pub fn new(input: ButtonInput) -> Self {
+        Self {
+            input,
+            modifiers: Vec::new(),
+        }
+    }
+
+    pub fn key(key: Key) -> Self {
+        Self::new(ButtonInput::Key(key))
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::new(ButtonInput::Mouse(button))
+    }
+
+    pub fn gamepad(button: GamepadButton) -> Self {
+        Self::new(ButtonInput::Gamepad(button))
+    }
+
+    pub fn with_modifier(mut self, modifier: ModifierKey) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+        }
+        self
+    }
+
+    // The required modifiers as a bit mask.
+    pub fn modifier_mask(&self) -> Modifier {
+        self.modifiers
+            .iter()
+            .fold(0, |mask, modifier| mask | modifier.bit())
+    }
+}
+
+// Reads -1 while `negative` is held and 1 while `positive` is, plus the
+// analog `input`, times `scale`. Analog values within `dead_zone` read as 0,
+// gamepad axes are rescaled so they still reach 1 at the edge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative: Option<ButtonInput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub positive: Option<ButtonInput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<AxisInput>,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    #[serde(default)]
+    pub dead_zone: f32,
+}
+
+impl AxisBinding {
+    pub fn buttons(negative: ButtonInput, positive: ButtonInput) -> Self {
+        Self {
+            negative: Some(negative),
+            positive: Some(positive),
+            input: None,
+            scale: 1.0,
+            dead_zone: 0.0,
+        }
+    }
+
+    pub fn keys(negative: Key, positive: Key) -> Self {
+        Self::buttons(ButtonInput::Key(negative), ButtonInput::Key(positive))
+    }
+
+    pub fn axis(input: AxisInput) -> Self {
+        Self {
+            negative: None,
+            positive: None,
+            input: Some(input),
+            scale: 1.0,
+            dead_zone: 0.0,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
@@ -0,0 +1,133 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ActionBinding, AxisBinding, InputError};
+
+// Named actions and axes with their bindings, e.g. "jump" on Space and the
+// gamepad A button. Saved as TOML or RON, picked from the extension.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    pub actions: BTreeMap<String, Vec<ActionBinding>>,
+    #[serde(default)]
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &str) -> Result<Self, InputError> {
+        let format = MapFormat::from_path(path)?;
+        let data = std::fs::read_to_string(path)
+            .map_err(|_| InputError::FileNotFound(path.to_string()))?;
+        let parsing_failed =
+            |err: &dyn std::fmt::Display| InputError::ParsingFailed(format!("{}: {}", path, err));
+
+        match format {
+            MapFormat::Toml => toml::from_str(&data).map_err(|err| parsing_failed(&err)),
+            MapFormat::Ron => ron::from_str(&data).map_err(|err| parsing_failed(&err)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), InputError> {
+        let serialization_failed =
+            |err: &dyn std::fmt::Display| InputError::SerializationFailed(err.to_string());
+        let data = match MapFormat::from_path(path)? {
+            MapFormat::Toml => {
+                toml::to_string_pretty(self).map_err(|err| serialization_failed(&err))?
+            }
+            MapFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|err| serialization_failed(&err))?,
+        };
+
+        std::fs::write(path, data)
+            .map_err(|err| InputError::SerializationFailed(format!("{}: {}", path, err)))
+    }
+
+    pub fn with_action(mut self, name: &str, binding: ActionBinding) -> Self {
+        self.bind_action(name, binding);
+        self
+    }
+
+    pub fn with_axis(mut self, name: &str, binding: AxisBinding) -> Self {
+        self.bind_axis(name, binding);
+        self
+    }
+
+    // Adds a binding, the action fires from any of them.
+    pub fn bind_action(&mut self, name: &str, binding: ActionBinding) {
+        let bindings = self.actions.entry(name.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    // Replaces every binding of the action, e.g. from an options menu.
+    pub fn rebind_action(&mut self, name: &str, bindings: Vec<ActionBinding>) {
+        self.actions.insert(name.to_string(), bindings);
+    }
+
+    pub fn unbind_action(&mut self, name: &str, binding: &ActionBinding) -> bool {
+        let Some(bindings) = self.actions.get_mut(name) else {
+            return false;
+        };
+        let len = bindings.len();
+        bindings.retain(|other| other != binding);
+        bindings.len() != len
+    }
+
+    pub fn remove_action(&mut self, name: &str) -> Option<Vec<ActionBinding>> {
+        self.actions.remove(name)
+    }
+
+    pub fn action(&self, name: &str) -> &[ActionBinding] {
+        self.actions.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    // The axis reads the binding with the largest value.
+    pub fn bind_axis(&mut self, name: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(name.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn rebind_axis(&mut self, name: &str, bindings: Vec<AxisBinding>) {
+        self.axes.insert(name.to_string(), bindings);
+    }
+
+    pub fn unbind_axis(&mut self, name: &str, binding: &AxisBinding) -> bool {
+        let Some(bindings) = self.axes.get_mut(name) else {
+            return false;
+        };
+        let len = bindings.len();
+        bindings.retain(|other| other != binding);
+        bindings.len() != len
+    }
+
+    pub fn remove_axis(&mut self, name: &str) -> Option<Vec<AxisBinding>> {
+        self.axes.remove(name)
+    }
+
+    pub fn axis(&self, name: &str) -> &[AxisBinding] {
+        self.axes.get(name).map_or(&[], Vec::as_slice)
+    }
+}
+
+enum MapFormat {
+    Toml,
+    Ron,
+}
+
+impl MapFormat {
+    fn from_path(path: &str) -> Result<Self, InputError> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(MapFormat::Toml),
+            Some("ron") => Ok(MapFormat::Ron),
+            _ => Err(InputError::UnsupportedFormat(path.to_string())),
+        }
+    }
+}
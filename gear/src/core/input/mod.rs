@@ -0,0 +1,363 @@
+mod binding;
+mod map;
+mod state;
+
+use std::collections::HashMap;
+
+pub use binding::{ActionBinding, AxisBinding, AxisInput, ButtonInput, ModifierKey};
+pub use map::InputMap;
+pub use state::InputState;
+
+#[derive(Debug)]
+pub enum InputError {
+    FileNotFound(String),
+    UnsupportedFormat(String),
+    ParsingFailed(String),
+    SerializationFailed(String),
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct ActionState {
+    down: bool,
+    pressed: bool,
+    released: bool,
+}
+
+// Actions and axes of the input map, evaluated once per frame from the
+// input state.
+pub struct Input {
+    map: InputMap,
+    actions: HashMap<String, ActionState>,
+    axes: HashMap<String, f32>,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self {
+            map: InputMap::new(),
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    // Bindings changed here apply from the next frame.
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    pub fn set_map(&mut self, map: InputMap) {
+        self.map = map;
+    }
+
+    pub fn load_map(&mut self, path: &str) -> Result<(), InputError> {
+        self.map = InputMap::load(path)?;
+        Ok(())
+    }
+
+    pub fn save_map(&self, path: &str) -> Result<(), InputError> {
+        self.map.save(path)
+    }
+
+    pub fn action_down(&self, name: &str) -> bool {
+        self.action(name).down
+    }
+
+    // A binding was pressed since the previous frame, even if released
+    // again before it.
+    pub fn action_pressed(&self, name: &str) -> bool {
+        self.action(name).pressed
+    }
+
+    pub fn action_released(&self, name: &str) -> bool {
+        self.action(name).released
+    }
+
+    // 0 for unknown axes.
+    pub fn axis(&self, name: &str) -> f32 {
+        self.axes.get(name).copied().unwrap_or(0.0)
+    }
+
+    // Called by the application at the start of every frame, after the
+    // state.
+    pub(crate) fn update(&mut self, state: &InputState) {
+        let held = state.modifiers();
+        let mut actions = HashMap::with_capacity(self.map.actions.len());
+        for (name, bindings) in &self.map.actions {
+            let modifiers_held = |binding: &ActionBinding| {
+                let mask = binding.modifier_mask();
+                held & mask == mask
+            };
+            let down = bindings
+                .iter()
+                .any(|binding| is_down(state, binding.input) && modifiers_held(binding));
+            // presses and releases come from the frame sets, so a tap between
+            // two frames still counts
+            let pressed = bindings
+                .iter()
+                .any(|binding| just_pressed(state, binding.input) && modifiers_held(binding));
+            let was_down = self.actions.get(name).is_some_and(|state| state.down);
+            let input_released = bindings
+                .iter()
+                .any(|binding| just_released(state, binding.input));
+            // letting go of a modifier releases the action too
+            let released = (input_released && (was_down || pressed)) || (was_down && !down);
+            actions.insert(
+                name.clone(),
+                ActionState {
+                    down,
+                    pressed,
+                    released,
+                },
+            );
+        }
+        self.actions = actions;
+
+        // the binding with the largest value wins
+        self.axes = self
+            .map
+            .axes
+            .iter()
+            .map(|(name, bindings)| {
+                let value = bindings
+                    .iter()
+                    .map(|binding| binding_value(state, binding))
+                    .fold(0.0, |value: f32, other| {
+                        if other.abs() > value.abs() {
+                            other
+                        } else {
+                            value
+                        }
+                    });
+                (name.clone(), value)
+            })
+            .collect();
+    }
+
+    fn action(&self, name: &str) -> ActionState {
+        self.actions.get(name).copied().unwrap_or_default()
+    }
+}
+
+fn is_down(state: &InputState, input: ButtonInput) -> bool {
+    match input {
+        ButtonInput::Key(key) => state.is_key_down(key),
+        ButtonInput::Mouse(button) => state.is_mouse_down(button),
+        ButtonInput::Gamepad(button) => state
+            .gamepads()
+            .any(|gamepad| state.is_gamepad_down(gamepad, button)),
+    }
+}
+
+fn just_pressed(state: &InputState, input: ButtonInput) -> bool {
+    match input {
+        ButtonInput::Key(key) => state.just_pressed(key),
+        ButtonInput::Mouse(button) => state.mouse_just_pressed(button),
+        ButtonInput::Gamepad(button) => state
+            .gamepads()
+            .any(|gamepad| state.gamepad_just_pressed(gamepad, button)),
+    }
+}
+
+fn just_released(state: &InputState, input: ButtonInput) -> bool {
+    match input {
+        ButtonInput::Key(key) => state.just_released(key),
+        ButtonInput::Mouse(button) => state.mouse_just_released(button),
+        ButtonInput::Gamepad(button) => state
+            .gamepads()
+            .any(|gamepad| state.gamepad_just_released(gamepad, button)),
+    }
+}
+
+fn axis_value(state: &InputState, input: AxisInput) -> f32 {
+    match input {
+        AxisInput::MouseX => state.mouse_delta().0 as f32,
+        AxisInput::MouseY => state.mouse_delta().1 as f32,
+        AxisInput::ScrollX => state.scroll_delta().0 as f32,
+        AxisInput::ScrollY => state.scroll_delta().1 as f32,
+        // any connected gamepad, the one pushed furthest wins
+        AxisInput::Gamepad(axis) => state
+            .gamepads()
+            .map(|gamepad| state.gamepad_axis(gamepad, axis))
+            .fold(0.0, |value: f32, other| {
+                if other.abs() > value.abs() {
+                    other
+                } else {
+                    value
+                }
+            }),
+    }
+}
+
+fn binding_value(state: &InputState, binding: &AxisBinding) -> f32 {
+    let held = |input: Option<ButtonInput>| input.is_some_and(|input| is_down(state, input));
+    let mut value = match (held(binding.negative), held(binding.positive)) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+
+    if let Some(input) = binding.input {
+        let analog = axis_value(state, input);
+        let dead_zone = binding.dead_zone;
+        value += match input {
+            _ if analog.abs() <= dead_zone => 0.0,
+            AxisInput::Gamepad(_) if dead_zone < 1.0 => {
+                analog.signum() * (analog.abs() - dead_zone) / (1.0 - dead_zone)
+            }
+            _ => analog,
+        };
+    }
+    value * binding.scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::{
+        modifiers, Event, EventDispatcher, EventDispatcherImpl, GamepadButton, GamepadEvent,
+        GenericEventListener, Key, KeyboardEvent, MouseEvent,
+    };
+
+    // Feeds the state the way the application does, through the dispatcher.
+    struct Feed<'a>(&'a mut InputState);
+
+    impl GenericEventListener for Feed<'_> {
+        fn on_event(&mut self, event: Event) -> bool {
+            self.0.on_event(&event);
+            false
+        }
+    }
+
+    fn key(press: bool, key: Key) -> Event {
+        Event::Keyboard(if press {
+            KeyboardEvent::Press(key, 0)
+        } else {
+            KeyboardEvent::Release(key, 0)
+        })
+    }
+
+    fn frame(input: &mut Input, state: &mut InputState, events: &[Event]) {
+        let mut dispatcher = EventDispatcherImpl::new();
+        for event in events {
+            dispatcher.dispatch(*event);
+        }
+        dispatcher.consume(&mut Feed(state));
+        state.update();
+        input.update(state);
+    }
+
+    fn edges(input: &Input, name: &str) -> (bool, bool, bool) {
+        (
+            input.action_pressed(name),
+            input.action_down(name),
+            input.action_released(name),
+        )
+    }
+
+    #[test]
+    fn tap_between_frames() {
+        let mut input = Input::new();
+        input
+            .map_mut()
+            .bind_action("jump", ActionBinding::key(Key::Space));
+        let mut state = InputState::new();
+
+        frame(
+            &mut input,
+            &mut state,
+            &[key(true, Key::Space), key(false, Key::Space)],
+        );
+        assert_eq!(edges(&input, "jump"), (true, false, true));
+
+        frame(&mut input, &mut state, &[]);
+        assert_eq!(edges(&input, "jump"), (false, false, false));
+    }
+
+    #[test]
+    fn dispatched_in_order() {
+        let mut input = Input::new();
+        let mut state = InputState::new();
+        frame(
+            &mut input,
+            &mut state,
+            &[
+                key(true, Key::A),
+                key(false, Key::A),
+                Event::Mouse(MouseEvent::Move(10.0, 10.0)),
+                Event::Mouse(MouseEvent::Move(15.0, 20.0)),
+                Event::Mouse(MouseEvent::Move(30.0, 25.0)),
+            ],
+        );
+
+        // the release came last, nothing stays stuck
+        assert!(!state.is_key_down(Key::A));
+        assert!(state.just_pressed(Key::A) && state.just_released(Key::A));
+        assert_eq!(state.mouse_position(), Some((30.0, 25.0)));
+        assert_eq!(state.mouse_delta(), (20.0, 15.0));
+    }
+
+    #[test]
+    fn hold_and_release() {
+        let mut input = Input::new();
+        input
+            .map_mut()
+            .bind_action("jump", ActionBinding::key(Key::Space));
+        let mut state = InputState::new();
+
+        frame(&mut input, &mut state, &[key(true, Key::Space)]);
+        assert_eq!(edges(&input, "jump"), (true, true, false));
+        frame(&mut input, &mut state, &[]);
+        assert_eq!(edges(&input, "jump"), (false, true, false));
+        frame(&mut input, &mut state, &[key(false, Key::Space)]);
+        assert_eq!(edges(&input, "jump"), (false, false, true));
+    }
+
+    #[test]
+    fn modifier_released_first() {
+        let mut input = Input::new();
+        input.map_mut().bind_action(
+            "save",
+            ActionBinding::key(Key::S).with_modifier(ModifierKey::Ctrl),
+        );
+        let mut state = InputState::new();
+
+        frame(
+            &mut input,
+            &mut state,
+            &[key(true, Key::LControl), key(true, Key::S)],
+        );
+        assert_eq!(edges(&input, "save"), (true, true, false));
+        assert_eq!(state.modifiers(), modifiers::CTRL);
+
+        frame(&mut input, &mut state, &[key(false, Key::LControl)]);
+        assert_eq!(edges(&input, "save"), (false, false, true));
+
+        // S alone is still held, the modifier coming back is no press
+        frame(&mut input, &mut state, &[key(true, Key::LControl)]);
+        assert_eq!(edges(&input, "save"), (false, true, false));
+    }
+
+    #[test]
+    fn gamepad_tap() {
+        let mut input = Input::new();
+        input
+            .map_mut()
+            .bind_action("jump", ActionBinding::gamepad(GamepadButton::A));
+        let mut state = InputState::new();
+
+        frame(
+            &mut input,
+            &mut state,
+            &[
+                Event::Gamepad(GamepadEvent::Connected(1)),
+                Event::Gamepad(GamepadEvent::Press(1, GamepadButton::A)),
+                Event::Gamepad(GamepadEvent::Release(1, GamepadButton::A)),
+            ],
+        );
+        assert_eq!(edges(&input, "jump"), (true, false, true));
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::core::event::{
+    modifiers, Event, GamepadAxis, GamepadButton, GamepadEvent, Key, KeyboardEvent, Modifier,
+    MouseButton, MouseEvent, WindowEvent,
+};
+
+// Held buttons of one device, with the presses and releases of the last
+// frame. Events arrive between frames and are moved to the frame sets by
+// `update`.
+#[derive(Debug)]
+struct Buttons<T> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+    frame_pressed: HashSet<T>,
+    frame_released: HashSet<T>,
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    fn new() -> Self {
+        Self {
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            frame_pressed: HashSet::new(),
+            frame_released: HashSet::new(),
+        }
+    }
+
+    fn press(&mut self, button: T) {
+        if self.down.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.down.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+    }
+
+    fn update(&mut self) {
+        self.frame_pressed = std::mem::take(&mut self.pressed);
+        self.frame_released = std::mem::take(&mut self.released);
+    }
+}
+
+// Gamepad slots GLFW has.
+const GAMEPADS: usize = 16;
+
+#[derive(Debug)]
+struct Gamepad {
+    connected: bool,
+    buttons: Buttons<GamepadButton>,
+    axes: [f32; GamepadAxis::ALL.len()],
+}
+
+impl Gamepad {
+    fn new() -> Self {
+        Self {
+            connected: false,
+            buttons: Buttons::new(),
+            axes: [0.0; GamepadAxis::ALL.len()],
+        }
+    }
+}
+
+// Keyboard, mouse and gamepads as of the current frame, kept from the window events so
+// code can poll instead of tracking presses itself.
+#[derive(Debug)]
+pub struct InputState {
+    keys: Buttons<Key>,
+    buttons: Buttons<MouseButton>,
+    cursor: Option<(f64, f64)>,
+    motion: (f64, f64),
+    scroll: (f64, f64),
+    frame_motion: (f64, f64),
+    frame_scroll: (f64, f64),
+    gamepads: Vec<Gamepad>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self {
+            keys: Buttons::new(),
+            buttons: Buttons::new(),
+            cursor: None,
+            motion: (0.0, 0.0),
+            scroll: (0.0, 0.0),
+            frame_motion: (0.0, 0.0),
+            frame_scroll: (0.0, 0.0),
+            gamepads: (0..GAMEPADS).map(|_| Gamepad::new()).collect(),
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.down.contains(&key)
+    }
+
+    // Pressed since the previous frame. A key pressed and released in
+    // between is both just pressed and just released.
+    pub fn just_pressed(&self, key: Key) -> bool {
+        self.keys.frame_pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: Key) -> bool {
+        self.keys.frame_released.contains(&key)
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons.down.contains(&button)
+    }
+
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.frame_pressed.contains(&button)
+    }
+
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        self.buttons.frame_released.contains(&button)
+    }
+
+    pub fn keys_down(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys.down.iter().copied()
+    }
+
+    // In window coordinates, `None` until the cursor first moves.
+    pub fn mouse_position(&self) -> Option<(f64, f64)> {
+        self.cursor
+    }
+
+    // Cursor motion during the last frame, in pixels.
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.frame_motion
+    }
+
+    pub fn scroll_delta(&self) -> (f64, f64) {
+        self.frame_scroll
+    }
+
+    // Modifier keys held, as `modifiers` bits.
+    pub fn modifiers(&self) -> Modifier {
+        let held = |left, right| self.is_key_down(left) || self.is_key_down(right);
+        let mut mask = 0;
+        if held(Key::LShift, Key::RShift) {
+            mask |= modifiers::SHIFT;
+        }
+        if held(Key::LControl, Key::RControl) {
+            mask |= modifiers::CTRL;
+        }
+        if held(Key::LAlt, Key::RAlt) {
+            mask |= modifiers::ALT;
+        }
+        if held(Key::LSuper, Key::RSuper) {
+            mask |= modifiers::SUPER;
+        }
+        mask
+    }
+
+    // Indices of the connected gamepads.
+    pub fn gamepads(&self) -> impl Iterator<Item = usize> + '_ {
+        self.gamepads
+            .iter()
+            .enumerate()
+            .filter(|(_, gamepad)| gamepad.connected)
+            .map(|(index, _)| index)
+    }
+
+    pub fn is_gamepad_connected(&self, gamepad: usize) -> bool {
+        self.gamepad(gamepad).is_some()
+    }
+
+    pub fn is_gamepad_down(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad)
+            .is_some_and(|state| state.buttons.down.contains(&button))
+    }
+
+    pub fn gamepad_just_pressed(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad)
+            .is_some_and(|state| state.buttons.frame_pressed.contains(&button))
+    }
+
+    pub fn gamepad_just_released(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad)
+            .is_some_and(|state| state.buttons.frame_released.contains(&button))
+    }
+
+    // 0 when the gamepad isn't connected.
+    pub fn gamepad_axis(&self, gamepad: usize, axis: GamepadAxis) -> f32 {
+        self.gamepad(gamepad)
+            .map_or(0.0, |state| state.axes[axis as usize])
+    }
+
+    fn gamepad(&self, gamepad: usize) -> Option<&Gamepad> {
+        self.gamepads.get(gamepad).filter(|state| state.connected)
+    }
+
+    pub(crate) fn on_event(&mut self, event: &Event) {
+        match *event {
+            Event::Keyboard(KeyboardEvent::Press(key, _)) => self.keys.press(key),
+            Event::Keyboard(KeyboardEvent::Release(key, _)) => self.keys.release(key),
+            Event::Mouse(MouseEvent::Press(button, _)) => self.buttons.press(button),
+            Event::Mouse(MouseEvent::Release(button, _)) => self.buttons.release(button),
+            Event::Mouse(MouseEvent::Move(x, y)) => {
+                if let Some((last_x, last_y)) = self.cursor {
+                    self.motion.0 += x - last_x;
+                    self.motion.1 += y - last_y;
+                }
+                self.cursor = Some((x, y));
+            }
+            Event::Mouse(MouseEvent::Scroll(x, y)) => {
+                self.scroll.0 += x;
+                self.scroll.1 += y;
+            }
+            // the releases happen in another window, nothing stays held and
+            // the cursor doesn't jump when it comes back
+            Event::Window(WindowEvent::Focus(false)) => {
+                self.keys.release_all();
+                self.buttons.release_all();
+                self.cursor = None;
+            }
+            Event::Gamepad(event) => self.on_gamepad_event(event),
+            _ => {}
+        }
+    }
+
+    fn on_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(index) => {
+                if let Some(gamepad) = self.gamepads.get_mut(index) {
+                    gamepad.connected = true;
+                }
+            }
+            // the window sends the releases before
+            GamepadEvent::Disconnected(index) => {
+                if let Some(gamepad) = self.gamepads.get_mut(index) {
+                    gamepad.connected = false;
+                    gamepad.axes = [0.0; GamepadAxis::ALL.len()];
+                }
+            }
+            GamepadEvent::Press(index, button) => {
+                if let Some(gamepad) = self.gamepads.get_mut(index) {
+                    gamepad.buttons.press(button);
+                }
+            }
+            GamepadEvent::Release(index, button) => {
+                if let Some(gamepad) = self.gamepads.get_mut(index) {
+                    gamepad.buttons.release(button);
+                }
+            }
+            GamepadEvent::Axis(index, axis, value) => {
+                if let Some(gamepad) = self.gamepads.get_mut(index) {
+                    gamepad.axes[axis as usize] = value;
+                }
+            }
+        }
+    }
+
+    // Called by the application at the start of every frame.
+    pub(crate) fn update(&mut self) {
+        self.keys.update();
+        self.buttons.update();
+        for gamepad in &mut self.gamepads {
+            gamepad.buttons.update();
+        }
+        self.frame_motion = std::mem::take(&mut self.motion);
+        self.frame_scroll = std::mem::take(&mut self.scroll);
+    }
+}
@@ -34,6 +34,14 @@ impl LayerStackImpl {
     pub fn iter_mut(&mut self) -> std::slice::IterMut<Box<dyn Layer>> {
         self.layers.iter_mut()
     }
+
+    // Dropped files don't fit in an `Event`, they are passed on apart.
+    pub fn on_file_drop(&mut self, paths: &[std::path::PathBuf]) -> bool {
+        self.layers
+            .iter_mut()
+            .rev()
+            .any(|layer| layer.on_file_drop(paths))
+    }
 }
 
 impl LayerStack for LayerStackImpl {
@@ -0,0 +1,311 @@
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use super::Ray;
+
+// Intersection tests return the ray parameter of the closest hit in front of
+// the origin, a ray starting inside a volume hits it at 0.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.inf(&point),
+            max: aabb.max.sup(&point),
+        }))
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn contains(&self, point: &Vector3<f32>) -> bool {
+        (0..3).all(|i| point[i] >= self.min[i] && point[i] <= self.max[i])
+    }
+
+    // Box enclosing the eight transformed corners.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        let corners = (0..8).map(|i| {
+            let corner = Vector3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            matrix.transform_point(&Point3::from(corner)).coords
+        });
+        // there are always eight corners
+        Self::from_points(corners).unwrap()
+    }
+
+    // Slab test.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+
+        for i in 0..3 {
+            if ray.direction[i].abs() <= f32::EPSILON {
+                if ray.origin[i] < self.min[i] || ray.origin[i] > self.max[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = 1.0 / ray.direction[i];
+            let t0 = (self.min[i] - ray.origin[i]) * inverse;
+            let t1 = (self.max[i] - ray.origin[i]) * inverse;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let offset = ray.origin - self.center;
+        let a = ray.direction.norm_squared();
+        let b = offset.dot(&ray.direction);
+        let c = offset.norm_squared() - self.radius * self.radius;
+
+        let discriminant = b * b - a * c;
+        if a <= f32::EPSILON || discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let near = (-b - root) / a;
+        let far = (-b + root) / a;
+        if far < 0.0 {
+            None
+        } else {
+            Some(near.max(0.0))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle {
+    pub a: Vector3<f32>,
+    pub b: Vector3<f32>,
+    pub c: Vector3<f32>,
+}
+
+impl Triangle {
+    pub fn new(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Self {
+        Self { a, b, c }
+    }
+
+    // Counter-clockwise winding faces the viewer, like OpenGL. Zero for
+    // degenerate triangles.
+    pub fn normal(&self) -> Vector3<f32> {
+        (self.b - self.a)
+            .cross(&(self.c - self.a))
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros)
+    }
+
+    // Möller-Trumbore, both faces are hit.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let edge1 = self.b - self.a;
+        let edge2 = self.c - self.a;
+
+        let p = ray.direction.cross(&edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() <= f32::EPSILON * edge1.norm() * edge2.norm() {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+
+        let offset = ray.origin - self.a;
+        let u = offset.dot(&p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = offset.cross(&edge1);
+        let v = ray.direction.dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(&q) * inverse;
+        if t < 0.0 {
+            None
+        } else {
+            Some(t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray::new(Vector3::from(origin), Vector3::from(direction))
+    }
+
+    fn approx(a: Option<f32>, b: f32) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-5)
+    }
+
+    #[test]
+    fn aabb_ray() {
+        let aabb = unit_box();
+        assert!(approx(
+            aabb.intersect_ray(&ray([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0])),
+            4.0
+        ));
+        assert!(approx(
+            aabb.intersect_ray(&ray([0.0, 0.0, 0.0], [0.0, 1.0, 0.0])),
+            0.0
+        ));
+        assert_eq!(
+            aabb.intersect_ray(&ray([-5.0, 0.0, 0.0], [-1.0, 0.0, 0.0])),
+            None
+        );
+        assert_eq!(
+            aabb.intersect_ray(&ray([-5.0, 3.0, 0.0], [1.0, 1.0, 0.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn aabb_ray_parallel_to_slab() {
+        let aabb = unit_box();
+        // along x, so y and z have no slab crossing
+        assert!(approx(
+            aabb.intersect_ray(&ray([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0])),
+            4.0
+        ));
+        assert_eq!(
+            aabb.intersect_ray(&ray([-5.0, 2.0, 0.0], [1.0, 0.0, 0.0])),
+            None
+        );
+        // grazing a face still hits
+        assert!(approx(
+            aabb.intersect_ray(&ray([-5.0, 1.0, 0.0], [1.0, 0.0, 0.0])),
+            4.0
+        ));
+    }
+
+    #[test]
+    fn aabb_transform_encloses_rotated_box() {
+        let rotation = Matrix4::new_rotation(Vector3::z() * std::f32::consts::FRAC_PI_4);
+        let aabb = unit_box().transform(&rotation);
+        let half_diagonal = 2.0f32.sqrt();
+        assert!((aabb.max.x - half_diagonal).abs() < 1e-5);
+        assert!((aabb.min.y + half_diagonal).abs() < 1e-5);
+        assert!((aabb.max.z - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sphere_ray() {
+        let sphere = Sphere::new(Vector3::new(0.0, 0.0, -5.0), 1.0);
+        assert!(approx(
+            sphere.intersect_ray(&ray([0.0, 0.0, 0.0], [0.0, 0.0, -1.0])),
+            4.0
+        ));
+        assert!(approx(
+            sphere.intersect_ray(&ray([0.0, 0.0, -5.0], [0.0, 1.0, 0.0])),
+            0.0
+        ));
+        assert_eq!(
+            sphere.intersect_ray(&ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0])),
+            None
+        );
+        assert_eq!(
+            sphere.intersect_ray(&ray([0.0, 2.0, 0.0], [0.0, 0.0, -1.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn sphere_ray_with_zero_direction() {
+        let sphere = Sphere::new(Vector3::zeros(), 1.0);
+        let ray = Ray {
+            origin: Vector3::new(0.0, 0.0, 5.0),
+            direction: Vector3::zeros(),
+        };
+        assert_eq!(sphere.intersect_ray(&ray), None);
+    }
+
+    #[test]
+    fn triangle_ray() {
+        let triangle = Triangle::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(triangle.normal(), Vector3::z());
+
+        // both faces
+        assert!(approx(
+            triangle.intersect_ray(&ray([0.2, 0.2, 3.0], [0.0, 0.0, -1.0])),
+            3.0
+        ));
+        assert!(approx(
+            triangle.intersect_ray(&ray([0.2, 0.2, -3.0], [0.0, 0.0, 1.0])),
+            3.0
+        ));
+        // outside, behind and along the plane
+        assert_eq!(
+            triangle.intersect_ray(&ray([0.8, 0.8, 3.0], [0.0, 0.0, -1.0])),
+            None
+        );
+        assert_eq!(
+            triangle.intersect_ray(&ray([0.2, 0.2, 3.0], [0.0, 0.0, 1.0])),
+            None
+        );
+        assert_eq!(
+            triangle.intersect_ray(&ray([-1.0, 0.2, 0.0], [1.0, 0.0, 0.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn degenerate_triangle() {
+        let triangle = Triangle::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+        );
+        assert_eq!(triangle.normal(), Vector3::zeros());
+        assert_eq!(
+            triangle.intersect_ray(&ray([0.5, 0.0, 1.0], [0.0, 0.0, -1.0])),
+            None
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+// Remaps progress in [0, 1], e.g. between two keys of an animation.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    // Cubic, slow start
+    EaseIn,
+    // Cubic, slow end
+    EaseOut,
+    EaseInOut,
+    // Holds the start value until the end
+    Step,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+            Easing::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::Step,
+    ];
+
+    #[test]
+    fn ends_and_clamping() {
+        for easing in ALL {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(2.0), 1.0, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn monotonic() {
+        for easing in ALL {
+            let mut previous = 0.0;
+            for i in 0..=100 {
+                let value = easing.apply(i as f32 / 100.0);
+                assert!(value >= previous, "{:?} at {}", easing, i);
+                previous = value;
+            }
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!((Easing::EaseIn.apply(0.3) + Easing::EaseOut.apply(0.7) - 1.0).abs() < 1e-6);
+        assert_eq!(Easing::Step.apply(0.99), 0.0);
+    }
+}
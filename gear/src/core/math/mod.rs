@@ -0,0 +1,9 @@
+mod bounds;
+mod easing;
+mod ray;
+mod spline;
+
+pub use bounds::{Aabb, Sphere, Triangle};
+pub use easing::Easing;
+pub use ray::Ray;
+pub use spline::{bezier, catmull_rom, Bezier, CatmullRom};
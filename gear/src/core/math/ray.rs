@@ -0,0 +1,47 @@
+use nalgebra::{Matrix4, Point3, Vector3};
+
+// Points along the ray are `origin + t * direction`. Rays built with `new` have
+// a unit direction so `t` is a distance; `transform` keeps the direction
+// unnormalized so that `t` stays the same in both spaces.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        self.origin + self.direction * t
+    }
+
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        Self {
+            origin: matrix.transform_point(&Point3::from(self.origin)).coords,
+            direction: matrix.transform_vector(&self.direction),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_keeps_the_parameter() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -2.0));
+        assert_eq!(ray.direction, Vector3::new(0.0, 0.0, -1.0));
+
+        let matrix = Matrix4::new_translation(&Vector3::new(1.0, 0.0, 0.0))
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 2.0, 4.0));
+        let local = ray.transform(&matrix);
+        let world = matrix.transform_point(&Point3::from(ray.at(3.0))).coords;
+        assert!((local.at(3.0) - world).norm() < 1e-5);
+    }
+}
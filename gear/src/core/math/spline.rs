@@ -0,0 +1,252 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Curve through every point. `alpha` picks the parameterization: 0 uniform,
+// 0.5 centripetal (no cusps or loops on uneven spacing), 1 chordal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatmullRom {
+    pub points: Vec<Vector3<f32>>,
+    pub alpha: f32,
+    // Joins the last point back to the first
+    pub closed: bool,
+}
+
+impl CatmullRom {
+    pub fn new(points: Vec<Vector3<f32>>) -> Self {
+        Self {
+            points,
+            alpha: 0.5,
+            closed: false,
+        }
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn segments(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            len if self.closed => len,
+            len => len - 1,
+        }
+    }
+
+    // Point at `u` in [0, 1] between `points[segment]` and the next one. The
+    // open ends are extended by mirroring their neighbour.
+    pub fn point(&self, segment: usize, u: f32) -> Vector3<f32> {
+        let len = self.points.len();
+        if len < 2 {
+            return self.points.first().copied().unwrap_or_else(Vector3::zeros);
+        }
+
+        let at = |index: isize| -> Vector3<f32> {
+            if self.closed {
+                return self.points[index.rem_euclid(len as isize) as usize];
+            }
+            match index {
+                -1 => 2.0 * self.points[0] - self.points[1],
+                i if i as usize >= len => 2.0 * self.points[len - 1] - self.points[len - 2],
+                i => self.points[i as usize],
+            }
+        };
+
+        let i = segment.min(self.segments() - 1) as isize;
+        catmull_rom(at(i - 1), at(i), at(i + 1), at(i + 2), self.alpha, u)
+    }
+
+    // Point at `t` in [0, 1] along the whole curve, every segment takes the
+    // same share of `t`.
+    pub fn sample(&self, t: f32) -> Vector3<f32> {
+        let (segment, u) = locate(self.segments(), t);
+        self.point(segment, u)
+    }
+}
+
+// Cubic Bézier segments sharing their end points: anchor, control, control,
+// anchor, control, control, anchor... Trailing points short of a segment are
+// ignored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bezier {
+    pub points: Vec<Vector3<f32>>,
+}
+
+impl Bezier {
+    pub fn new(points: Vec<Vector3<f32>>) -> Self {
+        Self { points }
+    }
+
+    pub fn segments(&self) -> usize {
+        self.points.len().saturating_sub(1) / 3
+    }
+
+    pub fn point(&self, segment: usize, u: f32) -> Vector3<f32> {
+        if self.segments() == 0 {
+            return self.points.first().copied().unwrap_or_else(Vector3::zeros);
+        }
+
+        let i = segment.min(self.segments() - 1) * 3;
+        let p = &self.points;
+        bezier(p[i], p[i + 1], p[i + 2], p[i + 3], u)
+    }
+
+    pub fn sample(&self, t: f32) -> Vector3<f32> {
+        let (segment, u) = locate(self.segments(), t);
+        self.point(segment, u)
+    }
+}
+
+// Catmull-Rom segment from `p1` to `p2`, Barry and Goldman's pyramidal form.
+pub fn catmull_rom(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    alpha: f32,
+    u: f32,
+) -> Vector3<f32> {
+    // coincident points would divide by zero
+    let knot = |a: &Vector3<f32>, b: &Vector3<f32>| (b - a).norm().powf(alpha).max(1e-4);
+    let t0 = 0.0;
+    let t1 = t0 + knot(&p0, &p1);
+    let t2 = t1 + knot(&p1, &p2);
+    let t3 = t2 + knot(&p2, &p3);
+    let t = t1 + (t2 - t1) * u;
+
+    let lerp = |a: Vector3<f32>, b: Vector3<f32>, ta: f32, tb: f32| {
+        a * ((tb - t) / (tb - ta)) + b * ((t - ta) / (tb - ta))
+    };
+    let a1 = lerp(p0, p1, t0, t1);
+    let a2 = lerp(p1, p2, t1, t2);
+    let a3 = lerp(p2, p3, t2, t3);
+    let b1 = lerp(a1, a2, t0, t2);
+    let b2 = lerp(a2, a3, t1, t3);
+    lerp(b1, b2, t1, t2)
+}
+
+pub fn bezier(
+    p0: Vector3<f32>,
+    c0: Vector3<f32>,
+    c1: Vector3<f32>,
+    p1: Vector3<f32>,
+    u: f32,
+) -> Vector3<f32> {
+    let v = 1.0 - u;
+    p0 * (v * v * v) + c0 * (3.0 * v * v * u) + c1 * (3.0 * v * u * u) + p1 * (u * u * u)
+}
+
+// Segment and position in it for `t` in [0, 1] over `segments`.
+fn locate(segments: usize, t: f32) -> (usize, f32) {
+    if segments == 0 {
+        return (0, 0.0);
+    }
+
+    let scaled = t.clamp(0.0, 1.0) * segments as f32;
+    let segment = (scaled as usize).min(segments - 1);
+    (segment, scaled - segment as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    fn points() -> Vec<Vector3<f32>> {
+        vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(3.0, 2.0, 1.0),
+            Vector3::new(4.0, 0.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_the_points() {
+        for alpha in [0.0, 0.5, 1.0] {
+            let curve = CatmullRom::new(points()).with_alpha(alpha);
+            assert_eq!(curve.segments(), 3);
+            for (segment, point) in points().iter().take(3).enumerate() {
+                assert!(approx(curve.point(segment, 0.0), *point));
+            }
+            assert!(approx(curve.point(2, 1.0), points()[3]));
+            assert!(approx(curve.sample(0.0), points()[0]));
+            assert!(approx(curve.sample(1.0), points()[3]));
+        }
+    }
+
+    #[test]
+    fn catmull_rom_closed_wraps() {
+        let curve = CatmullRom::new(points()).with_closed(true);
+        assert_eq!(curve.segments(), 4);
+        assert!(approx(curve.point(3, 0.0), points()[3]));
+        assert!(approx(curve.point(3, 1.0), points()[0]));
+    }
+
+    #[test]
+    fn catmull_rom_coincident_points() {
+        let point = Vector3::new(1.0, 1.0, 1.0);
+        let curve = CatmullRom::new(vec![point, point, point]);
+        for t in [0.0, 0.3, 0.5, 1.0] {
+            assert!(approx(curve.sample(t), point));
+        }
+
+        let mut doubled = points();
+        doubled.insert(1, doubled[1]);
+        let curve = CatmullRom::new(doubled);
+        for i in 0..=20 {
+            assert!(curve.sample(i as f32 / 20.0).iter().all(|x| x.is_finite()));
+        }
+    }
+
+    #[test]
+    fn catmull_rom_uniform_line_is_linear() {
+        let line = (0..4).map(|i| Vector3::new(i as f32, 0.0, 0.0)).collect();
+        let curve = CatmullRom::new(line).with_alpha(0.0);
+        assert!(approx(curve.sample(0.5), Vector3::new(1.5, 0.0, 0.0)));
+        assert!(approx(curve.point(0, 0.25), Vector3::new(0.25, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(CatmullRom::new(Vec::new()).sample(0.5), Vector3::zeros());
+        let point = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(CatmullRom::new(vec![point]).sample(0.5), point);
+        assert_eq!(Bezier::new(vec![point, point]).sample(0.5), point);
+    }
+
+    #[test]
+    fn bezier_segments() {
+        let mut points = points();
+        points.extend([
+            Vector3::new(5.0, -2.0, 0.0),
+            Vector3::new(6.0, -2.0, 0.0),
+            Vector3::new(7.0, 0.0, 0.0),
+            // short of a segment
+            Vector3::new(9.0, 9.0, 9.0),
+        ]);
+        let curve = Bezier::new(points.clone());
+        assert_eq!(curve.segments(), 2);
+        assert!(approx(curve.sample(0.0), points[0]));
+        assert!(approx(curve.sample(0.5), points[3]));
+        assert!(approx(curve.sample(1.0), points[6]));
+        // the midpoint weights are 1/8, 3/8, 3/8, 1/8
+        let middle = (points[0] + points[1] * 3.0 + points[2] * 3.0 + points[3]) / 8.0;
+        assert!(approx(curve.point(0, 0.5), middle));
+    }
+
+    #[test]
+    fn sample_clamps_t() {
+        let curve = CatmullRom::new(points());
+        assert!(approx(curve.sample(-1.0), points()[0]));
+        assert!(approx(curve.sample(2.0), points()[3]));
+    }
+}
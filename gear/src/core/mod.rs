@@ -1,8 +1,14 @@
 pub mod application;
 pub mod assets;
+pub mod audio;
+pub mod ecs;
 pub mod entity;
 pub mod event;
+pub mod input;
 pub mod layer;
 pub mod logger;
+pub mod math;
+pub mod physics;
 pub mod renderer;
+pub mod scene;
 pub mod window;
@@ -0,0 +1,223 @@
+use nalgebra::Vector3;
+use rapier3d::prelude::{ColliderBuilder, Point, RigidBodyBuilder};
+
+use crate::core::entity::Mesh;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BodyKind {
+    // Moved by the simulation, its `Transform` is overwritten every step
+    Dynamic,
+    // Moved by the game through its `Transform`, pushes dynamic bodies
+    Kinematic,
+    // Never moves, e.g. level geometry
+    Static,
+}
+
+// Rigid body component. The body is created on the next physics step from
+// the entity world transform; changing the fields afterwards has no effect,
+// use `Physics::body_mut` instead.
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    pub kind: BodyKind,
+    pub gravity_scale: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub ccd: bool,
+}
+
+impl RigidBody {
+    pub fn new(kind: BodyKind) -> Self {
+        Self {
+            kind,
+            gravity_scale: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            ccd: false,
+        }
+    }
+
+    pub fn dynamic() -> Self {
+        Self::new(BodyKind::Dynamic)
+    }
+
+    pub fn kinematic() -> Self {
+        Self::new(BodyKind::Kinematic)
+    }
+
+    pub fn fixed() -> Self {
+        Self::new(BodyKind::Static)
+    }
+
+    pub fn with_gravity_scale(mut self, gravity_scale: f32) -> Self {
+        self.gravity_scale = gravity_scale;
+        self
+    }
+
+    pub fn with_damping(mut self, linear: f32, angular: f32) -> Self {
+        self.linear_damping = linear;
+        self.angular_damping = angular;
+        self
+    }
+
+    // Continuous collision detection, for fast and small bodies.
+    pub fn with_ccd(mut self, ccd: bool) -> Self {
+        self.ccd = ccd;
+        self
+    }
+
+    pub(super) fn builder(&self) -> RigidBodyBuilder {
+        let builder = match self.kind {
+            BodyKind::Dynamic => RigidBodyBuilder::dynamic(),
+            BodyKind::Kinematic => RigidBodyBuilder::kinematic_position_based(),
+            BodyKind::Static => RigidBodyBuilder::fixed(),
+        };
+
+        builder
+            .gravity_scale(self.gravity_scale)
+            .linear_damping(self.linear_damping)
+            .angular_damping(self.angular_damping)
+            .ccd_enabled(self.ccd)
+    }
+}
+
+// Shapes are in world units and ignore the entity scale.
+#[derive(Debug, Clone)]
+pub enum ColliderShape {
+    Box(Vector3<f32>),
+    Sphere(f32),
+    // Along the Y axis, `half_height` excludes the caps
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    ConvexHull(Vec<Vector3<f32>>),
+    TriMesh {
+        vertices: Vec<Vector3<f32>>,
+        indices: Vec<[u32; 3]>,
+    },
+}
+
+impl ColliderShape {
+    pub fn cuboid(half_extents: Vector3<f32>) -> Self {
+        ColliderShape::Box(half_extents)
+    }
+
+    pub fn capsule(half_height: f32, radius: f32) -> Self {
+        ColliderShape::Capsule {
+            half_height,
+            radius,
+        }
+    }
+
+    // Hull of the mesh vertices.
+    pub fn convex_hull(mesh: &Mesh) -> Self {
+        ColliderShape::ConvexHull(
+            (0..mesh.vertex_count())
+                .filter_map(|vertex| mesh.position(vertex))
+                .collect(),
+        )
+    }
+
+    // The triangles of the mesh, best kept for static geometry.
+    pub fn trimesh(mesh: &Mesh) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for triangle in mesh.triangles() {
+            let first = vertices.len() as u32;
+            vertices.extend([triangle.a, triangle.b, triangle.c]);
+            indices.push([first, first + 1, first + 2]);
+        }
+        ColliderShape::TriMesh { vertices, indices }
+    }
+
+    // `None` for a degenerate hull or an empty mesh.
+    fn builder(&self) -> Option<ColliderBuilder> {
+        let points = |vertices: &[Vector3<f32>]| -> Vec<Point<f32>> {
+            vertices.iter().map(|v| Point::from(*v)).collect()
+        };
+
+        match self {
+            ColliderShape::Box(half) => Some(ColliderBuilder::cuboid(half.x, half.y, half.z)),
+            ColliderShape::Sphere(radius) => Some(ColliderBuilder::ball(*radius)),
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => Some(ColliderBuilder::capsule_y(*half_height, *radius)),
+            ColliderShape::ConvexHull(vertices) => ColliderBuilder::convex_hull(&points(vertices)),
+            ColliderShape::TriMesh { vertices, indices } => {
+                if indices.is_empty() {
+                    None
+                } else {
+                    Some(ColliderBuilder::trimesh(points(vertices), indices.clone()))
+                }
+            }
+        }
+    }
+}
+
+// Collider component. Attached to the `RigidBody` of the same entity, or a
+// static collider on its own otherwise.
+#[derive(Debug, Clone)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub friction: f32,
+    pub restitution: f32,
+    // Overrides the mass computed from the density
+    pub mass: Option<f32>,
+    pub density: f32,
+    // Reports collisions without any contact response
+    pub sensor: bool,
+}
+
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            friction: 0.5,
+            restitution: 0.0,
+            mass: None,
+            density: 1.0,
+            sensor: false,
+        }
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_sensor(mut self, sensor: bool) -> Self {
+        self.sensor = sensor;
+        self
+    }
+
+    pub(super) fn builder(&self) -> Option<ColliderBuilder> {
+        let builder = self
+            .shape
+            .builder()?
+            .friction(self.friction)
+            .restitution(self.restitution)
+            .sensor(self.sensor);
+
+        Some(match self.mass {
+            Some(mass) => builder.mass(mass),
+            None => builder.density(self.density),
+        })
+    }
+}
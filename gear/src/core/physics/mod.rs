@@ -0,0 +1,561 @@
+mod components;
+
+use std::collections::HashMap;
+
+use log::warn;
+use nalgebra::{Isometry3, Matrix4, Point3, Translation3, Unit, Vector3};
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    crossbeam::channel::{unbounded, Receiver},
+    prelude::{
+        ActiveEvents, BroadPhase, CCDSolver, ChannelEventCollector, ColliderBuilder,
+        ColliderHandle, ColliderSet, CollisionEvent, ContactForceEvent, ImpulseJointSet,
+        IntegrationParameters, IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline,
+        QueryFilter, QueryPipeline, RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
+    },
+};
+
+pub use components::{BodyKind, Collider, ColliderShape, RigidBody};
+
+use super::{
+    ecs::{EntityId, World},
+    entity::{Player, Transform},
+    event::PhysicsEvent,
+};
+
+// Simulation rate, independent of the frame rate.
+pub const PHYSICS_TIMESTEP: f32 = 1.0 / 60.0;
+// Steps run at most per frame, a slow frame makes the simulation lag behind
+// rather than spiral.
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+// Rapier world mirroring the entities with a `RigidBody` or `Collider`.
+// Bodies are created and removed as the components come and go; dynamic
+// bodies write their pose back to the entity `Transform` after each step,
+// kinematic and static ones follow it. Entities with a `Player` get a
+// kinematic capsule moved by the character controller once per frame.
+pub struct Physics {
+    pub gravity: Vector3<f32>,
+
+    pipeline: PhysicsPipeline,
+    integration_parameters: IntegrationParameters,
+    islands: IslandManager,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
+
+    collision_events: Receiver<CollisionEvent>,
+    event_collector: ChannelEventCollector,
+    // contact forces are not reported, the receiver keeps the channel open
+    _contact_force_events: Receiver<ContactForceEvent>,
+    events: Vec<PhysicsEvent>,
+
+    entity_bodies: HashMap<EntityId, RigidBodyHandle>,
+    entity_colliders: HashMap<EntityId, ColliderHandle>,
+    characters: HashMap<EntityId, (RigidBodyHandle, ColliderHandle)>,
+    // owner of every collider, kept for removed ones until the step that
+    // reports their last contacts
+    collider_entities: HashMap<ColliderHandle, EntityId>,
+    removed_colliders: Vec<ColliderHandle>,
+    accumulator: f32,
+}
+
+impl Physics {
+    pub fn new() -> Self {
+        let (collision_sender, collision_events) = unbounded();
+        let (contact_force_sender, contact_force_events) = unbounded();
+        let integration_parameters = IntegrationParameters {
+            dt: PHYSICS_TIMESTEP,
+            ..IntegrationParameters::default()
+        };
+
+        Self {
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            pipeline: PhysicsPipeline::new(),
+            integration_parameters,
+            islands: IslandManager::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            query_pipeline: QueryPipeline::new(),
+            collision_events,
+            event_collector: ChannelEventCollector::new(collision_sender, contact_force_sender),
+            _contact_force_events: contact_force_events,
+            events: Vec::new(),
+            entity_bodies: HashMap::new(),
+            entity_colliders: HashMap::new(),
+            characters: HashMap::new(),
+            collider_entities: HashMap::new(),
+            removed_colliders: Vec::new(),
+            accumulator: 0.0,
+        }
+    }
+
+    // Advances the simulation by `delta` seconds in fixed steps.
+    pub fn update(&mut self, world: &mut World, delta: f32) {
+        self.sync_bodies(world);
+        self.sync_characters(world);
+        self.move_characters(world, delta);
+
+        self.accumulator =
+            (self.accumulator + delta).min(PHYSICS_TIMESTEP * MAX_STEPS_PER_FRAME as f32);
+        let mut stepped = false;
+        while self.accumulator >= PHYSICS_TIMESTEP {
+            self.accumulator -= PHYSICS_TIMESTEP;
+            self.step();
+            stepped = true;
+        }
+
+        self.collect_events();
+        if stepped {
+            for handle in self.removed_colliders.drain(..) {
+                self.collider_entities.remove(&handle);
+            }
+        }
+        self.write_back(world);
+    }
+
+    // Collision events since the last call, in the order they happened.
+    pub fn drain_events(&mut self) -> Vec<PhysicsEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn body(&self, entity: EntityId) -> Option<&rapier3d::dynamics::RigidBody> {
+        self.bodies.get(*self.entity_bodies.get(&entity)?)
+    }
+
+    // Direct access to the simulated body, e.g. to apply forces or impulses.
+    pub fn body_mut(&mut self, entity: EntityId) -> Option<&mut rapier3d::dynamics::RigidBody> {
+        self.bodies.get_mut(*self.entity_bodies.get(&entity)?)
+    }
+
+    pub fn apply_impulse(&mut self, entity: EntityId, impulse: Vector3<f32>) {
+        if let Some(body) = self.body_mut(entity) {
+            body.apply_impulse(impulse, true);
+        }
+    }
+
+    pub fn linear_velocity(&self, entity: EntityId) -> Option<Vector3<f32>> {
+        self.body(entity).map(|body| *body.linvel())
+    }
+
+    pub fn set_linear_velocity(&mut self, entity: EntityId, velocity: Vector3<f32>) {
+        if let Some(body) = self.body_mut(entity) {
+            body.set_linvel(velocity, true);
+        }
+    }
+
+    fn step(&mut self) {
+        self.pipeline.step(
+            &self.gravity,
+            &self.integration_parameters,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            Some(&mut self.query_pipeline),
+            &(),
+            &self.event_collector,
+        );
+    }
+
+    // Creates the bodies and colliders of new entities, removes the ones
+    // whose entity or component is gone, and moves the kinematic and static
+    // bodies to their entity transform.
+    fn sync_bodies(&mut self, world: &mut World) {
+        let stale_bodies: Vec<EntityId> = self
+            .entity_bodies
+            .keys()
+            .filter(|entity| !world.has::<RigidBody>(**entity))
+            .copied()
+            .collect();
+        for entity in stale_bodies {
+            self.remove_body(entity);
+        }
+
+        let stale_colliders: Vec<EntityId> = self
+            .entity_colliders
+            .keys()
+            .filter(|entity| !world.has::<Collider>(**entity))
+            .copied()
+            .collect();
+        for entity in stale_colliders {
+            if let Some(handle) = self.entity_colliders.remove(&entity) {
+                self.remove_collider(handle);
+            }
+        }
+
+        let mut new_bodies = Vec::new();
+        world.query::<&RigidBody>().for_each(|entity, body| {
+            if !self.entity_bodies.contains_key(&entity) {
+                new_bodies.push((entity, body.clone()));
+            }
+        });
+        for (entity, body) in new_bodies {
+            let handle = self
+                .bodies
+                .insert(body.builder().position(world_pose(world, entity)).build());
+            self.entity_bodies.insert(entity, handle);
+
+            // a static collider of the entity is recreated on the body below
+            if let Some(collider) = self.entity_colliders.remove(&entity) {
+                self.remove_collider(collider);
+            }
+        }
+
+        let mut new_colliders = Vec::new();
+        world.query::<&Collider>().for_each(|entity, collider| {
+            if !self.entity_colliders.contains_key(&entity) {
+                new_colliders.push((entity, collider.clone()));
+            }
+        });
+        for (entity, collider) in new_colliders {
+            let Some(builder) = collider.builder() else {
+                warn!(target: "GEAR", "Skipping degenerate collider of entity {}", entity);
+                continue;
+            };
+            let collider = builder
+                .active_events(ActiveEvents::COLLISION_EVENTS)
+                .build();
+
+            let handle = match self.entity_bodies.get(&entity) {
+                Some(body) => self
+                    .colliders
+                    .insert_with_parent(collider, *body, &mut self.bodies),
+                None => {
+                    let mut collider = collider;
+                    collider.set_position(world_pose(world, entity));
+                    self.colliders.insert(collider)
+                }
+            };
+            self.entity_colliders.insert(entity, handle);
+            self.collider_entities.insert(handle, entity);
+        }
+
+        // game driven bodies follow their transform
+        for (entity, handle) in self.entity_bodies.iter() {
+            let Some(body) = self.bodies.get_mut(*handle) else {
+                continue;
+            };
+            if body.is_dynamic() {
+                continue;
+            }
+
+            let pose = world_pose(world, *entity);
+            if body.is_kinematic() {
+                body.set_next_kinematic_position(pose);
+            } else if *body.position() != pose {
+                body.set_position(pose, true);
+            }
+        }
+        for (entity, handle) in self.entity_colliders.iter() {
+            if self.entity_bodies.contains_key(entity) {
+                continue;
+            }
+            if let Some(collider) = self.colliders.get_mut(*handle) {
+                let pose = world_pose(world, *entity);
+                if *collider.position() != pose {
+                    collider.set_position(pose);
+                }
+            }
+        }
+    }
+
+    fn sync_characters(&mut self, world: &World) {
+        let stale: Vec<EntityId> = self
+            .characters
+            .keys()
+            .filter(|entity| !world.has::<Player>(**entity))
+            .copied()
+            .collect();
+        for entity in stale {
+            if let Some((body, collider)) = self.characters.remove(&entity) {
+                self.removed_colliders.push(collider);
+                self.bodies.remove(
+                    body,
+                    &mut self.islands,
+                    &mut self.colliders,
+                    &mut self.impulse_joints,
+                    &mut self.multibody_joints,
+                    true,
+                );
+            }
+        }
+
+        let mut new_characters = Vec::new();
+        world.query::<&Player>().for_each(|entity, player| {
+            if !self.characters.contains_key(&entity) {
+                new_characters.push((entity, player.half_height(), player.radius));
+            }
+        });
+        for (entity, half_height, radius) in new_characters {
+            let body = self.bodies.insert(
+                RigidBodyBuilder::kinematic_position_based()
+                    .position(world_pose(world, entity))
+                    .build(),
+            );
+            let collider = ColliderBuilder::capsule_y(half_height, radius)
+                .active_events(ActiveEvents::COLLISION_EVENTS)
+                .build();
+            let collider = self
+                .colliders
+                .insert_with_parent(collider, body, &mut self.bodies);
+            self.characters.insert(entity, (body, collider));
+            self.collider_entities.insert(collider, entity);
+        }
+    }
+
+    // Moves the player capsules against the other colliders and writes the
+    // result to their `Transform`.
+    fn move_characters(&mut self, world: &mut World, delta: f32) {
+        if self.characters.is_empty() || delta <= 0.0 {
+            return;
+        }
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+
+        let gravity = self.gravity.norm();
+        let up = Unit::try_new(-self.gravity, f32::EPSILON).unwrap_or(Vector3::y_axis());
+
+        for (entity, (body, collider)) in self.characters.iter() {
+            let Some(mut player) = world.get_mut::<Player>(*entity) else {
+                continue;
+            };
+            let Some(shape) = self.colliders.get(*collider).map(|c| c.shape()) else {
+                continue;
+            };
+            let pose = world_pose(world, *entity);
+
+            // heading of the entity, flattened on the ground plane
+            let flatten = |v: Vector3<f32>| {
+                (v - up.into_inner() * v.dot(&up))
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros)
+            };
+            let forward = flatten(pose.rotation * -Vector3::z());
+            let right = flatten(pose.rotation * Vector3::x());
+            let mut direction = forward * player.input.forward + right * player.input.right;
+            if direction.norm() > 1.0 {
+                direction.normalize_mut();
+            }
+
+            let vertical = player.fall(gravity, delta);
+            let desired = (direction * player.speed + up.into_inner() * vertical) * delta;
+
+            let controller = KinematicCharacterController {
+                up,
+                offset: CharacterLength::Absolute(0.01),
+                slide: true,
+                autostep: Some(CharacterAutostep {
+                    max_height: CharacterLength::Absolute(player.step_height),
+                    min_width: CharacterLength::Absolute(player.radius),
+                    include_dynamic_bodies: false,
+                }),
+                max_slope_climb_angle: player.max_slope,
+                min_slope_slide_angle: player.max_slope,
+                snap_to_ground: Some(CharacterLength::Absolute(player.step_height)),
+            };
+            let movement = controller.move_shape(
+                delta,
+                &self.bodies,
+                &self.colliders,
+                &self.query_pipeline,
+                shape,
+                &pose,
+                desired,
+                QueryFilter::default()
+                    .exclude_rigid_body(*body)
+                    .exclude_sensors(),
+                |_| {},
+            );
+            player.land(movement.grounded);
+            player.rise(movement.translation.dot(&up), delta);
+            drop(player);
+
+            let position = pose.translation.vector + movement.translation;
+            if let Some(body) = self.bodies.get_mut(*body) {
+                body.set_next_kinematic_translation(position);
+            }
+
+            let parent = world
+                .parent(*entity)
+                .map(|parent| world.world_matrix(parent))
+                .and_then(|matrix| matrix.try_inverse())
+                .unwrap_or_else(Matrix4::identity);
+            if let Some(mut transform) = world.get_mut::<Transform>(*entity) {
+                transform.position = parent.transform_point(&Point3::from(position)).coords;
+            }
+        }
+    }
+
+    fn remove_body(&mut self, entity: EntityId) {
+        let Some(handle) = self.entity_bodies.remove(&entity) else {
+            return;
+        };
+
+        // a collider left on the entity is recreated as a static one
+        if let Some(collider) = self.entity_colliders.remove(&entity) {
+            self.remove_collider(collider);
+        }
+        self.bodies.remove(
+            handle,
+            &mut self.islands,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            true,
+        );
+    }
+
+    fn remove_collider(&mut self, handle: ColliderHandle) {
+        self.colliders
+            .remove(handle, &mut self.islands, &mut self.bodies, true);
+        self.removed_colliders.push(handle);
+    }
+
+    // Contacts of removed colliders end in the step after the removal, the
+    // entities still resolve through `collider_entities` then.
+    fn collect_events(&mut self) {
+        while let Ok(event) = self.collision_events.try_recv() {
+            let entities = |a: ColliderHandle, b: ColliderHandle| {
+                let a = self.collider_entities.get(&a)?;
+                let b = self.collider_entities.get(&b)?;
+                Some((*a, *b))
+            };
+
+            match event {
+                CollisionEvent::Started(a, b, _) => {
+                    if let Some((a, b)) = entities(a, b) {
+                        self.events.push(PhysicsEvent::CollisionStart(a, b));
+                    }
+                }
+                CollisionEvent::Stopped(a, b, _) => {
+                    if let Some((a, b)) = entities(a, b) {
+                        self.events.push(PhysicsEvent::CollisionStop(a, b));
+                    }
+                }
+            }
+        }
+    }
+
+    // Copies the pose of dynamic bodies into the local `Transform` of their
+    // entity, keeping its scale.
+    fn write_back(&self, world: &mut World) {
+        for (entity, handle) in self.entity_bodies.iter() {
+            let Some(body) = self.bodies.get(*handle) else {
+                continue;
+            };
+            if !body.is_dynamic() {
+                continue;
+            }
+
+            let parent = world
+                .parent(*entity)
+                .map(|parent| world.world_matrix(parent))
+                .and_then(|matrix| matrix.try_inverse())
+                .unwrap_or_else(Matrix4::identity);
+            let local = Transform::from_matrix(&(parent * body.position().to_homogeneous()));
+
+            if let Some(mut transform) = world.get_mut::<Transform>(*entity) {
+                transform.position = local.position;
+                transform.rotation = local.rotation;
+            }
+        }
+    }
+}
+
+// Position and rotation of the entity in world space, its scale is dropped.
+fn world_pose(world: &World, entity: EntityId) -> Isometry3<f32> {
+    let transform = Transform::from_matrix(&world.world_matrix(entity));
+    Isometry3::from_parts(Translation3::from(transform.position), transform.rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::UnitQuaternion;
+
+    use super::*;
+
+    fn falling_box(world: &mut World, transform: Transform) -> EntityId {
+        let entity = world.spawn();
+        world.insert(entity, transform).unwrap();
+        world.insert(entity, RigidBody::dynamic()).unwrap();
+        world
+            .insert(
+                entity,
+                Collider::new(ColliderShape::Box(Vector3::repeat(0.5))),
+            )
+            .unwrap();
+        entity
+    }
+
+    fn fall_speed(physics: &Physics, entity: EntityId) -> f32 {
+        -physics.linear_velocity(entity).unwrap().y
+    }
+
+    #[test]
+    fn steps_at_a_fixed_rate() {
+        let mut world = World::new();
+        let entity = falling_box(&mut world, Transform::new());
+        let mut physics = Physics::new();
+        let step_speed = physics.gravity.norm() * PHYSICS_TIMESTEP;
+
+        physics.update(&mut world, PHYSICS_TIMESTEP * 0.5);
+        assert_eq!(fall_speed(&physics, entity), 0.0);
+
+        // the half step left over adds up to a full one
+        physics.update(&mut world, PHYSICS_TIMESTEP * 0.5);
+        assert!((fall_speed(&physics, entity) - step_speed).abs() < 1e-4);
+
+        physics.update(&mut world, PHYSICS_TIMESTEP * 2.25);
+        assert!((fall_speed(&physics, entity) - step_speed * 3.0).abs() < 1e-4);
+
+        // a long frame runs a bounded number of steps and drops the rest
+        physics.update(&mut world, 1.0);
+        let steps = 3 + MAX_STEPS_PER_FRAME;
+        assert!((fall_speed(&physics, entity) - step_speed * steps as f32).abs() < 1e-4);
+        assert!(physics.accumulator < PHYSICS_TIMESTEP);
+    }
+
+    #[test]
+    fn writes_dynamic_poses_back_under_a_parent() {
+        let mut world = World::new();
+        let parent = world.spawn();
+        world
+            .insert(
+                parent,
+                Transform::new()
+                    .with_position(Vector3::new(0.0, 10.0, 0.0))
+                    .with_rotation(UnitQuaternion::from_euler_angles(0.0, 1.2, 0.0))
+                    .with_scale(Vector3::repeat(2.0)),
+            )
+            .unwrap();
+        let child = falling_box(&mut world, Transform::new().with_position(Vector3::x()));
+        world.set_parent(child, parent).unwrap();
+        let start = world.world_matrix(child).column(3).xyz();
+        let scale = world.get::<Transform>(child).unwrap().scale;
+
+        let mut physics = Physics::new();
+        physics.update(&mut world, PHYSICS_TIMESTEP * 3.0);
+
+        // the local transform puts the entity where the body is
+        let body = physics.body(child).unwrap().translation();
+        let position = world.world_matrix(child).column(3).xyz();
+        assert!((position - body).norm() < 1e-4);
+        assert!(position.y < start.y);
+        assert!((position.xz() - start.xz()).norm() < 1e-4);
+        assert_eq!(world.get::<Transform>(child).unwrap().scale, scale);
+    }
+}
@@ -0,0 +1,35 @@
+mod ring;
+
+pub use ring::RingBuffer;
+
+use super::gl;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BufferUsage {
+    // uploaded once, drawn many times
+    Static,
+    // modified repeatedly, drawn many times
+    Dynamic,
+    // rewritten every frame or so
+    Stream,
+}
+
+impl BufferUsage {
+    pub fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            BufferUsage::Static => gl::STATIC_DRAW,
+            BufferUsage::Dynamic => gl::DYNAMIC_DRAW,
+            BufferUsage::Stream => gl::STREAM_DRAW,
+        }
+    }
+}
+
+// Capacity to allocate when a buffer of `current` bytes has to fit `required`
+// bytes. Grows geometrically so repeated appends don't reallocate every time.
+pub fn grow_capacity(current: usize, required: usize) -> usize {
+    let mut capacity = current.max(64);
+    while capacity < required {
+        capacity *= 2;
+    }
+    capacity
+}
@@ -0,0 +1,167 @@
+use log::warn;
+
+use super::{
+    super::{
+        resource::{defer_delete, GpuResource},
+        support::gl_supports,
+    },
+    gl,
+};
+
+// A buffer split in `sections`, one of which is written each frame while the
+// GPU may still be reading the others. When `glBufferStorage` is available
+// (OpenGL 4.4) the buffer is persistently mapped and each section is guarded
+// by a fence; otherwise it falls back to orphaning + `glBufferSubData`.
+pub struct RingBuffer {
+    id: u32,
+    target: gl::types::GLenum,
+    section_size: usize,
+    sections: usize,
+    current: usize,
+    cursor: usize,
+
+    mapped: *mut u8,
+    fences: Vec<gl::types::GLsync>,
+}
+
+impl RingBuffer {
+    pub fn new(target: gl::types::GLenum, section_size: usize, sections: usize) -> Self {
+        let sections = sections.max(1);
+        let size = section_size * sections;
+        let mut id = 0;
+        let mut mapped = std::ptr::null_mut();
+
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(target, id);
+
+            let storage = gl_supports(4, 4, "GL_ARB_buffer_storage")
+                && gl::BufferStorage::is_loaded()
+                && gl::MapBufferRange::is_loaded();
+            if storage {
+                let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+                gl::BufferStorage(target, size as isize, std::ptr::null(), flags);
+                mapped = gl::MapBufferRange(target, 0, size as isize, flags) as *mut u8;
+            }
+
+            if mapped.is_null() {
+                // the storage is immutable once allocated, start over
+                if storage {
+                    gl::DeleteBuffers(1, &id);
+                    gl::GenBuffers(1, &mut id);
+                    gl::BindBuffer(target, id);
+                }
+                warn!(target: "GEAR", "Persistent mapping unavailable, ring buffer will orphan");
+                gl::BufferData(target, size as isize, std::ptr::null(), gl::STREAM_DRAW);
+            }
+
+            gl::BindBuffer(target, 0);
+        }
+
+        Self {
+            id,
+            target,
+            section_size,
+            sections,
+            current: 0,
+            cursor: 0,
+            mapped,
+            fences: vec![std::ptr::null(); sections],
+        }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        !self.mapped.is_null()
+    }
+
+    pub fn section_size(&self) -> usize {
+        self.section_size
+    }
+
+    // Moves to the next section, waiting for the GPU to finish reading it.
+    pub fn begin_frame(&mut self) {
+        self.current = (self.current + 1) % self.sections;
+        self.cursor = 0;
+
+        let fence = self.fences[self.current];
+        if !fence.is_null() {
+            unsafe {
+                while gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, 1_000_000)
+                    == gl::TIMEOUT_EXPIRED
+                {}
+                gl::DeleteSync(fence);
+            }
+            self.fences[self.current] = std::ptr::null();
+        }
+    }
+
+    // Marks the current section as in use by the commands issued so far.
+    pub fn end_frame(&mut self) {
+        if self.is_persistent() {
+            unsafe {
+                self.fences[self.current] = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            }
+        }
+    }
+
+    // Appends `data` to the current section and returns its byte offset in the
+    // buffer, or `None` when the section is full.
+    pub fn write<T: Copy>(&mut self, data: &[T]) -> Option<usize> {
+        let size = std::mem::size_of_val(data);
+        if self.cursor + size > self.section_size {
+            return None;
+        }
+
+        let offset = self.current * self.section_size + self.cursor;
+
+        unsafe {
+            if self.is_persistent() {
+                std::ptr::copy_nonoverlapping(
+                    data.as_ptr() as *const u8,
+                    self.mapped.add(offset),
+                    size,
+                );
+            } else {
+                gl::BindBuffer(self.target, self.id);
+                if self.cursor == 0 {
+                    gl::BufferData(
+                        self.target,
+                        (self.section_size * self.sections) as isize,
+                        std::ptr::null(),
+                        gl::STREAM_DRAW,
+                    );
+                }
+                gl::BufferSubData(
+                    self.target,
+                    offset as isize,
+                    size as isize,
+                    data.as_ptr() as *const gl::types::GLvoid,
+                );
+                gl::BindBuffer(self.target, 0);
+            }
+        }
+
+        self.cursor += size;
+        Some(offset)
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBuffer(self.target, self.id);
+        }
+    }
+}
+
+impl Drop for RingBuffer {
+    fn drop(&mut self) {
+        for fence in self.fences.iter().filter(|fence| !fence.is_null()) {
+            defer_delete(GpuResource::Sync(*fence as usize));
+        }
+        // deleting a buffer implicitly unmaps it
+        defer_delete(GpuResource::Buffer(self.id));
+    }
+}
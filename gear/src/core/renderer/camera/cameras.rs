@@ -0,0 +1,66 @@
+use super::Camera;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CameraId(u64);
+
+// Cameras drawn besides the main one, e.g. the second player of a split
+// screen or a rear-view mirror.
+pub struct Cameras {
+    cameras: Vec<(CameraId, Camera)>,
+    next_id: u64,
+}
+
+impl Cameras {
+    pub fn new() -> Self {
+        Self {
+            cameras: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, camera: Camera) -> CameraId {
+        let id = CameraId(self.next_id);
+        self.next_id += 1;
+        self.cameras.push((id, camera));
+        id
+    }
+
+    pub fn remove(&mut self, id: CameraId) -> Option<Camera> {
+        let index = self.cameras.iter().position(|(other, _)| *other == id)?;
+        Some(self.cameras.remove(index).1)
+    }
+
+    pub fn clear(&mut self) {
+        self.cameras.clear();
+    }
+
+    pub fn get(&self, id: CameraId) -> Option<&Camera> {
+        self.cameras
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, camera)| camera)
+    }
+
+    pub fn get_mut(&mut self, id: CameraId) -> Option<&mut Camera> {
+        self.cameras
+            .iter_mut()
+            .find(|(other, _)| *other == id)
+            .map(|(_, camera)| camera)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cameras.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (CameraId, &Camera)> {
+        self.cameras.iter().map(|(id, camera)| (*id, camera))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (CameraId, &mut Camera)> {
+        self.cameras.iter_mut().map(|(id, camera)| (*id, camera))
+    }
+}
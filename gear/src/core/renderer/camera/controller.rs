@@ -0,0 +1,485 @@
+use std::collections::HashSet;
+
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    ecs::{EntityId, World},
+    entity::Transform,
+    event::{Event, Key, KeyboardEvent, MouseButton, MouseEvent, WindowEvent},
+};
+
+use super::{Camera, Fov};
+
+// Longest step of the follow spring, longer frames are split.
+const SPRING_STEP: f32 = 1.0 / 120.0;
+// Bounds the work after a long hitch, the implicit step stays stable anyway.
+const MAX_SPRING_STEPS: f32 = 32.0;
+
+// Moves the camera from the input events. The application forwards keyboard,
+// mouse and focus events before the app and layers see them, then calls
+// `update` once per frame before the transforms are propagated.
+pub trait CameraController {
+    // Returning true consumes the event.
+    fn on_event(&mut self, _event: &Event) -> bool {
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, world: &World, delta: f32);
+}
+
+// Keys and buttons held, and the mouse motion since the last update.
+#[derive(Debug, Default)]
+struct InputTracker {
+    keys: HashSet<Key>,
+    buttons: HashSet<MouseButton>,
+    cursor: Option<(f64, f64)>,
+    motion: (f32, f32),
+    scroll: f32,
+}
+
+impl InputTracker {
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::Keyboard(KeyboardEvent::Press(key, _)) => {
+                self.keys.insert(*key);
+            }
+            Event::Keyboard(KeyboardEvent::Release(key, _)) => {
+                self.keys.remove(key);
+            }
+            Event::Mouse(MouseEvent::Press(button, _)) => {
+                self.buttons.insert(*button);
+            }
+            Event::Mouse(MouseEvent::Release(button, _)) => {
+                self.buttons.remove(button);
+            }
+            Event::Mouse(MouseEvent::Move(x, y)) => {
+                if let Some((last_x, last_y)) = self.cursor {
+                    self.motion.0 += (x - last_x) as f32;
+                    self.motion.1 += (y - last_y) as f32;
+                }
+                self.cursor = Some((*x, *y));
+            }
+            Event::Mouse(MouseEvent::Scroll(_, y)) => self.scroll += *y as f32,
+            // the releases happen in another window, nothing stays held and
+            // the cursor doesn't jump when it comes back
+            Event::Window(WindowEvent::Focus(false)) => {
+                self.keys.clear();
+                self.buttons.clear();
+                self.cursor = None;
+                self.motion = (0.0, 0.0);
+            }
+            _ => {}
+        }
+    }
+
+    fn is_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    fn axis(&self, negative: Key, positive: Key) -> f32 {
+        let value = |key| if self.is_down(key) { 1.0 } else { 0.0 };
+        value(positive) - value(negative)
+    }
+
+    // Motion in pixels, right and down are positive.
+    fn take_motion(&mut self) -> (f32, f32) {
+        std::mem::take(&mut self.motion)
+    }
+
+    fn take_scroll(&mut self) -> f32 {
+        std::mem::take(&mut self.scroll)
+    }
+}
+
+// WASD to move, Space and left control to go up and down, the mouse to look
+// around, shift to sprint and the wheel to zoom.
+#[derive(Debug)]
+pub struct FreeFlyController {
+    // Units per second
+    pub speed: f32,
+    pub sprint: f32,
+    // Degrees per pixel
+    pub sensitivity: f32,
+    // Field of view range of the zoom, in degrees
+    pub min_fov: f32,
+    pub max_fov: f32,
+    input: InputTracker,
+}
+
+impl FreeFlyController {
+    pub fn new() -> Self {
+        Self {
+            speed: 3.0,
+            sprint: 3.0,
+            sensitivity: 0.1,
+            min_fov: 10.0,
+            max_fov: 90.0,
+            input: InputTracker::default(),
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32, sprint: f32) -> Self {
+        self.speed = speed;
+        self.sprint = sprint;
+        self
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+}
+
+impl CameraController for FreeFlyController {
+    fn on_event(&mut self, event: &Event) -> bool {
+        self.input.on_event(event);
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, _world: &World, delta: f32) {
+        let (dx, dy) = self.input.take_motion();
+        camera.rotate(dx * self.sensitivity, -dy * self.sensitivity);
+
+        let scroll = self.input.take_scroll();
+        if let Some(fov) = camera.projection.fov() {
+            let degrees = (fov.to_degrees() - scroll).clamp(self.min_fov, self.max_fov);
+            camera.projection.set_fov(Fov::Degrees(degrees));
+        }
+
+        let mut speed = self.speed * delta;
+        if self.input.is_down(Key::LShift) || self.input.is_down(Key::RShift) {
+            speed *= self.sprint;
+        }
+        let forward = self.input.axis(Key::S, Key::W);
+        let right = self.input.axis(Key::A, Key::D);
+        let up = self.input.axis(Key::LControl, Key::Space);
+
+        camera.position += (camera.front() * forward + camera.right() * right) * speed;
+        camera.position.y += up * speed;
+    }
+}
+
+// Turns around `target` while the left button is held, pans with the right
+// or middle one and dollies with the wheel.
+#[derive(Debug)]
+pub struct OrbitController {
+    pub target: Vector3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // Degrees per pixel
+    pub sensitivity: f32,
+    // Fraction of the distance per pixel, so panning feels the same at any
+    // distance
+    pub pan_speed: f32,
+    // Fraction of the distance per wheel step
+    pub zoom_speed: f32,
+    yaw: f32,
+    pitch: f32,
+    input: InputTracker,
+}
+
+impl OrbitController {
+    pub fn new(target: Vector3<f32>, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            sensitivity: 0.3,
+            pan_speed: 0.002,
+            zoom_speed: 0.1,
+            yaw: 90.0,
+            pitch: 0.0,
+            input: InputTracker::default(),
+        }
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn with_distance_range(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    // In degrees, a yaw of 90 puts the camera on +Z looking down -Z.
+    pub fn with_angles(mut self, yaw: f32, pitch: f32) -> Self {
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-89.0, 89.0);
+        self
+    }
+}
+
+impl CameraController for OrbitController {
+    fn on_event(&mut self, event: &Event) -> bool {
+        self.input.on_event(event);
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, _world: &World, _delta: f32) {
+        let (dx, dy) = self.input.take_motion();
+        let buttons = &self.input.buttons;
+
+        if buttons.contains(&MouseButton::Left) {
+            self.yaw += dx * self.sensitivity;
+            self.pitch = (self.pitch + dy * self.sensitivity).clamp(-89.0, 89.0);
+        } else if buttons.contains(&MouseButton::Right) || buttons.contains(&MouseButton::Middle) {
+            let pan = self.distance * self.pan_speed;
+            self.target += (camera.up() * dy - camera.right() * dx) * pan;
+        }
+
+        let scroll = self.input.take_scroll();
+        self.distance = (self.distance * (1.0 - scroll * self.zoom_speed))
+            .clamp(self.min_distance, self.max_distance);
+
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let offset = Vector3::new(
+            yaw.cos() * pitch.cos(),
+            pitch.sin(),
+            yaw.sin() * pitch.cos(),
+        );
+        camera.position = self.target + offset * self.distance;
+        camera.look_at(&self.target);
+    }
+}
+
+// Third person camera trailing an entity: the camera is pulled by a damped
+// spring towards `offset`, in the entity space, and looks at the entity.
+#[derive(Debug)]
+pub struct FollowController {
+    pub target: EntityId,
+    pub offset: Vector3<f32>,
+    // Point looked at, in the entity space
+    pub look_offset: Vector3<f32>,
+    pub stiffness: f32,
+    pub damping: f32,
+    velocity: Vector3<f32>,
+}
+
+impl FollowController {
+    pub fn new(target: EntityId) -> Self {
+        let stiffness = 40.0;
+        Self {
+            target,
+            offset: Vector3::new(0.0, 2.0, 5.0),
+            look_offset: Vector3::new(0.0, 1.0, 0.0),
+            stiffness,
+            // critically damped
+            damping: 2.0 * stiffness.sqrt(),
+            velocity: Vector3::zeros(),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vector3<f32>, look_offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self.look_offset = look_offset;
+        self
+    }
+
+    pub fn with_spring(mut self, stiffness: f32, damping: f32) -> Self {
+        self.stiffness = stiffness;
+        self.damping = damping;
+        self
+    }
+}
+
+impl CameraController for FollowController {
+    fn update(&mut self, camera: &mut Camera, world: &World, delta: f32) {
+        if !world.is_alive(self.target) {
+            return;
+        }
+
+        // the entity scale would stretch the offsets
+        let matrix = world.world_matrix(self.target);
+        let transform = Transform::from_matrix(&matrix);
+        let origin = Point3::from(transform.position);
+        let desired = origin + transform.rotation * self.offset;
+        let look = origin + transform.rotation * self.look_offset;
+
+        // implicit Euler: converges for any stiffness and frame time
+        let steps = (delta / SPRING_STEP).ceil().clamp(1.0, MAX_SPRING_STEPS);
+        let dt = delta.max(0.0) / steps;
+        for _ in 0..steps as u32 {
+            let pull = (desired.coords - camera.position) * self.stiffness;
+            self.velocity =
+                (self.velocity + pull * dt) / (1.0 + dt * self.damping + dt * dt * self.stiffness);
+            camera.position += self.velocity * dt;
+        }
+        camera.look_at(&look.coords);
+    }
+}
+
+// First person view of a `Player`: the mouse turns the camera and the player
+// with it. Movement is up to the player input, e.g. `PlayerControls`.
+#[derive(Debug)]
+pub struct FirstPersonController {
+    pub target: EntityId,
+    // Above the entity origin
+    pub eye_height: f32,
+    // Degrees per pixel
+    pub sensitivity: f32,
+    input: InputTracker,
+}
+
+impl FirstPersonController {
+    pub fn new(target: EntityId, eye_height: f32) -> Self {
+        Self {
+            target,
+            eye_height,
+            sensitivity: 0.1,
+            input: InputTracker::default(),
+        }
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+}
+
+impl CameraController for FirstPersonController {
+    fn on_event(&mut self, event: &Event) -> bool {
+        self.input.on_event(event);
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, world: &World, _delta: f32) {
+        if !world.is_alive(self.target) {
+            return;
+        }
+
+        let (dx, dy) = self.input.take_motion();
+        camera.rotate(dx * self.sensitivity, -dy * self.sensitivity);
+
+        // the camera looks down -Z at a yaw of -90 degrees
+        let heading = -(camera.yaw + 90.0).to_radians();
+        if let Some(mut transform) = world.get_mut::<Transform>(self.target) {
+            transform.rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), heading);
+        }
+
+        let position = world.world_matrix(self.target).column(3).xyz();
+        camera.position = position + Vector3::new(0.0, self.eye_height, 0.0);
+    }
+}
+
+// Serializable choice of controller, e.g. from a settings file. Entities are
+// looked up by name when the controller is built.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CameraControllerConfig {
+    None,
+    FreeFly {
+        speed: f32,
+        sprint: f32,
+        sensitivity: f32,
+    },
+    Orbit {
+        target: [f32; 3],
+        distance: f32,
+        sensitivity: f32,
+    },
+    Follow {
+        target: String,
+        offset: [f32; 3],
+        stiffness: f32,
+    },
+    FirstPerson {
+        target: String,
+        eye_height: f32,
+        sensitivity: f32,
+    },
+}
+
+impl CameraControllerConfig {
+    // `None` for `CameraControllerConfig::None` or a missing entity.
+    pub fn build(&self, world: &World) -> Option<Box<dyn CameraController>> {
+        Some(match self {
+            CameraControllerConfig::None => return None,
+            CameraControllerConfig::FreeFly {
+                speed,
+                sprint,
+                sensitivity,
+            } => Box::new(
+                FreeFlyController::new()
+                    .with_speed(*speed, *sprint)
+                    .with_sensitivity(*sensitivity),
+            ),
+            CameraControllerConfig::Orbit {
+                target,
+                distance,
+                sensitivity,
+            } => Box::new(
+                OrbitController::new(Vector3::from(*target), *distance)
+                    .with_sensitivity(*sensitivity),
+            ),
+            CameraControllerConfig::Follow {
+                target,
+                offset,
+                stiffness,
+            } => Box::new(
+                FollowController::new(world.find_by_name(target)?)
+                    .with_offset(Vector3::from(*offset), Vector3::new(0.0, 1.0, 0.0))
+                    .with_spring(*stiffness, 2.0 * stiffness.sqrt()),
+            ),
+            CameraControllerConfig::FirstPerson {
+                target,
+                eye_height,
+                sensitivity,
+            } => Box::new(
+                FirstPersonController::new(world.find_by_name(target)?, *eye_height)
+                    .with_sensitivity(*sensitivity),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_loss_releases_everything() {
+        let mut camera = Camera::new();
+        let world = World::new();
+        let mut controller = FreeFlyController::new();
+        for event in [
+            Event::Keyboard(KeyboardEvent::Press(Key::W, 0)),
+            Event::Mouse(MouseEvent::Move(100.0, 100.0)),
+            Event::Window(WindowEvent::Focus(false)),
+            // back over the window somewhere else
+            Event::Window(WindowEvent::Focus(true)),
+            Event::Mouse(MouseEvent::Move(500.0, 300.0)),
+        ] {
+            controller.on_event(&event);
+        }
+
+        let (position, yaw, pitch) = (camera.position, camera.yaw, camera.pitch);
+        controller.update(&mut camera, &world, 1.0);
+        assert_eq!(camera.position, position);
+        assert_eq!((camera.yaw, camera.pitch), (yaw, pitch));
+
+        controller.on_event(&Event::Mouse(MouseEvent::Move(510.0, 300.0)));
+        controller.update(&mut camera, &world, 1.0);
+        assert!((camera.yaw - yaw - 10.0 * controller.sensitivity).abs() < 1e-4);
+    }
+
+    #[test]
+    fn follow_spring_settles_with_long_frames() {
+        let mut world = World::new();
+        let target = world.spawn();
+        world.insert(target, Transform::new()).unwrap();
+
+        let mut camera = Camera::new();
+        camera.position = Vector3::new(50.0, 0.0, 0.0);
+        let mut controller = FollowController::new(target).with_spring(10000.0, 1.0);
+        for _ in 0..200 {
+            controller.update(&mut camera, &world, 0.5);
+            assert!(camera.position.iter().all(|x| x.is_finite()));
+        }
+        assert!((camera.position - controller.offset).norm() < 1e-3);
+    }
+}
@@ -1,22 +1,132 @@
-use nalgebra::{Matrix4, Vector3};
+mod cameras;
+mod controller;
+mod path;
+mod projection;
+
+use nalgebra::{Matrix4, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+pub use cameras::{CameraId, Cameras};
+pub use controller::{
+    CameraController, CameraControllerConfig, FirstPersonController, FollowController,
+    FreeFlyController, OrbitController,
+};
+pub use path::{CameraPath, PathInterpolation, PathKey, PathMarker, PathPlayer, PathTarget};
+pub use projection::{Fov, Projection, REVERSE_Z_FALLBACK_FAR};
+
+pub(crate) use path::play_paths;
+
+use crate::core::math::Ray;
+
+use super::target::RenderTarget;
+
+// Screen area the camera renders to, in window coordinates with the origin in
+// the top left corner.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        }
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width / self.height
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    // `x, y, width, height` for `glViewport`, whose origin is the bottom left
+    // corner of a surface `height` pixels high.
+    pub(crate) fn to_gl(self, height: f32) -> [i32; 4] {
+        [
+            self.x.round() as i32,
+            (height - self.y - self.height).round() as i32,
+            self.width.round() as i32,
+            self.height.round() as i32,
+        ]
+    }
+}
+
+// Part of the window or render target a camera draws to, as fractions of its
+// size with the origin in the top left corner.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn full() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    // The area in pixels on a `width` by `height` surface.
+    pub fn to_viewport(self, width: f32, height: f32) -> Viewport {
+        Viewport {
+            x: self.x * width,
+            y: self.y * height,
+            width: self.width * width,
+            height: self.height * height,
+        }
+    }
+}
+
+// What a camera clears in its viewport before drawing.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClearMode {
+    // Color and depth
+    Color(Vector4<f32>),
+    // Draws over the cameras before it
+    Depth,
+    Keep,
+}
+
+// Layers of entities without a `RenderLayers` component.
+pub const DEFAULT_RENDER_LAYER: u32 = 1;
 
 pub struct Camera {
     pub position: Vector3<f32>,
     pub rotation: Vector3<f32>,
-    pub fov: f32,
-    pub near: f32,
-    pub far: f32,
+    pub projection: Projection,
     pub yaw: f32,
     pub pitch: f32,
+    pub viewport: ViewportRect,
+    // Cameras draw in increasing priority, later ones on top
+    pub priority: i32,
+    pub clear: ClearMode,
+    // Render layers drawn by the camera
+    pub culling_mask: u32,
+    // Draws to the window when `None`
+    pub target: Option<RenderTarget>,
 
     up: Vector3<f32>,
     front: Vector3<f32>,
     right: Vector3<f32>,
     world_up: Vector3<f32>,
-
-    first_mouse: bool,
-    last_x: f32,
-    last_y: f32,
 }
 
 impl Camera {
@@ -24,25 +134,59 @@ impl Camera {
         Self {
             position: Vector3::new(0.0, 0.0, 3.0),
             rotation: Vector3::new(0.0, 0.0, 0.0),
-            fov: 45.0,
-            near: 0.1,
-            far: 100.0,
+            projection: Projection::default(),
             yaw: -90.0,
             pitch: 0.0,
+            viewport: ViewportRect::full(),
+            priority: 0,
+            clear: ClearMode::Color(Vector4::new(0.0, 0.0, 0.0, 1.0)),
+            culling_mask: u32::MAX,
+            target: None,
 
             up: Vector3::new(0.0, 1.0, 0.0),
             front: Vector3::new(0.0, 0.0, -1.0),
             right: Vector3::new(1.0, 0.0, 0.0),
             world_up: Vector3::new(0.0, 1.0, 0.0),
-
-            first_mouse: true,
-            last_x: 0.0,
-            last_y: 0.0,
         }
     }
 
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: ViewportRect) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_clear(mut self, clear: ClearMode) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    pub fn with_culling_mask(mut self, culling_mask: u32) -> Self {
+        self.culling_mask = culling_mask;
+        self
+    }
+
+    pub fn with_target(mut self, target: RenderTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    // Whether the camera draws entities on `layers`.
+    pub fn sees(&self, layers: u32) -> bool {
+        self.culling_mask & layers != 0
+    }
+
     pub fn get_projection_matrix(&self, aspect_ratio: f32) -> Matrix4<f32> {
-        Matrix4::new_perspective(aspect_ratio, self.fov, self.near, self.far)
+        self.projection.matrix(aspect_ratio)
     }
 
     pub fn get_view_matrix(&self) -> Matrix4<f32> {
@@ -53,31 +197,60 @@ impl Camera {
         )
     }
 
-    pub fn on_mouse_move(&mut self, xpos: f32, ypos: f32) {
-        if self.first_mouse {
-            self.last_x = xpos;
-            self.last_y = ypos;
-            self.first_mouse = false;
-        }
-
-        let xoffset = xpos - self.last_x;
-        let yoffset = self.last_y - ypos;
-        self.last_x = xpos;
-        self.last_y = ypos;
+    pub fn view_projection(&self, aspect_ratio: f32) -> Matrix4<f32> {
+        self.get_projection_matrix(aspect_ratio) * self.get_view_matrix()
+    }
 
-        let sensitivity = 0.1;
-        let xoffset = xoffset * sensitivity;
-        let yoffset = yoffset * sensitivity;
+    // Ray from the near plane through the window point `(x, y)`, e.g. the
+    // cursor position. `update` must have run since the camera last moved.
+    pub fn screen_point_to_ray(&self, x: f32, y: f32, viewport: &Viewport) -> Ray {
+        let (near_depth, far_depth) = self.projection.depth_range();
+        let near = self.unproject_ndc(x, y, near_depth, viewport);
+        let far = self.unproject_ndc(x, y, far_depth, viewport);
+        Ray::new(near, far - near)
+    }
 
-        self.yaw += xoffset;
-        self.pitch += yoffset;
+    // World position of the window point `(x, y)` at `depth`, a value read
+    // from the depth buffer.
+    pub fn unproject(&self, x: f32, y: f32, depth: f32, viewport: &Viewport) -> Vector3<f32> {
+        self.unproject_ndc(x, y, self.projection.ndc_depth(depth), viewport)
+    }
 
-        if self.pitch > 89.0 {
-            self.pitch = 89.0;
-        }
-        if self.pitch < -89.0 {
-            self.pitch = -89.0;
+    // Window position and depth of a world point, `None` when it is behind
+    // the camera. The point may still be outside the viewport.
+    pub fn world_to_screen(
+        &self,
+        point: &Vector3<f32>,
+        viewport: &Viewport,
+    ) -> Option<Vector3<f32>> {
+        let clip = self.view_projection(viewport.aspect_ratio()) * point.push(1.0);
+        if clip.w <= f32::EPSILON {
+            return None;
         }
+
+        let ndc = clip.xyz() / clip.w;
+        Some(Vector3::new(
+            viewport.x + (ndc.x + 1.0) * 0.5 * viewport.width,
+            viewport.y + (1.0 - ndc.y) * 0.5 * viewport.height,
+            self.projection.window_depth(ndc.z),
+        ))
+    }
+
+    // In degrees, the pitch stays short of straight up or down.
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-89.0, 89.0);
+        self.update();
+    }
+
+    // Turns the camera towards a point, keeping its position.
+    pub fn look_at(&mut self, target: &Vector3<f32>) {
+        let Some(direction) = (target - self.position).try_normalize(f32::EPSILON) else {
+            return;
+        };
+        self.yaw = direction.z.atan2(direction.x).to_degrees();
+        self.pitch = direction.y.asin().to_degrees().clamp(-89.0, 89.0);
+        self.update();
     }
 
     pub fn update(&mut self) {
@@ -92,6 +265,19 @@ impl Camera {
         self.up = self.right.cross(&self.front).normalize();
     }
 
+    // Unit axes of the view, as of the last `update`.
+    pub fn front(&self) -> Vector3<f32> {
+        self.front
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.right
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.up
+    }
+
     pub fn move_forward(&mut self, amount: f32) {
         self.position += self.front * amount;
     }
@@ -107,4 +293,16 @@ impl Camera {
     pub fn move_right(&mut self, amount: f32) {
         self.position += self.right * amount;
     }
+
+    fn unproject_ndc(&self, x: f32, y: f32, ndc_z: f32, viewport: &Viewport) -> Vector3<f32> {
+        let ndc_x = 2.0 * (x - viewport.x) / viewport.width - 1.0;
+        let ndc_y = 1.0 - 2.0 * (y - viewport.y) / viewport.height;
+
+        let inverse = self
+            .view_projection(viewport.aspect_ratio())
+            .try_inverse()
+            .unwrap_or_else(Matrix4::identity);
+        let point = inverse * Vector4::new(ndc_x, ndc_y, ndc_z, 1.0);
+        point.xyz() / point.w
+    }
 }
@@ -0,0 +1,371 @@
+use std::sync::Arc;
+
+use nalgebra::{UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    ecs::{EntityId, World},
+    entity::Transform,
+    event::PathEvent,
+    math::{bezier, catmull_rom, Easing},
+    scene::{read_file, write_file, SceneError},
+};
+
+use super::Camera;
+
+// Pose at a point in time. `easing` shapes the motion towards the next key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathKey {
+    pub time: f32,
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    #[serde(default)]
+    pub easing: Easing,
+    // Bézier handles relative to the position, derived from the neighbouring
+    // keys when `None`
+    #[serde(default)]
+    pub in_handle: Option<Vector3<f32>>,
+    #[serde(default)]
+    pub out_handle: Option<Vector3<f32>>,
+}
+
+impl PathKey {
+    pub fn new(time: f32, position: Vector3<f32>, rotation: UnitQuaternion<f32>) -> Self {
+        Self {
+            time,
+            position,
+            rotation,
+            easing: Easing::Linear,
+            in_handle: None,
+            out_handle: None,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_handles(mut self, in_handle: Vector3<f32>, out_handle: Vector3<f32>) -> Self {
+        self.in_handle = Some(in_handle);
+        self.out_handle = Some(out_handle);
+        self
+    }
+}
+
+// How positions are interpolated between keys, rotations always slerp.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PathInterpolation {
+    Linear,
+    CatmullRom { alpha: f32 },
+    Bezier,
+}
+
+impl Default for PathInterpolation {
+    fn default() -> Self {
+        PathInterpolation::CatmullRom { alpha: 0.5 }
+    }
+}
+
+// Named point in time reported by the player when it passes it, e.g. to
+// start a sound or a dialogue line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathMarker {
+    pub time: f32,
+    pub name: String,
+}
+
+// Authored camera or entity motion, saved as RON or JSON like scenes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keys: Vec<PathKey>,
+    #[serde(default)]
+    pub interpolation: PathInterpolation,
+    #[serde(default)]
+    pub markers: Vec<PathMarker>,
+}
+
+impl CameraPath {
+    pub fn new(interpolation: PathInterpolation) -> Self {
+        Self {
+            keys: Vec::new(),
+            interpolation,
+            markers: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        let mut camera_path: CameraPath = read_file(path)?;
+        camera_path.sort();
+        Ok(camera_path)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SceneError> {
+        write_file(path, self)
+    }
+
+    // Keys and markers stay sorted by time.
+    pub fn with_key(mut self, key: PathKey) -> Self {
+        self.keys.push(key);
+        self.sort();
+        self
+    }
+
+    pub fn with_marker(mut self, time: f32, name: &str) -> Self {
+        self.markers.push(PathMarker {
+            time,
+            name: name.to_string(),
+        });
+        self.sort();
+        self
+    }
+
+    // Time of the last key, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    pub fn marker(&self, index: usize) -> Option<&PathMarker> {
+        self.markers.get(index)
+    }
+
+    // Pose at `time` seconds, clamped to the first and last keys. `None`
+    // without keys.
+    pub fn sample(&self, time: f32) -> Option<(Vector3<f32>, UnitQuaternion<f32>)> {
+        let keys = &self.keys;
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if keys.len() == 1 || time <= first.time {
+            return Some((first.position, first.rotation));
+        }
+        if time >= last.time {
+            return Some((last.position, last.rotation));
+        }
+
+        let i = keys.partition_point(|key| key.time <= time) - 1;
+        let (from, to) = (&keys[i], &keys[i + 1]);
+        let span = to.time - from.time;
+        let u = if span > 0.0 {
+            from.easing.apply((time - from.time) / span)
+        } else {
+            1.0
+        };
+
+        // the ends are extended by mirroring their neighbour
+        let before = match i {
+            0 => 2.0 * from.position - to.position,
+            _ => keys[i - 1].position,
+        };
+        let after = match keys.get(i + 2) {
+            Some(key) => key.position,
+            None => 2.0 * to.position - from.position,
+        };
+
+        let position = match self.interpolation {
+            PathInterpolation::Linear => from.position.lerp(&to.position, u),
+            PathInterpolation::CatmullRom { alpha } => {
+                catmull_rom(before, from.position, to.position, after, alpha, u)
+            }
+            PathInterpolation::Bezier => {
+                let out_handle = from
+                    .out_handle
+                    .unwrap_or_else(|| (to.position - before) / 6.0);
+                let in_handle = to
+                    .in_handle
+                    .unwrap_or_else(|| (from.position - after) / 6.0);
+                bezier(
+                    from.position,
+                    from.position + out_handle,
+                    to.position + in_handle,
+                    to.position,
+                    u,
+                )
+            }
+        };
+        Some((position, from.rotation.slerp(&to.rotation, u)))
+    }
+
+    fn sort(&mut self) {
+        self.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathTarget {
+    // The main camera, which loses its roll
+    Camera,
+    // The `Transform` of the entity holding the player
+    Transform,
+}
+
+// Component playing a path, on any entity. Runs after the camera controller
+// so a cutscene overrides it.
+pub struct PathPlayer {
+    path: Arc<CameraPath>,
+    time: f32,
+    playing: bool,
+    // Negative plays backwards
+    pub speed: f32,
+    pub looping: bool,
+    pub target: PathTarget,
+}
+
+impl PathPlayer {
+    pub fn new(path: Arc<CameraPath>) -> Self {
+        Self {
+            path,
+            time: 0.0,
+            playing: true,
+            speed: 1.0,
+            looping: false,
+            target: PathTarget::Transform,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_target(mut self, target: PathTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn path(&self) -> &Arc<CameraPath> {
+        &self.path
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    // Pauses and rewinds.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.time = 0.0;
+    }
+
+    // Jumps to `time` seconds without reporting the markers in between, e.g.
+    // when scrubbing in an editor.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.path.duration());
+    }
+
+    // Moves the time forward, returns the markers passed in playing order
+    // and whether a non-looping path reached its end.
+    fn advance(&mut self, delta: f32) -> (Vec<usize>, bool) {
+        let duration = self.path.duration();
+        if !self.playing || self.speed == 0.0 || duration <= 0.0 {
+            return (Vec::new(), false);
+        }
+
+        let from = self.time;
+        let to = from + delta * self.speed;
+        let mut markers = Vec::new();
+
+        // markers right on the end the player starts from are passed too
+        let from = if from <= 0.0 && to > from {
+            f32::NEG_INFINITY
+        } else if from >= duration && to < from {
+            f32::INFINITY
+        } else {
+            from
+        };
+
+        if (0.0..=duration).contains(&to) {
+            self.passed(from, to, &mut markers);
+            self.time = to;
+            return (markers, false);
+        }
+
+        let end = if to > duration { duration } else { 0.0 };
+        self.passed(from, end, &mut markers);
+        if !self.looping {
+            self.time = end;
+            self.playing = false;
+            return (markers, true);
+        }
+
+        // a frame wraps at most once
+        let wrapped = to.rem_euclid(duration);
+        let start = if to > duration {
+            f32::NEG_INFINITY
+        } else {
+            f32::INFINITY
+        };
+        self.passed(start, wrapped, &mut markers);
+        self.time = wrapped;
+        (markers, false)
+    }
+
+    // Markers in (from, to], or [to, from) playing backwards.
+    fn passed(&self, from: f32, to: f32, markers: &mut Vec<usize>) {
+        let all = self.path.markers.iter().enumerate();
+        if to >= from {
+            markers.extend(
+                all.filter(|(_, marker)| marker.time > from && marker.time <= to)
+                    .map(|(index, _)| index),
+            );
+        } else {
+            markers.extend(
+                all.rev()
+                    .filter(|(_, marker)| marker.time >= to && marker.time < from)
+                    .map(|(index, _)| index),
+            );
+        }
+    }
+}
+
+// Advances every player and moves its target, returns the events to dispatch.
+pub(crate) fn play_paths(world: &World, camera: &mut Camera, delta: f32) -> Vec<PathEvent> {
+    let mut events = Vec::new();
+    world
+        .query::<&mut PathPlayer>()
+        .for_each(|entity: EntityId, player| {
+            let (markers, finished) = player.advance(delta);
+            events.extend(
+                markers
+                    .into_iter()
+                    .map(|marker| PathEvent::Marker(entity, marker)),
+            );
+            if finished {
+                events.push(PathEvent::Finished(entity));
+            }
+
+            let Some((position, rotation)) = player.path.sample(player.time) else {
+                return;
+            };
+            match player.target {
+                PathTarget::Camera => {
+                    camera.position = position;
+                    camera.look_at(&(position + rotation * -Vector3::z()));
+                }
+                PathTarget::Transform => {
+                    if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                        transform.position = position;
+                        transform.rotation = rotation;
+                    }
+                }
+            }
+        });
+    events
+}
@@ -0,0 +1,223 @@
+use nalgebra::Matrix4;
+use serde::{Deserialize, Serialize};
+
+// Far plane of the perspective drawn in place of `InfiniteReverseZ` when the
+// GL has no glClipControl.
+pub const REVERSE_Z_FALLBACK_FAR: f32 = 1000.0;
+
+// Vertical field of view, the unit is explicit so a degree value never ends
+// up where radians are expected.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Fov {
+    Degrees(f32),
+    Radians(f32),
+}
+
+impl Fov {
+    pub fn to_radians(self) -> f32 {
+        match self {
+            Fov::Degrees(degrees) => degrees.to_radians(),
+            Fov::Radians(radians) => radians,
+        }
+    }
+
+    pub fn to_degrees(self) -> f32 {
+        match self {
+            Fov::Degrees(degrees) => degrees,
+            Fov::Radians(radians) => radians.to_degrees(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    Perspective {
+        fov: Fov,
+        near: f32,
+        far: f32,
+    },
+    // `size` is the height of the view in world units, the width follows the
+    // aspect ratio
+    Orthographic {
+        size: f32,
+        near: f32,
+        far: f32,
+    },
+    // Fixed view volume, ignores the aspect ratio
+    OrthographicExtents {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    },
+    // Perspective without a far plane, the depth goes from 1 at the near
+    // plane to 0 at infinity. The renderer switches to a [0, 1] clip range
+    // and a greater depth test for it, which needs glClipControl (GL 4.5):
+    // without it the camera falls back to `without_reverse_z`.
+    InfiniteReverseZ {
+        fov: Fov,
+        near: f32,
+    },
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection::perspective(Fov::Degrees(45.0), 0.1, 100.0)
+    }
+}
+
+impl Projection {
+    pub fn perspective(fov: Fov, near: f32, far: f32) -> Self {
+        Projection::Perspective { fov, near, far }
+    }
+
+    pub fn orthographic(size: f32, near: f32, far: f32) -> Self {
+        Projection::Orthographic { size, near, far }
+    }
+
+    pub fn orthographic_extents(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        Projection::OrthographicExtents {
+            left,
+            right,
+            bottom,
+            top,
+            near,
+            far,
+        }
+    }
+
+    pub fn infinite_reverse_z(fov: Fov, near: f32) -> Self {
+        Projection::InfiniteReverseZ { fov, near }
+    }
+
+    // A perspective with a far plane in place of reverse-Z, the other
+    // projections as is.
+    pub fn without_reverse_z(&self) -> Self {
+        match *self {
+            Projection::InfiniteReverseZ { fov, near } => {
+                Projection::perspective(fov, near, REVERSE_Z_FALLBACK_FAR)
+            }
+            projection => projection,
+        }
+    }
+
+    pub fn matrix(&self, aspect_ratio: f32) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fov, near, far } => {
+                Matrix4::new_perspective(aspect_ratio, fov.to_radians(), near, far)
+            }
+            Projection::Orthographic { size, near, far } => {
+                let half_height = size * 0.5;
+                let half_width = half_height * aspect_ratio;
+                Matrix4::new_orthographic(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+            Projection::OrthographicExtents {
+                left,
+                right,
+                bottom,
+                top,
+                near,
+                far,
+            } => Matrix4::new_orthographic(left, right, bottom, top, near, far),
+            Projection::InfiniteReverseZ { fov, near } => {
+                let focal = 1.0 / (fov.to_radians() * 0.5).tan();
+                let mut matrix = Matrix4::zeros();
+                matrix[(0, 0)] = focal / aspect_ratio;
+                matrix[(1, 1)] = focal;
+                matrix[(2, 3)] = near;
+                matrix[(3, 2)] = -1.0;
+                matrix
+            }
+        }
+    }
+
+    // `None` for the orthographic projections.
+    pub fn fov(&self) -> Option<Fov> {
+        match *self {
+            Projection::Perspective { fov, .. } | Projection::InfiniteReverseZ { fov, .. } => {
+                Some(fov)
+            }
+            _ => None,
+        }
+    }
+
+    // Ignored by the orthographic projections.
+    pub fn set_fov(&mut self, value: Fov) {
+        match self {
+            Projection::Perspective { fov, .. } | Projection::InfiniteReverseZ { fov, .. } => {
+                *fov = value
+            }
+            _ => {}
+        }
+    }
+
+    pub fn near(&self) -> f32 {
+        match *self {
+            Projection::Perspective { near, .. }
+            | Projection::Orthographic { near, .. }
+            | Projection::OrthographicExtents { near, .. }
+            | Projection::InfiniteReverseZ { near, .. } => near,
+        }
+    }
+
+    // `None` when there is no far plane.
+    pub fn far(&self) -> Option<f32> {
+        match *self {
+            Projection::Perspective { far, .. }
+            | Projection::Orthographic { far, .. }
+            | Projection::OrthographicExtents { far, .. } => Some(far),
+            Projection::InfiniteReverseZ { .. } => None,
+        }
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        matches!(
+            self,
+            Projection::Orthographic { .. } | Projection::OrthographicExtents { .. }
+        )
+    }
+
+    pub fn is_reverse_z(&self) -> bool {
+        matches!(self, Projection::InfiniteReverseZ { .. })
+    }
+
+    // Normalized device depth of the near and far planes. For reverse-Z the
+    // far value is a point in between since infinity can't be unprojected.
+    pub(super) fn depth_range(&self) -> (f32, f32) {
+        match self {
+            Projection::InfiniteReverseZ { .. } => (1.0, 0.5),
+            _ => (-1.0, 1.0),
+        }
+    }
+
+    // Window depth, as stored in the depth buffer, to normalized device depth.
+    pub(super) fn ndc_depth(&self, depth: f32) -> f32 {
+        match self {
+            Projection::InfiniteReverseZ { .. } => depth,
+            _ => depth * 2.0 - 1.0,
+        }
+    }
+
+    pub(super) fn window_depth(&self, ndc: f32) -> f32 {
+        match self {
+            Projection::InfiniteReverseZ { .. } => ndc,
+            _ => (ndc + 1.0) * 0.5,
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use log::info;
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use super::{
+    camera::Camera,
+    gl,
+    resource::{defer_delete, GpuResource},
+    shader::{Shader, ShaderError},
+    texture::{Cubemap, Texture, TextureError},
+};
+
+const CUBEMAP_VERTEX_SOURCE: &str = include_str!("../shader/glsl/cubemap.vert");
+const EQUIRECTANGULAR_FRAGMENT_SOURCE: &str = include_str!("../shader/glsl/equirectangular.frag");
+const IRRADIANCE_FRAGMENT_SOURCE: &str = include_str!("../shader/glsl/irradiance.frag");
+const PREFILTER_FRAGMENT_SOURCE: &str = include_str!("../shader/glsl/prefilter.frag");
+const BRDF_VERTEX_SOURCE: &str = include_str!("../shader/glsl/brdf.vert");
+const BRDF_FRAGMENT_SOURCE: &str = include_str!("../shader/glsl/brdf.frag");
+const SKYBOX_VERTEX_SOURCE: &str = include_str!("../shader/glsl/skybox.vert");
+const SKYBOX_FRAGMENT_SOURCE: &str = include_str!("../shader/glsl/skybox.frag");
+const IBL_COMMON_SOURCE: &str = include_str!("../shader/glsl/ibl_common.glsl");
+
+const IRRADIANCE_SIZE: u32 = 32;
+const PREFILTER_SIZE: u32 = 128;
+const PREFILTER_MIP_LEVELS: u32 = 5;
+const BRDF_LUT_SIZE: u32 = 512;
+
+pub const IRRADIANCE_MAP_UNIT: u32 = 5;
+pub const PREFILTER_MAP_UNIT: u32 = 6;
+pub const BRDF_LUT_UNIT: u32 = 7;
+
+// The environment of a scene: a skybox drawn behind everything else, plus the
+// precomputed maps used by the PBR shader for image-based lighting.
+pub struct Environment {
+    skybox: Cubemap,
+    irradiance: Cubemap,
+    prefiltered: Cubemap,
+    brdf_lut: Texture,
+
+    skybox_shader: Shader,
+    cube: UnitCube,
+}
+
+impl Environment {
+    pub fn from_faces(faces: [&str; 6]) -> Result<Self, EnvironmentError> {
+        let skybox = Cubemap::from_faces(faces)?;
+        Self::from_cubemap(skybox)
+    }
+
+    pub fn from_equirectangular(path: &str, size: u32) -> Result<Self, EnvironmentError> {
+        let equirectangular = Texture::new_hdr(path)?;
+        let cube = UnitCube::new();
+        let capture = CaptureTarget::new();
+
+        let shader = Shader::from_source(CUBEMAP_VERTEX_SOURCE, EQUIRECTANGULAR_FRAGMENT_SOURCE)?;
+        let skybox = Cubemap::empty(size, true);
+
+        unsafe { gl::UseProgram(shader.get_id()) };
+        shader.set_int("equirectangularMap", 0);
+        equirectangular.bind(0);
+
+        capture.render_faces(&skybox, 0, &shader, &cube);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox.get_id());
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+        }
+
+        Self::from_cubemap(skybox)
+    }
+
+    pub fn from_cubemap(skybox: Cubemap) -> Result<Self, EnvironmentError> {
+        info!(target: "GEAR", "Generating environment lighting maps");
+
+        let cube = UnitCube::new();
+        let capture = CaptureTarget::new();
+
+        let irradiance_shader =
+            Shader::from_source(CUBEMAP_VERTEX_SOURCE, IRRADIANCE_FRAGMENT_SOURCE)?;
+        let prefilter_shader = Shader::from_source(
+            CUBEMAP_VERTEX_SOURCE,
+            &with_common(PREFILTER_FRAGMENT_SOURCE),
+        )?;
+        let brdf_shader =
+            Shader::from_source(BRDF_VERTEX_SOURCE, &with_common(BRDF_FRAGMENT_SOURCE))?;
+        let skybox_shader = Shader::from_source(SKYBOX_VERTEX_SOURCE, SKYBOX_FRAGMENT_SOURCE)?;
+
+        // diffuse irradiance
+        let irradiance = Cubemap::empty(IRRADIANCE_SIZE, false);
+        unsafe { gl::UseProgram(irradiance_shader.get_id()) };
+        irradiance_shader.set_int("environmentMap", 0);
+        skybox.bind(0);
+        capture.render_faces(&irradiance, 0, &irradiance_shader, &cube);
+
+        // specular prefiltered map, one roughness level per mip
+        let prefiltered = Cubemap::empty(PREFILTER_SIZE, true);
+        unsafe { gl::UseProgram(prefilter_shader.get_id()) };
+        prefilter_shader.set_int("environmentMap", 0);
+        prefilter_shader.set_float("resolution", skybox.get_size() as f32);
+        skybox.bind(0);
+        for mip in 0..PREFILTER_MIP_LEVELS {
+            let roughness = mip as f32 / (PREFILTER_MIP_LEVELS - 1) as f32;
+            prefilter_shader.set_float("roughness", roughness);
+            capture.render_faces(&prefiltered, mip, &prefilter_shader, &cube);
+        }
+
+        // BRDF integration lookup table
+        let brdf_lut = Texture::empty_rg16f(BRDF_LUT_SIZE, BRDF_LUT_SIZE);
+        unsafe { gl::UseProgram(brdf_shader.get_id()) };
+        capture.render_quad(&brdf_lut);
+
+        Ok(Self {
+            skybox,
+            irradiance,
+            prefiltered,
+            brdf_lut,
+            skybox_shader,
+            cube,
+        })
+    }
+
+    pub fn get_skybox(&self) -> &Cubemap {
+        &self.skybox
+    }
+
+    pub fn max_reflection_lod(&self) -> f32 {
+        (PREFILTER_MIP_LEVELS - 1) as f32
+    }
+
+    pub fn bind_lighting_maps(&self) {
+        self.irradiance.bind(IRRADIANCE_MAP_UNIT);
+        self.prefiltered.bind(PREFILTER_MAP_UNIT);
+        self.brdf_lut.bind(BRDF_LUT_UNIT);
+    }
+
+    // Draws the skybox at the far plane. Meant to run after the opaque
+    // geometry so only the uncovered pixels are shaded.
+    pub fn draw_skybox(&self, camera: &Camera, aspect_ratio: f32) {
+        let shader = &self.skybox_shader;
+        let reverse_z = camera.projection.is_reverse_z();
+
+        unsafe {
+            gl::DepthFunc(if reverse_z { gl::GEQUAL } else { gl::LEQUAL });
+            gl::UseProgram(shader.get_id());
+        }
+
+        shader.set_mat4("view", &camera.get_view_matrix());
+        shader.set_mat4("projection", &camera.get_projection_matrix(aspect_ratio));
+        shader.set_float("farDepth", if reverse_z { 0.0 } else { 1.0 });
+        shader.set_int("environmentMap", 0);
+        self.skybox.bind(0);
+
+        self.cube.draw();
+
+        unsafe { gl::DepthFunc(if reverse_z { gl::GREATER } else { gl::LESS }) };
+    }
+}
+
+#[derive(Debug)]
+pub enum EnvironmentError {
+    Texture(TextureError),
+    Shader(ShaderError),
+}
+
+impl From<TextureError> for EnvironmentError {
+    fn from(err: TextureError) -> Self {
+        EnvironmentError::Texture(err)
+    }
+}
+
+impl From<ShaderError> for EnvironmentError {
+    fn from(err: ShaderError) -> Self {
+        EnvironmentError::Shader(err)
+    }
+}
+
+fn with_common(source: &str) -> String {
+    let (version, body) = source.split_once('\n').unwrap_or((source, ""));
+    format!("{}\n{}\n{}", version, IBL_COMMON_SOURCE, body)
+}
+
+// Framebuffer used to render into the faces of a cubemap or a 2D texture.
+struct CaptureTarget {
+    fbo: u32,
+    rbo: u32,
+}
+
+impl CaptureTarget {
+    fn new() -> Self {
+        let mut fbo = 0;
+        let mut rbo = 0;
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenRenderbuffers(1, &mut rbo);
+        }
+
+        Self { fbo, rbo }
+    }
+
+    fn bind(&self, size: u32) -> [i32; 4] {
+        let mut viewport = [0; 4];
+
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.rbo);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                size as i32,
+                size as i32,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.rbo,
+            );
+            gl::Viewport(0, 0, size as i32, size as i32);
+        }
+
+        viewport
+    }
+
+    fn unbind(&self, viewport: [i32; 4]) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+
+    fn render_faces(&self, target: &Cubemap, mip: u32, shader: &Shader, cube: &UnitCube) {
+        let size = (target.get_size() >> mip).max(1);
+        let viewport = self.bind(size);
+
+        let projection = Matrix4::new_perspective(1.0, 90.0f32.to_radians(), 0.1, 10.0);
+        shader.set_mat4("projection", &projection);
+
+        for (face, view) in capture_views().iter().enumerate() {
+            shader.set_mat4("view", view);
+
+            unsafe {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                    target.get_id(),
+                    mip as i32,
+                );
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            }
+
+            cube.draw();
+        }
+
+        self.unbind(viewport);
+    }
+
+    fn render_quad(&self, target: &Texture) {
+        let (width, _) = target.get_size();
+        let viewport = self.bind(width);
+        let quad = ScreenQuad::new();
+
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                target.get_id(),
+                0,
+            );
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+
+        quad.draw();
+
+        self.unbind(viewport);
+    }
+}
+
+impl Drop for CaptureTarget {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::Framebuffer(self.fbo));
+        defer_delete(GpuResource::Renderbuffer(self.rbo));
+    }
+}
+
+fn capture_views() -> [Matrix4<f32>; 6] {
+    let eye = Point3::origin();
+    let look = |dir: [f32; 3], up: [f32; 3]| {
+        Matrix4::look_at_rh(&eye, &Point3::from(Vector3::from(dir)), &Vector3::from(up))
+    };
+
+    [
+        look([1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+        look([-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+        look([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        look([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+        look([0.0, 0.0, 1.0], [0.0, -1.0, 0.0]),
+        look([0.0, 0.0, -1.0], [0.0, -1.0, 0.0]),
+    ]
+}
+
+// Position-only cube, used by the cubemap captures and the skybox pass.
+struct UnitCube {
+    vao: u32,
+    vbo: u32,
+}
+
+impl UnitCube {
+    #[rustfmt::skip]
+    fn new() -> Self {
+        let vertices: [f32; 108] = [
+            -1.0,  1.0, -1.0, -1.0, -1.0, -1.0,  1.0, -1.0, -1.0,
+             1.0, -1.0, -1.0,  1.0,  1.0, -1.0, -1.0,  1.0, -1.0,
+            -1.0, -1.0,  1.0, -1.0, -1.0, -1.0, -1.0,  1.0, -1.0,
+            -1.0,  1.0, -1.0, -1.0,  1.0,  1.0, -1.0, -1.0,  1.0,
+             1.0, -1.0, -1.0,  1.0, -1.0,  1.0,  1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,  1.0,  1.0, -1.0,  1.0, -1.0, -1.0,
+            -1.0, -1.0,  1.0, -1.0,  1.0,  1.0,  1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,  1.0, -1.0,  1.0, -1.0, -1.0,  1.0,
+            -1.0,  1.0, -1.0,  1.0,  1.0, -1.0,  1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0, -1.0,  1.0,  1.0, -1.0,  1.0, -1.0,
+            -1.0, -1.0, -1.0, -1.0, -1.0,  1.0,  1.0, -1.0, -1.0,
+             1.0, -1.0, -1.0, -1.0, -1.0,  1.0,  1.0, -1.0,  1.0,
+        ];
+
+        let (vao, vbo) = upload_vertices(&vertices, &[3]);
+        Self { vao, vbo }
+    }
+
+    fn draw(&self) {
+        unsafe {
+            // the cube is seen from the inside
+            let culling = gl::IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            gl::Disable(gl::CULL_FACE);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 36);
+            gl::BindVertexArray(0);
+            if culling {
+                gl::Enable(gl::CULL_FACE);
+            }
+        }
+    }
+}
+
+impl Drop for UnitCube {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::VertexArray(self.vao));
+        defer_delete(GpuResource::Buffer(self.vbo));
+    }
+}
+
+struct ScreenQuad {
+    vao: u32,
+    vbo: u32,
+}
+
+impl ScreenQuad {
+    #[rustfmt::skip]
+    fn new() -> Self {
+        let vertices: [f32; 20] = [
+            -1.0,  1.0, 0.0, 0.0, 1.0,
+            -1.0, -1.0, 0.0, 0.0, 0.0,
+             1.0,  1.0, 0.0, 1.0, 1.0,
+             1.0, -1.0, 0.0, 1.0, 0.0,
+        ];
+
+        let (vao, vbo) = upload_vertices(&vertices, &[3, 2]);
+        Self { vao, vbo }
+    }
+
+    fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for ScreenQuad {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::VertexArray(self.vao));
+        defer_delete(GpuResource::Buffer(self.vbo));
+    }
+}
+
+fn upload_vertices(vertices: &[f32], attributes: &[i32]) -> (u32, u32) {
+    let mut vao = 0;
+    let mut vbo = 0;
+    let stride: i32 = attributes.iter().sum::<i32>() * std::mem::size_of::<f32>() as i32;
+
+    unsafe {
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            std::mem::size_of_val(vertices) as gl::types::GLsizeiptr,
+            vertices.as_ptr() as *const gl::types::GLvoid,
+            gl::STATIC_DRAW,
+        );
+
+        let mut offset = 0;
+        for (location, size) in attributes.iter().enumerate() {
+            gl::VertexAttribPointer(
+                location as u32,
+                *size,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (offset * std::mem::size_of::<f32>()) as *const gl::types::GLvoid,
+            );
+            gl::EnableVertexAttribArray(location as u32);
+            offset += *size as usize;
+        }
+
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        gl::BindVertexArray(0);
+    }
+
+    (vao, vbo)
+}
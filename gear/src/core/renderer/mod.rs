@@ -1,105 +1,334 @@
+pub mod buffer;
 pub mod camera;
+pub mod environment;
+pub mod picking;
+pub mod resource;
 pub mod shader;
+mod support;
+pub mod target;
+pub mod texture;
+
+use log::{error, warn};
+use nalgebra::Vector3;
+
+use self::{
+    camera::{Camera, ClearMode, DEFAULT_RENDER_LAYER, REVERSE_Z_FALLBACK_FAR},
+    picking::{Picking, PickingPass},
+    resource::flush_deletions,
+    shader::{Shader, MAX_LIGHTS, PBR_SHADER},
+    support::gl_supports,
+};
 
 use super::{
     application::context::Context,
-    entity::{EntityBuffer, Renderable},
+    assets::{AssetError, AssetsManager},
+    ecs::{EntityId, World},
+    entity::{GlobalTransform, Light, Mesh, RenderLayers, Renderable},
 };
 
 pub mod gl {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
-pub struct Renderer {}
+// Camera being drawn and what every draw of the view shares.
+struct View<'a> {
+    camera: &'a Camera,
+    aspect_ratio: f32,
+    lights: &'a [(Vector3<f32>, Light)],
+}
+
+pub struct Renderer {
+    // created while the world has a `Picking` resource
+    picking: Option<PickingPass>,
+    // glClipControl is GL 4.5 or GL_ARB_clip_control, reverse-Z needs it. A
+    // driver may export the entry point without supporting it
+    clip_control: bool,
+    // reverse-Z of the current GL depth state, `None` before the first view
+    depth_mode: Option<bool>,
+    // the world has more than `MAX_LIGHTS` lights, warned once until it drops
+    // back under
+    lights_capped: bool,
+}
 
 impl Renderer {
     pub fn new() -> Self {
-        Renderer {}
+        Renderer {
+            picking: None,
+            clip_control: false,
+            depth_mode: None,
+            lights_capped: false,
+        }
     }
 
     pub fn init(&mut self, ctx: &mut Context) {
-        let Context {
-            assets,
-            window,
-            entity_buffer,
-            ..
-        } = ctx;
+        let Context { assets, window, .. } = ctx;
         gl::load_with(|name| window.get_proc_address(name));
-        unsafe { gl::Enable(gl::DEPTH_TEST) }
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        }
+        self.clip_control =
+            gl_supports(4, 5, "GL_ARB_clip_control") && gl::ClipControl::is_loaded();
+        self.depth_mode = None;
 
-        for entity in entity_buffer.entities.iter_mut() {
-            entity.init(assets);
+        match Shader::pbr() {
+            Ok(shader) => {
+                assets.put_shader(PBR_SHADER, shader);
+            }
+            Err(err) => error!(target: "GEAR", "Failed to compile PBR shader: {:?}", err),
         }
     }
 
     pub fn render(&mut self, ctx: &mut Context) {
+        // GPU work queued since the last frame: objects dropped anywhere in
+        // the engine and meshes created or modified by the application
+        flush_deletions();
+        ctx.assets.upload_meshes();
+        if !self.clip_control {
+            fall_back_from_reverse_z(ctx);
+        }
+
+        let lights = collect_lights(&ctx.world);
+        let capped = lights.len() > MAX_LIGHTS;
+        if capped && !self.lights_capped {
+            warn!(target: "GEAR", "Only the first {} lights are used", MAX_LIGHTS);
+        }
+        self.lights_capped = capped;
+
+        // stable, the main camera goes first among equal priorities
+        let ctx = &*ctx;
+        let mut cameras: Vec<&Camera> = std::iter::once(&ctx.camera)
+            .chain(ctx.cameras.iter().map(|(_, camera)| camera))
+            .collect();
+        cameras.sort_by_key(|camera| camera.priority);
+        for camera in cameras {
+            self.render_view(ctx, camera, &lights);
+        }
+
+        let (width, height) = ctx.window.get_framebuffer_size();
         unsafe {
-            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+        self.set_depth_mode(ctx.camera.projection.is_reverse_z());
+
+        self.render_picking(ctx);
+    }
+
+    fn render_picking(&mut self, ctx: &Context) {
+        let Some(mut picking) = ctx.world.resource_mut::<Picking>() else {
+            self.picking = None;
+            return;
+        };
+
+        if self.picking.is_none() {
+            match PickingPass::new() {
+                Ok(pass) => self.picking = Some(pass),
+                Err(err) => {
+                    error!(target: "GEAR", "Failed to compile picking shader: {:?}", err);
+                    return;
+                }
+            }
+        }
 
-            for entity in ctx.entity_buffer.entities.iter() {
-                self.render_renderable(ctx, &entity.renderable);
+        if let Some(pass) = &mut self.picking {
+            if picking.is_blocking() {
+                pass.render(ctx);
+                pass.read_pending(&ctx.window, &ctx.world, &mut picking);
+            } else {
+                pass.poll(&ctx.world, &mut picking);
+                pass.render(ctx);
+                pass.read_async(&ctx.window, &mut picking);
             }
-            self.render_light(ctx);
         }
     }
 
-    fn render_light(&self, ctx: &Context) {
-        let Context {
-            assets,
-            entity_buffer,
+    // Reverse-Z projections put the near plane at depth 1 and infinity at 0,
+    // so the clip range, the cleared depth and the depth test are flipped for
+    // them. The GL state only changes with the mode.
+    fn set_depth_mode(&mut self, reverse_z: bool) {
+        if self.depth_mode == Some(reverse_z) {
+            return;
+        }
+        self.depth_mode = Some(reverse_z);
+
+        unsafe {
+            if self.clip_control {
+                let depth = if reverse_z {
+                    gl::ZERO_TO_ONE
+                } else {
+                    gl::NEGATIVE_ONE_TO_ONE
+                };
+                gl::ClipControl(gl::LOWER_LEFT, depth);
+            }
+            if reverse_z {
+                gl::ClearDepth(0.0);
+                gl::DepthFunc(gl::GREATER);
+            } else {
+                gl::ClearDepth(1.0);
+                gl::DepthFunc(gl::LESS);
+            }
+        }
+    }
+
+    fn render_view(&mut self, ctx: &Context, camera: &Camera, lights: &[(Vector3<f32>, Light)]) {
+        let (width, height) = match &camera.target {
+            Some(target) => target.size(),
+            None => ctx.window.get_framebuffer_size(),
+        };
+        let viewport = camera.viewport.to_viewport(width as f32, height as f32);
+        let [x, y, width, height] = viewport.to_gl(height as f32);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        unsafe {
+            let framebuffer = camera
+                .target
+                .as_ref()
+                .map_or(0, |target| target.framebuffer());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::Viewport(x, y, width, height);
+        }
+        self.set_depth_mode(camera.projection.is_reverse_z());
+
+        // the scissor keeps the clear inside the viewport
+        let mask = match camera.clear {
+            ClearMode::Color(color) => unsafe {
+                gl::ClearColor(color.x, color.y, color.z, color.w);
+                gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT
+            },
+            ClearMode::Depth => gl::DEPTH_BUFFER_BIT,
+            ClearMode::Keep => 0,
+        };
+        if mask != 0 {
+            unsafe {
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(x, y, width, height);
+                gl::Clear(mask);
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+        }
+
+        let view = View {
             camera,
-            window,
-            ..
-        } = ctx;
-        let EntityBuffer { light, .. } = entity_buffer;
-        let light = light.as_ref().unwrap();
+            aspect_ratio: viewport.aspect_ratio(),
+            lights,
+        };
+        ctx.world
+            .query::<(&GlobalTransform, &Renderable)>()
+            .for_each(|entity, (transform, renderable)| {
+                let layers = ctx
+                    .world
+                    .get::<RenderLayers>(entity)
+                    .map_or(DEFAULT_RENDER_LAYER, |layers| layers.0);
+                if !camera.sees(layers) {
+                    return;
+                }
+
+                // light entities are drawn as unlit gizmos in their color
+                match ctx.world.get::<Light>(entity) {
+                    Some(light) => {
+                        self.render_light(ctx, &view, entity, transform, renderable, &light)
+                    }
+                    None => self.render_renderable(ctx, &view, entity, transform, renderable),
+                }
+            });
 
-        let shader = assets.get_shader("light").unwrap();
+        if let Some(environment) = &ctx.environment {
+            environment.draw_skybox(camera, view.aspect_ratio);
+        }
+    }
+
+    fn render_light(
+        &self,
+        ctx: &Context,
+        view: &View,
+        entity: EntityId,
+        transform: &GlobalTransform,
+        renderable: &Renderable,
+        light: &Light,
+    ) {
+        let assets = &ctx.assets;
+
+        let (shader, mesh) = match resolve(assets, renderable) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                warn!(target: "GEAR", "Skipping light {}: {:?}", entity, err);
+                return;
+            }
+        };
         unsafe { gl::UseProgram(shader.get_id()) };
 
-        shader.with_transform(&light.entity.renderable.transform);
-        shader.with_camera(&camera, window.get_aspect_ratio());
+        shader.with_model_matrix(transform.matrix());
+        shader.with_camera(view.camera, view.aspect_ratio);
         shader.set_vec3("lightColor", &light.color);
 
-        unsafe {
-            gl::BindVertexArray(light.entity.renderable.mesh_id);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                light.entity.renderable.mesh_indices as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
-        }
+        mesh.draw();
     }
 
-    fn render_renderable(&self, ctx: &Context, renderable: &Renderable) {
+    fn render_renderable(
+        &self,
+        ctx: &Context,
+        view: &View,
+        entity: EntityId,
+        transform: &GlobalTransform,
+        renderable: &Renderable,
+    ) {
         let Context {
             assets,
-            camera,
-            window,
-            entity_buffer,
+            environment,
             ..
         } = ctx;
-        let EntityBuffer { light, .. } = entity_buffer;
 
-        let shader = renderable.shader.as_str();
-        let shader = assets.get_shader(shader).unwrap();
+        let (shader, mesh) = match resolve(assets, renderable) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                warn!(target: "GEAR", "Skipping entity {}: {:?}", entity, err);
+                return;
+            }
+        };
         unsafe { gl::UseProgram(shader.get_id()) };
 
-        shader.with_transform(&renderable.transform);
-        shader.with_material(&renderable.material);
-        shader.with_camera(&camera, window.get_aspect_ratio());
-        shader.with_light(&light.as_ref().unwrap());
+        shader.with_model_matrix(transform.matrix());
+        shader.with_material(&renderable.material, assets);
+        shader.with_camera(view.camera, view.aspect_ratio);
+        shader.with_lights(view.lights);
+        shader.with_environment(environment.as_ref());
 
-        unsafe {
-            gl::BindVertexArray(renderable.mesh_id);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                renderable.mesh_indices as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
-        }
+        mesh.draw();
+    }
+}
+
+fn resolve<'a>(
+    assets: &'a AssetsManager,
+    renderable: &Renderable,
+) -> Result<(&'a Shader, &'a Mesh), AssetError> {
+    Ok((
+        assets.shader(renderable.shader)?,
+        assets.mesh(renderable.mesh)?,
+    ))
+}
+
+fn collect_lights(world: &World) -> Vec<(Vector3<f32>, Light)> {
+    let mut lights = Vec::new();
+    world
+        .query::<(&GlobalTransform, &Light)>()
+        .for_each(|_, (transform, light)| lights.push((transform.position(), light.clone())));
+    lights
+}
+
+// Without glClipControl the reverse-Z depth would land in half the buffer and
+// picking would unproject it wrong, those cameras get a far plane instead.
+fn fall_back_from_reverse_z(ctx: &mut Context) {
+    let cameras =
+        std::iter::once(&mut ctx.camera).chain(ctx.cameras.iter_mut().map(|(_, camera)| camera));
+    for camera in cameras.filter(|camera| camera.projection.is_reverse_z()) {
+        warn!(
+            target: "GEAR",
+            "Reverse-Z needs glClipControl (GL 4.5), using a far plane at {}",
+            REVERSE_Z_FALLBACK_FAR
+        );
+        camera.projection = camera.projection.without_reverse_z();
     }
 }
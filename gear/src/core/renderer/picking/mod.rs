@@ -0,0 +1,399 @@
+use std::collections::VecDeque;
+
+use log::warn;
+use nalgebra::Vector2;
+
+use crate::core::{
+    application::context::Context,
+    ecs::{EntityId, World},
+    entity::{GlobalTransform, RenderLayers, Renderable},
+    window::Window,
+};
+
+use super::{
+    camera::DEFAULT_RENDER_LAYER,
+    gl,
+    resource::{defer_delete, GpuResource},
+    shader::{Shader, ShaderError},
+};
+
+// Reads in flight at the same time, each one owns a pixel pack buffer.
+const READBACKS: usize = 3;
+
+// Insert this resource in the world to enable the ID pass: every renderable
+// is drawn once more with its entity id into an integer attachment, so picks
+// match what is on screen pixel for pixel. Picks go through this resource
+// rather than a `Renderer::pick(x, y)` call, which couldn't return the
+// entity before the ID pass of the next frame has been read back.
+pub struct Picking {
+    requests: VecDeque<(f32, f32)>,
+    result: Option<PickResult>,
+    blocking: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PickResult {
+    // Window coordinates of the request
+    pub x: f32,
+    pub y: f32,
+    // `None` over the background or when the entity was despawned since
+    pub entity: Option<EntityId>,
+}
+
+impl Picking {
+    pub fn new() -> Self {
+        Self {
+            requests: VecDeque::new(),
+            result: None,
+            blocking: false,
+        }
+    }
+
+    // Reads the requests right after the ID pass, waiting for the GPU, so
+    // the result is there on the next update. Stalls the frame, meant for
+    // editors and tools rather than per frame hover.
+    pub fn with_blocking(mut self, blocking: bool) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    // Window coordinates, e.g. the cursor position. The read back is
+    // asynchronous, the result shows up in `result` a frame or two later.
+    pub fn request(&mut self, x: f32, y: f32) {
+        self.requests.push_back((x, y));
+    }
+
+    // Latest completed pick.
+    pub fn result(&self) -> Option<PickResult> {
+        self.result
+    }
+
+    pub fn take_result(&mut self) -> Option<PickResult> {
+        self.result.take()
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.requests.is_empty()
+    }
+}
+
+struct Readback {
+    pbo: u32,
+    fence: gl::types::GLsync,
+    request: (f32, f32),
+}
+
+pub(super) struct PickingPass {
+    fbo: u32,
+    ids: u32,
+    depth: u32,
+    width: u32,
+    height: u32,
+    shader: Shader,
+    readbacks: Vec<Readback>,
+    // indices in `readbacks`, oldest first
+    in_flight: VecDeque<usize>,
+}
+
+impl PickingPass {
+    pub fn new() -> Result<Self, ShaderError> {
+        let shader = Shader::picking()?;
+        let mut fbo = 0;
+        let mut ids = 0;
+        let mut depth = 0;
+        let mut readbacks = Vec::with_capacity(READBACKS);
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenTextures(1, &mut ids);
+            gl::GenRenderbuffers(1, &mut depth);
+
+            for _ in 0..READBACKS {
+                let mut pbo = 0;
+                gl::GenBuffers(1, &mut pbo);
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+                gl::BufferData(
+                    gl::PIXEL_PACK_BUFFER,
+                    std::mem::size_of::<[u32; 2]>() as isize,
+                    std::ptr::null(),
+                    gl::STREAM_READ,
+                );
+                readbacks.push(Readback {
+                    pbo,
+                    fence: std::ptr::null(),
+                    request: (0.0, 0.0),
+                });
+            }
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        }
+
+        Ok(Self {
+            fbo,
+            ids,
+            depth,
+            width: 0,
+            height: 0,
+            shader,
+            readbacks,
+            in_flight: VecDeque::new(),
+        })
+    }
+
+    // Reallocates the attachments when the framebuffer size changed.
+    fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.ids);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RG32UI as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RG_INTEGER,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                width as i32,
+                height as i32,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.ids,
+                0,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.depth,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                warn!(target: "GEAR", "Picking framebuffer is incomplete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    // Draws the ids of every renderable the main camera sees, with the same
+    // transforms, camera and layers as the main pass.
+    pub fn render(&mut self, ctx: &Context) {
+        let (width, height) = ctx.window.get_framebuffer_size();
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.resize(width, height);
+
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+
+            let background = [0u32; 4];
+            gl::ClearBufferuiv(gl::COLOR, 0, background.as_ptr());
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+
+        // ids land where the main camera drew, so window points map directly
+        let camera = &ctx.camera;
+        let area = camera.viewport.to_viewport(width as f32, height as f32);
+        let [x, y, area_width, area_height] = area.to_gl(height as f32);
+        unsafe { gl::Viewport(x, y, area_width, area_height) };
+
+        self.shader.with_camera(camera, area.aspect_ratio());
+        ctx.world
+            .query::<(&GlobalTransform, &Renderable)>()
+            .for_each(|entity, (transform, renderable)| {
+                let layers = ctx
+                    .world
+                    .get::<RenderLayers>(entity)
+                    .map_or(DEFAULT_RENDER_LAYER, |layers| layers.0);
+                if !camera.sees(layers) {
+                    return;
+                }
+
+                let Ok(mesh) = ctx.assets.mesh(renderable.mesh) else {
+                    return;
+                };
+                self.shader.with_model_matrix(transform.matrix());
+                self.shader.set_uvec2(
+                    "entityId",
+                    &Vector2::new(entity.index() + 1, entity.generation()),
+                );
+                mesh.draw();
+            });
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+
+    // Answers every pending request right away, waiting for the GPU.
+    pub fn read_pending(&self, window: &Window, world: &World, picking: &mut Picking) {
+        while let Some((x, y)) = picking.requests.pop_front() {
+            let entity = self.read(window, world, x, y);
+            picking.result = Some(PickResult { x, y, entity });
+        }
+    }
+
+    fn read(&self, window: &Window, world: &World, x: f32, y: f32) -> Option<EntityId> {
+        let (px, py) = self.to_pixel(window, x, y)?;
+        let mut id = [0u32; 2];
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            gl::ReadPixels(
+                px,
+                py,
+                1,
+                1,
+                gl::RG_INTEGER,
+                gl::UNSIGNED_INT,
+                id.as_mut_ptr() as *mut gl::types::GLvoid,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+
+        decode(world, id)
+    }
+
+    // Starts a read for each pending request while a pixel pack buffer is
+    // free. The copy happens on the GPU, the CPU picks it up in `poll`.
+    pub fn read_async(&mut self, window: &Window, picking: &mut Picking) {
+        while self.in_flight.len() < READBACKS {
+            let Some((x, y)) = picking.requests.pop_front() else {
+                break;
+            };
+            let Some((px, py)) = self.to_pixel(window, x, y) else {
+                picking.result = Some(PickResult { x, y, entity: None });
+                continue;
+            };
+
+            let slot = (0..READBACKS)
+                .find(|slot| !self.in_flight.contains(slot))
+                .unwrap_or(0);
+            let readback = &mut self.readbacks[slot];
+            readback.request = (x, y);
+
+            unsafe {
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, readback.pbo);
+                gl::ReadPixels(
+                    px,
+                    py,
+                    1,
+                    1,
+                    gl::RG_INTEGER,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null_mut(),
+                );
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+
+                readback.fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            }
+            self.in_flight.push_back(slot);
+        }
+    }
+
+    // Collects the reads the GPU finished, without blocking.
+    pub fn poll(&mut self, world: &World, picking: &mut Picking) {
+        while let Some(&slot) = self.in_flight.front() {
+            let readback = &mut self.readbacks[slot];
+
+            unsafe {
+                let status = gl::ClientWaitSync(readback.fence, 0, 0);
+                if status != gl::ALREADY_SIGNALED && status != gl::CONDITION_SATISFIED {
+                    break;
+                }
+                gl::DeleteSync(readback.fence);
+                readback.fence = std::ptr::null();
+
+                let mut id = [0u32; 2];
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, readback.pbo);
+                let mapped = gl::MapBufferRange(
+                    gl::PIXEL_PACK_BUFFER,
+                    0,
+                    std::mem::size_of_val(&id) as isize,
+                    gl::MAP_READ_BIT,
+                ) as *const u32;
+                if !mapped.is_null() {
+                    std::ptr::copy_nonoverlapping(mapped, id.as_mut_ptr(), id.len());
+                    gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER);
+                }
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+
+                let (x, y) = readback.request;
+                picking.result = Some(PickResult {
+                    x,
+                    y,
+                    entity: decode(world, id),
+                });
+            }
+            self.in_flight.pop_front();
+        }
+    }
+
+    // Window coordinates (top left origin) to framebuffer pixels (bottom left
+    // origin), `None` outside of the window.
+    fn to_pixel(&self, window: &Window, x: f32, y: f32) -> Option<(i32, i32)> {
+        let (width, height) = window.get_size();
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let px = (x * self.width as f32 / width as f32).floor() as i32;
+        let py = (y * self.height as f32 / height as f32).floor() as i32;
+        if px < 0 || py < 0 || px >= self.width as i32 || py >= self.height as i32 {
+            return None;
+        }
+        Some((px, self.height as i32 - 1 - py))
+    }
+}
+
+impl Drop for PickingPass {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::Framebuffer(self.fbo));
+        defer_delete(GpuResource::Texture(self.ids));
+        defer_delete(GpuResource::Renderbuffer(self.depth));
+        for readback in self.readbacks.iter() {
+            defer_delete(GpuResource::Buffer(readback.pbo));
+            defer_delete(GpuResource::Sync(readback.fence as usize));
+        }
+    }
+}
+
+fn decode(world: &World, id: [u32; 2]) -> Option<EntityId> {
+    if id[0] == 0 {
+        return None;
+    }
+
+    let entity = EntityId::from_raw(id[0] - 1, id[1]);
+    world.is_alive(entity).then_some(entity)
+}
@@ -0,0 +1,63 @@
+use std::sync::Mutex;
+
+use super::gl;
+
+// GPU objects whose owner was dropped. Dropping never touches OpenGL directly
+// (there may be no current context, or we may be on another thread); names
+// are queued here and deleted by the renderer on the render thread.
+#[derive(Debug, Copy, Clone)]
+pub enum GpuResource {
+    VertexArray(u32),
+    Buffer(u32),
+    Program(u32),
+    Texture(u32),
+    Framebuffer(u32),
+    Renderbuffer(u32),
+    // GLsync objects are pointers, stored as an address
+    Sync(usize),
+}
+
+static DELETION_QUEUE: Mutex<Vec<GpuResource>> = Mutex::new(Vec::new());
+
+pub fn defer_delete(resource: GpuResource) {
+    // zero is never a valid object name
+    let is_null = match resource {
+        GpuResource::VertexArray(name)
+        | GpuResource::Buffer(name)
+        | GpuResource::Program(name)
+        | GpuResource::Texture(name)
+        | GpuResource::Framebuffer(name)
+        | GpuResource::Renderbuffer(name) => name == 0,
+        GpuResource::Sync(address) => address == 0,
+    };
+
+    if is_null {
+        return;
+    }
+
+    if let Ok(mut queue) = DELETION_QUEUE.lock() {
+        queue.push(resource);
+    }
+}
+
+// Deletes every queued object. Must be called with the GL context current.
+pub fn flush_deletions() {
+    let resources = match DELETION_QUEUE.lock() {
+        Ok(mut queue) => std::mem::take(&mut *queue),
+        Err(_) => return,
+    };
+
+    for resource in resources {
+        unsafe {
+            match resource {
+                GpuResource::VertexArray(name) => gl::DeleteVertexArrays(1, &name),
+                GpuResource::Buffer(name) => gl::DeleteBuffers(1, &name),
+                GpuResource::Program(name) => gl::DeleteProgram(name),
+                GpuResource::Texture(name) => gl::DeleteTextures(1, &name),
+                GpuResource::Framebuffer(name) => gl::DeleteFramebuffers(1, &name),
+                GpuResource::Renderbuffer(name) => gl::DeleteRenderbuffers(1, &name),
+                GpuResource::Sync(address) => gl::DeleteSync(address as gl::types::GLsync),
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+#version 330 core
+
+out vec4 FragColor;
+
+in vec3 LocalPos;
+
+uniform sampler2D equirectangularMap;
+
+const vec2 invAtan = vec2(0.1591, 0.3183);
+
+vec2 sampleSphericalMap(vec3 v) {
+    vec2 uv = vec2(atan(v.z, v.x), asin(v.y));
+    return uv * invAtan + 0.5;
+}
+
+void main() {
+    vec2 uv = sampleSphericalMap(normalize(LocalPos));
+    FragColor = vec4(texture(equirectangularMap, uv).rgb, 1.0);
+}
@@ -0,0 +1,147 @@
+#version 330 core
+
+#define MAX_LIGHTS 16
+
+const float PI = 3.14159265359;
+
+out vec4 FragColor;
+
+struct Material {
+    vec4 baseColor;
+    float metallic;
+    float roughness;
+    float ao;
+    vec3 emissive;
+
+    bool useBaseColorMap;
+    bool useMetallicMap;
+    bool useRoughnessMap;
+    bool useAoMap;
+    bool useEmissiveMap;
+};
+
+struct Light {
+    vec3 position;
+    vec3 color;
+    float intensity;
+    float ambient;
+};
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+
+uniform Material material;
+uniform sampler2D baseColorMap;
+uniform sampler2D metallicMap;
+uniform sampler2D roughnessMap;
+uniform sampler2D aoMap;
+uniform sampler2D emissiveMap;
+
+uniform Light lights[MAX_LIGHTS];
+uniform int lightCount;
+uniform vec3 viewPos;
+
+uniform bool useIBL;
+uniform samplerCube irradianceMap;
+uniform samplerCube prefilterMap;
+uniform sampler2D brdfLUT;
+uniform float maxReflectionLod;
+
+float distributionGGX(vec3 N, vec3 H, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float NdotH = max(dot(N, H), 0.0);
+    float denom = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+float geometrySchlickGGX(float NdotV, float roughness) {
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    return NdotV / (NdotV * (1.0 - k) + k);
+}
+
+float geometrySmith(vec3 N, vec3 V, vec3 L, float roughness) {
+    float NdotV = max(dot(N, V), 0.0);
+    float NdotL = max(dot(N, L), 0.0);
+    return geometrySchlickGGX(NdotV, roughness) * geometrySchlickGGX(NdotL, roughness);
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 F0) {
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness) {
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+void main() {
+    vec4 baseColor = material.baseColor;
+    if (material.useBaseColorMap) {
+        vec4 texel = texture(baseColorMap, TexCoords);
+        baseColor = vec4(pow(texel.rgb, vec3(2.2)), texel.a);
+    }
+
+    float metallic = material.useMetallicMap ? texture(metallicMap, TexCoords).b : material.metallic;
+    float roughness = material.useRoughnessMap ? texture(roughnessMap, TexCoords).g : material.roughness;
+    float ao = material.useAoMap ? texture(aoMap, TexCoords).r : material.ao;
+    vec3 emissive = material.useEmissiveMap
+        ? pow(texture(emissiveMap, TexCoords).rgb, vec3(2.2))
+        : material.emissive;
+
+    roughness = clamp(roughness, 0.04, 1.0);
+
+    vec3 albedo = baseColor.rgb;
+    vec3 N = normalize(Normal);
+    vec3 V = normalize(viewPos - FragPos);
+
+    vec3 F0 = mix(vec3(0.04), albedo, metallic);
+
+    vec3 Lo = vec3(0.0);
+    vec3 ambient = vec3(0.0);
+    for (int i = 0; i < min(lightCount, MAX_LIGHTS); ++i) {
+        vec3 L = normalize(lights[i].position - FragPos);
+        vec3 H = normalize(V + L);
+        float distance = length(lights[i].position - FragPos);
+        float attenuation = 1.0 / (distance * distance);
+        vec3 radiance = lights[i].color * lights[i].intensity * attenuation;
+
+        float NDF = distributionGGX(N, H, roughness);
+        float G = geometrySmith(N, V, L, roughness);
+        vec3 F = fresnelSchlick(max(dot(H, V), 0.0), F0);
+
+        vec3 specular = (NDF * G * F) / (4.0 * max(dot(N, V), 0.0) * max(dot(N, L), 0.0) + 0.0001);
+
+        vec3 kD = (vec3(1.0) - F) * (1.0 - metallic);
+
+        float NdotL = max(dot(N, L), 0.0);
+        Lo += (kD * albedo / PI + specular) * radiance * NdotL;
+
+        ambient += lights[i].ambient * lights[i].color * albedo * ao;
+    }
+
+    if (useIBL) {
+        float NdotV = max(dot(N, V), 0.0);
+        vec3 F = fresnelSchlickRoughness(NdotV, F0, roughness);
+        vec3 kD = (vec3(1.0) - F) * (1.0 - metallic);
+
+        vec3 irradiance = texture(irradianceMap, N).rgb;
+        vec3 diffuse = irradiance * albedo;
+
+        vec3 R = reflect(-V, N);
+        vec3 prefilteredColor = textureLod(prefilterMap, R, roughness * maxReflectionLod).rgb;
+        vec2 brdf = texture(brdfLUT, vec2(NdotV, roughness)).rg;
+        vec3 specular = prefilteredColor * (F * brdf.x + brdf.y);
+
+        ambient = (kD * diffuse + specular) * ao;
+    }
+
+    vec3 color = ambient + Lo + emissive;
+
+    // Reinhard tone mapping and gamma correction
+    color = color / (color + vec3(1.0));
+    color = pow(color, vec3(1.0 / 2.2));
+
+    FragColor = vec4(color, baseColor.a);
+}
@@ -0,0 +1,10 @@
+#version 330 core
+
+// entity index + 1 (0 is the background) and generation
+uniform uvec2 entityId;
+
+out uvec2 FragId;
+
+void main() {
+    FragId = entityId;
+}
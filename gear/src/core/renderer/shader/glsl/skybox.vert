@@ -0,0 +1,21 @@
+#version 330 core
+
+layout(location = 0) in vec3 aPos;
+
+uniform mat4 view;
+uniform mat4 projection;
+// normalized depth of the far plane, 0 with reverse-Z
+uniform float farDepth;
+
+out vec3 LocalPos;
+
+void main() {
+    LocalPos = aPos;
+
+    // drop the translation so the skybox follows the camera
+    mat4 rotView = mat4(mat3(view));
+    vec4 clipPos = projection * rotView * vec4(aPos, 1.0);
+
+    // force the depth to the far plane
+    gl_Position = vec4(clipPos.xy, clipPos.w * farDepth, clipPos.w);
+}
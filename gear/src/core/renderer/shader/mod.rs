@@ -2,10 +2,29 @@ mod uniform;
 
 use std::ffi::CString;
 
-use crate::core::entity::{self, Light};
+use log::warn;
+use nalgebra::{Matrix4, Vector3};
+
+use crate::core::{
+    assets::AssetsManager,
+    entity::{self, Light, MaterialInput},
+};
 
 use self::gl::types::{GLchar, GLint};
-use super::{camera::Camera, gl};
+use super::{
+    camera::Camera,
+    environment::{Environment, BRDF_LUT_UNIT, IRRADIANCE_MAP_UNIT, PREFILTER_MAP_UNIT},
+    gl,
+    resource::{defer_delete, GpuResource},
+};
+
+pub const PBR_SHADER: &str = "pbr";
+pub const MAX_LIGHTS: usize = 16;
+
+const PBR_VERTEX_SOURCE: &str = include_str!("glsl/pbr.vert");
+const PBR_FRAGMENT_SOURCE: &str = include_str!("glsl/pbr.frag");
+const PICKING_VERTEX_SOURCE: &str = include_str!("glsl/picking.vert");
+const PICKING_FRAGMENT_SOURCE: &str = include_str!("glsl/picking.frag");
 
 pub struct Shader {
     id: u32,
@@ -19,9 +38,30 @@ impl Shader {
         Ok(shader)
     }
 
+    pub fn from_source(vertex_source: &str, fragment_source: &str) -> Result<Self, ShaderError> {
+        let vertex_shader = unsafe { compile_shader(vertex_source, gl::VERTEX_SHADER) }?;
+        let fragment_shader = unsafe { compile_shader(fragment_source, gl::FRAGMENT_SHADER) }?;
+
+        let id = unsafe { compile_program(vertex_shader, fragment_shader)? };
+
+        Ok(Self { id })
+    }
+
+    pub fn pbr() -> Result<Self, ShaderError> {
+        Self::from_source(PBR_VERTEX_SOURCE, PBR_FRAGMENT_SOURCE)
+    }
+
+    // Writes the `entityId` uniform into an unsigned integer attachment.
+    pub fn picking() -> Result<Self, ShaderError> {
+        Self::from_source(PICKING_VERTEX_SOURCE, PICKING_FRAGMENT_SOURCE)
+    }
+
     fn compile(&mut self, vertex_path: &str, fragment_path: &str) -> Result<(), ShaderError> {
-        let vertex_shader = unsafe { compile_shader(vertex_path, gl::VERTEX_SHADER) }?;
-        let fragment_shader = unsafe { compile_shader(fragment_path, gl::FRAGMENT_SHADER) }?;
+        let vertex_source = read_source(vertex_path)?;
+        let fragment_source = read_source(fragment_path)?;
+
+        let vertex_shader = unsafe { compile_shader(&vertex_source, gl::VERTEX_SHADER) }?;
+        let fragment_shader = unsafe { compile_shader(&fragment_source, gl::FRAGMENT_SHADER) }?;
 
         self.id = unsafe { compile_program(vertex_shader, fragment_shader)? };
 
@@ -33,18 +73,99 @@ impl Shader {
     }
 
     pub fn with_transform(&self, transformation: &entity::Transform) {
+        self.with_model_matrix(&transformation.get_model_matrix());
+    }
+
+    pub fn with_model_matrix(&self, model: &Matrix4<f32>) {
         unsafe {
             gl::UseProgram(self.id);
-
-            let model = transformation.get_model_matrix();
-            self.set_mat4("model", &model);
+            self.set_mat4("model", model);
         }
     }
 
-    pub fn with_material(&self, material: &entity::Material) {
+    pub fn with_material(&self, material: &entity::Material, assets: &AssetsManager) {
         unsafe {
             gl::UseProgram(self.id);
-            self.set_vec4("material", &material.color);
+        }
+
+        let base_color = match &material.base_color {
+            MaterialInput::Constant(color) => *color,
+            MaterialInput::Texture(_) => nalgebra::Vector4::new(1.0, 1.0, 1.0, 1.0),
+        };
+        self.set_vec4("material.baseColor", &base_color);
+        self.bind_material_texture(
+            "material.useBaseColorMap",
+            "baseColorMap",
+            0,
+            material.base_color.texture(),
+            assets,
+        );
+
+        let emissive = match &material.emissive {
+            MaterialInput::Constant(color) => *color,
+            MaterialInput::Texture(_) => Vector3::new(0.0, 0.0, 0.0),
+        };
+        self.set_vec3("material.emissive", &emissive);
+        self.bind_material_texture(
+            "material.useEmissiveMap",
+            "emissiveMap",
+            4,
+            material.emissive.texture(),
+            assets,
+        );
+
+        let scalars = [
+            (
+                "material.metallic",
+                "material.useMetallicMap",
+                "metallicMap",
+                &material.metallic,
+            ),
+            (
+                "material.roughness",
+                "material.useRoughnessMap",
+                "roughnessMap",
+                &material.roughness,
+            ),
+            (
+                "material.ao",
+                "material.useAoMap",
+                "aoMap",
+                &material.ambient_occlusion,
+            ),
+        ];
+        for (unit, (name, flag, sampler, input)) in (1..).zip(scalars) {
+            if let MaterialInput::Constant(value) = input {
+                self.set_float(name, *value);
+            }
+            self.bind_material_texture(flag, sampler, unit, input.texture(), assets);
+        }
+    }
+
+    fn bind_material_texture(
+        &self,
+        flag: &str,
+        sampler: &str,
+        unit: u32,
+        texture: Option<&str>,
+        assets: &AssetsManager,
+    ) {
+        self.set_int(sampler, unit as i32);
+
+        let texture = texture.and_then(|name| {
+            let texture = assets.get_texture(name);
+            if texture.is_none() {
+                warn!(target: "GEAR", "Texture '{}' not found, using constant value", name);
+            }
+            texture
+        });
+
+        match texture {
+            Some(texture) => {
+                texture.bind(unit);
+                self.set_bool(flag, true);
+            }
+            None => self.set_bool(flag, false),
         }
     }
 
@@ -62,26 +183,59 @@ impl Shader {
         }
     }
 
-    pub fn with_light(&self, light: &Light) {
+    pub fn with_environment(&self, environment: Option<&Environment>) {
         unsafe {
             gl::UseProgram(self.id);
-            self.set_vec3(
-                "light.position",
-                &light.entity.renderable.transform.position,
-            );
+        }
+
+        // samplers of different types must never share a texture unit, so the
+        // units are assigned even when image-based lighting is disabled
+        self.set_int("irradianceMap", IRRADIANCE_MAP_UNIT as i32);
+        self.set_int("prefilterMap", PREFILTER_MAP_UNIT as i32);
+        self.set_int("brdfLUT", BRDF_LUT_UNIT as i32);
+
+        match environment {
+            Some(environment) => {
+                environment.bind_lighting_maps();
+                self.set_float("maxReflectionLod", environment.max_reflection_lod());
+                self.set_bool("useIBL", true);
+            }
+            None => self.set_bool("useIBL", false),
+        }
+    }
+
+    pub fn with_light(&self, position: &Vector3<f32>, light: &Light) {
+        unsafe {
+            gl::UseProgram(self.id);
+            self.set_vec3("light.position", position);
             self.set_vec3("light.color", &light.color);
             self.set_float("light.ambient", light.ambient);
             self.set_float("light.diffuse", light.diffuse);
             self.set_float("light.specular", light.specular);
         }
     }
+
+    // Lights are given with their world position, past `MAX_LIGHTS` they
+    // are ignored.
+    pub fn with_lights(&self, lights: &[(Vector3<f32>, Light)]) {
+        unsafe {
+            gl::UseProgram(self.id);
+        }
+
+        let lights = &lights[..lights.len().min(MAX_LIGHTS)];
+        for (i, (position, light)) in lights.iter().enumerate() {
+            self.set_vec3(format!("lights[{}].position", i).as_str(), position);
+            self.set_vec3(format!("lights[{}].color", i).as_str(), &light.color);
+            self.set_float(format!("lights[{}].intensity", i).as_str(), light.intensity);
+            self.set_float(format!("lights[{}].ambient", i).as_str(), light.ambient);
+        }
+        self.set_int("lightCount", lights.len() as i32);
+    }
 }
 
 impl Drop for Shader {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.id);
-        }
+        defer_delete(GpuResource::Program(self.id));
     }
 }
 
@@ -92,9 +246,11 @@ pub enum ShaderError {
     ShaderFileNotFound(String),
 }
 
-unsafe fn compile_shader(path: &str, shader_type: gl::types::GLenum) -> Result<u32, ShaderError> {
-    let source = std::fs::read_to_string(&path)
-        .map_err(|_| ShaderError::ShaderFileNotFound(path.to_string()))?;
+fn read_source(path: &str) -> Result<String, ShaderError> {
+    std::fs::read_to_string(path).map_err(|_| ShaderError::ShaderFileNotFound(path.to_string()))
+}
+
+unsafe fn compile_shader(source: &str, shader_type: gl::types::GLenum) -> Result<u32, ShaderError> {
     let shader = gl::CreateShader(shader_type);
 
     let source = CString::new(source.as_bytes()).unwrap();
@@ -45,6 +45,17 @@ impl Shader {
         }
     }
 
+    pub fn set_uvec2(&self, name: &str, value: &na::Vector2<u32>) {
+        let name = std::ffi::CString::new(name).unwrap();
+        unsafe {
+            gl::Uniform2uiv(
+                gl::GetUniformLocation(self.id, name.as_ptr() as *const i8),
+                1,
+                value.as_ptr(),
+            );
+        }
+    }
+
     pub fn set_vec3(&self, name: &str, value: &na::Vector3<f32>) {
         let name = std::ffi::CString::new(name).unwrap();
         unsafe {
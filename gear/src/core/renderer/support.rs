@@ -0,0 +1,24 @@
+use std::ffi::CStr;
+
+use super::gl;
+
+// Whether the current context is at least `major.minor` or lists
+// `extension`. A loaded function pointer doesn't tell: GLX hands one out for
+// any name, whatever the context version.
+pub(crate) fn gl_supports(major: i32, minor: i32, extension: &str) -> bool {
+    unsafe {
+        let (mut context_major, mut context_minor) = (0, 0);
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut context_major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut context_minor);
+        if (context_major, context_minor) >= (major, minor) {
+            return true;
+        }
+
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count.max(0) as u32).any(|index| {
+            let name = gl::GetStringi(gl::EXTENSIONS, index);
+            !name.is_null() && CStr::from_ptr(name as *const _).to_bytes() == extension.as_bytes()
+        })
+    }
+}
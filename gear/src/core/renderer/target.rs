@@ -0,0 +1,77 @@
+use log::warn;
+
+use super::{
+    gl,
+    resource::{defer_delete, GpuResource},
+    texture::Texture,
+};
+
+// Offscreen color and depth buffers a camera draws to instead of the window,
+// e.g. a mirror or a minimap used as a texture. Created on the render thread.
+pub struct RenderTarget {
+    fbo: u32,
+    color: Texture,
+    depth: u32,
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32) -> Self {
+        let color = Texture::empty_rgba8(width, height);
+        let mut fbo = 0;
+        let mut depth = 0;
+
+        unsafe {
+            gl::GenRenderbuffers(1, &mut depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                width as i32,
+                height as i32,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                color.get_id(),
+                0,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                warn!(target: "GEAR", "Render target framebuffer is incomplete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Self { fbo, color, depth }
+    }
+
+    // What the camera rendered last frame.
+    pub fn texture(&self) -> &Texture {
+        &self.color
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.color.get_size()
+    }
+
+    pub(crate) fn framebuffer(&self) -> u32 {
+        self.fbo
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::Framebuffer(self.fbo));
+        defer_delete(GpuResource::Renderbuffer(self.depth));
+    }
+}
@@ -0,0 +1,159 @@
+use super::{
+    super::{
+        gl,
+        resource::{defer_delete, GpuResource},
+    },
+    TextureError,
+};
+
+pub struct Cubemap {
+    id: u32,
+    size: u32,
+}
+
+impl Cubemap {
+    // Faces are expected in OpenGL order: +X, -X, +Y, -Y, +Z, -Z
+    // (right, left, top, bottom, front, back). They are sRGB images, sampling
+    // returns linear colors like the HDR maps.
+    pub fn from_faces(faces: [&str; 6]) -> Result<Self, TextureError> {
+        let mut id = 0;
+        let mut size = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+        }
+
+        for (i, path) in faces.iter().enumerate() {
+            let image = match image::open(path) {
+                Ok(image) => image.into_rgba8(),
+                Err(err) => {
+                    unsafe { gl::DeleteTextures(1, &id) };
+                    return Err(match err {
+                        image::ImageError::IoError(_) => {
+                            TextureError::TextureFileNotFound(path.to_string())
+                        }
+                        err => TextureError::TextureDecodingFailed(err.to_string()),
+                    });
+                }
+            };
+
+            let (width, height) = image.dimensions();
+            if width != height || (size != 0 && width != size) {
+                unsafe { gl::DeleteTextures(1, &id) };
+                return Err(TextureError::TextureDecodingFailed(format!(
+                    "Cubemap face '{}' must be square and match the other faces",
+                    path
+                )));
+            }
+            size = width;
+
+            unsafe {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
+                    0,
+                    gl::SRGB8_ALPHA8 as i32,
+                    width as i32,
+                    height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    image.as_raw().as_ptr() as *const gl::types::GLvoid,
+                );
+            }
+        }
+
+        unsafe {
+            set_cubemap_parameters(gl::LINEAR_MIPMAP_LINEAR);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        Ok(Self { id, size })
+    }
+
+    // Allocates an empty floating point cubemap, used as a render target when
+    // converting equirectangular maps and generating the lighting maps.
+    pub fn empty(size: u32, mipmaps: bool) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            for i in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i,
+                    0,
+                    gl::RGB16F as i32,
+                    size as i32,
+                    size as i32,
+                    0,
+                    gl::RGB,
+                    gl::FLOAT,
+                    std::ptr::null(),
+                );
+            }
+
+            if mipmaps {
+                set_cubemap_parameters(gl::LINEAR_MIPMAP_LINEAR);
+                gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            } else {
+                set_cubemap_parameters(gl::LINEAR);
+            }
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        Self { id, size }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+        }
+    }
+}
+
+impl Drop for Cubemap {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::Texture(self.id));
+    }
+}
+
+unsafe fn set_cubemap_parameters(min_filter: gl::types::GLenum) {
+    gl::TexParameteri(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_EDGE as i32,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_EDGE as i32,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_WRAP_R,
+        gl::CLAMP_TO_EDGE as i32,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_MIN_FILTER,
+        min_filter as i32,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_CUBE_MAP,
+        gl::TEXTURE_MAG_FILTER,
+        gl::LINEAR as i32,
+    );
+}
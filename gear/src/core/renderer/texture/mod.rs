@@ -0,0 +1,199 @@
+mod cubemap;
+
+pub use cubemap::Cubemap;
+
+use super::{
+    gl,
+    resource::{defer_delete, GpuResource},
+};
+
+pub struct Texture {
+    id: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    pub fn new(path: &str) -> Result<Self, TextureError> {
+        let image = image::open(path)
+            .map_err(|err| match err {
+                image::ImageError::IoError(_) => {
+                    TextureError::TextureFileNotFound(path.to_string())
+                }
+                err => TextureError::TextureDecodingFailed(err.to_string()),
+            })?
+            .flipv()
+            .into_rgba8();
+
+        let (width, height) = image.dimensions();
+        Ok(Self::from_rgba8(width, height, image.as_raw()))
+    }
+
+    // Loads a high dynamic range image (e.g. Radiance .hdr) into a floating
+    // point texture, clamped to the edges. Used for equirectangular environments.
+    pub fn new_hdr(path: &str) -> Result<Self, TextureError> {
+        let image = image::open(path)
+            .map_err(|err| match err {
+                image::ImageError::IoError(_) => {
+                    TextureError::TextureFileNotFound(path.to_string())
+                }
+                err => TextureError::TextureDecodingFailed(err.to_string()),
+            })?
+            .flipv()
+            .into_rgb32f();
+
+        let (width, height) = image.dimensions();
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGB16F as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                image.as_raw().as_ptr() as *const gl::types::GLvoid,
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Ok(Self { id, width, height })
+    }
+
+    // Allocates an empty two channel floating point texture, used as the
+    // render target of the BRDF lookup table.
+    pub fn empty_rg16f(width: u32, height: u32) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RG16F as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RG,
+                gl::FLOAT,
+                std::ptr::null(),
+            );
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self { id, width, height }
+    }
+
+    // Allocates an empty color texture, used as the render target of cameras.
+    pub fn empty_rgba8(width: u32, height: u32) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self { id, width, height }
+    }
+
+    pub fn from_rgba8(width: u32, height: u32, pixels: &[u8]) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as i32,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr() as *const gl::types::GLvoid,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self { id, width, height }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        defer_delete(GpuResource::Texture(self.id));
+    }
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    TextureFileNotFound(String),
+    TextureDecodingFailed(String),
+}
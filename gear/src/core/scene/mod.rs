@@ -0,0 +1,643 @@
+mod prefab;
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
+
+use nalgebra::Vector3;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub use prefab::{
+    clear_overrides, reload_prefab, set_override, update_prefab, OverrideField, Prefab,
+    PrefabBuilder, PrefabInstance, PrefabOverride, Prefabs,
+};
+
+use super::{
+    application::context::Context,
+    assets::AssetsManager,
+    ecs::{EntityId, World},
+    entity::{cube, square, EntityBuilder, EntityError, Light, Material, Renderable, Transform},
+    renderer::{
+        camera::{Camera, Fov, Projection},
+        shader::ShaderError,
+        texture::TextureError,
+    },
+};
+
+// Bumped whenever the format changes in a way older loaders can't read.
+// Version 2 replaced the camera `fov`, `near` and `far` with a projection.
+pub const SCENE_VERSION: u32 = 2;
+
+// A level stored as data: the entities with their mesh, shader, material,
+// transform and light, the camera, and the assets they reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub version: u32,
+    #[serde(default)]
+    pub assets: SceneAssets,
+    #[serde(default)]
+    pub camera: Option<SceneCamera>,
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+}
+
+// Shaders and textures are loaded from their paths (relative to the assets
+// root) when they aren't registered yet. Meshes are referenced by name only:
+// they must be registered by the application, except the built-in "cube" and
+// "square" which are created on demand.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneAssets {
+    pub meshes: Vec<String>,
+    pub shaders: Vec<ShaderAsset>,
+    pub textures: Vec<TextureAsset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShaderAsset {
+    pub name: String,
+    #[serde(default)]
+    pub vertex: Option<String>,
+    #[serde(default)]
+    pub fragment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureAsset {
+    pub name: String,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneCamera {
+    pub position: Vector3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+    #[serde(default)]
+    pub projection: Projection,
+    // Version 1 perspective, `fov` in degrees, moved into `projection` when
+    // the scene is loaded
+    #[serde(default, skip_serializing)]
+    pub fov: Option<f32>,
+    #[serde(default, skip_serializing)]
+    pub near: Option<f32>,
+    #[serde(default, skip_serializing)]
+    pub far: Option<f32>,
+}
+
+impl SceneCamera {
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            position: camera.position,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            projection: camera.projection,
+            fov: None,
+            near: None,
+            far: None,
+        }
+    }
+
+    fn upgrade_v1(&mut self) {
+        if let (Some(fov), Some(near), Some(far)) =
+            (self.fov.take(), self.near.take(), self.far.take())
+        {
+            self.projection = Projection::perspective(Fov::Degrees(fov), near, far);
+        }
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.position = self.position;
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        camera.projection = self.projection;
+        camera.update();
+    }
+}
+
+impl SceneAssets {
+    // Registers the assets that aren't loaded yet.
+    pub fn load(&self, assets: &mut AssetsManager) -> Result<(), SceneError> {
+        for mesh in self.meshes.iter() {
+            if assets.mesh_handle(mesh).is_some() {
+                continue;
+            }
+            match mesh.as_str() {
+                "cube" => assets.put_object(mesh, cube()),
+                "square" => assets.put_object(mesh, square()),
+                _ => return Err(SceneError::MissingAsset(AssetKind::Mesh, mesh.clone())),
+            };
+        }
+
+        for shader in self.shaders.iter() {
+            if assets.shader_handle(&shader.name).is_some() {
+                continue;
+            }
+            match (&shader.vertex, &shader.fragment) {
+                (Some(vertex), Some(fragment)) => {
+                    assets.load_shader(&shader.name, vertex, fragment)?;
+                }
+                _ => {
+                    return Err(SceneError::MissingAsset(
+                        AssetKind::Shader,
+                        shader.name.clone(),
+                    ))
+                }
+            }
+        }
+
+        for texture in self.textures.iter() {
+            if assets.get_texture(&texture.name).is_some() {
+                continue;
+            }
+            match &texture.path {
+                Some(path) => {
+                    assets.load_texture(&texture.name, path)?;
+                }
+                None => {
+                    return Err(SceneError::MissingAsset(
+                        AssetKind::Texture,
+                        texture.name.clone(),
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneEntity {
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub mesh: Option<String>,
+    pub shader: Option<String>,
+    pub material: Material,
+    pub transform: Transform,
+    pub light: Option<Light>,
+    // When set the entity is an instance of the prefab, placed by `transform`;
+    // mesh, shader, material and light then come from the prefab. The name
+    // and tags are those of the instance root.
+    pub prefab: Option<PrefabRef>,
+    pub children: Vec<SceneEntity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabRef {
+    pub name: String,
+    // relative to the assets root, used when the prefab isn't loaded yet
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub overrides: Vec<PrefabOverride>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AssetKind {
+    Mesh,
+    Shader,
+    Texture,
+    Prefab,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    SceneFileNotFound(String),
+    UnsupportedFormat(String),
+    UnsupportedVersion(u32),
+    ParsingFailed(String),
+    SerializationFailed(String),
+    MissingAsset(AssetKind, String),
+    // A prefab with a prefab reference among its entities
+    NestedPrefab(String),
+    Shader(ShaderError),
+    Texture(TextureError),
+    Entity(EntityError),
+}
+
+impl From<ShaderError> for SceneError {
+    fn from(err: ShaderError) -> Self {
+        SceneError::Shader(err)
+    }
+}
+
+impl From<TextureError> for SceneError {
+    fn from(err: TextureError) -> Self {
+        SceneError::Texture(err)
+    }
+}
+
+impl From<EntityError> for SceneError {
+    fn from(err: EntityError) -> Self {
+        SceneError::Entity(err)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SceneFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFormat {
+    fn from_path(path: &str) -> Result<Self, SceneError> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("ron") => Ok(SceneFormat::Ron),
+            Some("json") => Ok(SceneFormat::Json),
+            _ => Err(SceneError::UnsupportedFormat(path.to_string())),
+        }
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            version: SCENE_VERSION,
+            assets: SceneAssets::default(),
+            camera: None,
+            entities: Vec::new(),
+        }
+    }
+
+    // Records every entity with a `Transform`, keeping the hierarchy. Game
+    // specific components are not part of the scene.
+    pub fn capture(ctx: &Context) -> Result<Self, SceneError> {
+        let Context {
+            assets,
+            prefabs,
+            world,
+            camera,
+            ..
+        } = ctx;
+
+        let roots: Vec<EntityId> = world
+            .query::<&Transform>()
+            .entities()
+            .into_iter()
+            .filter(|entity| world.parent(*entity).is_none())
+            .collect();
+
+        let mut scene = Scene::new();
+        for root in roots {
+            scene
+                .entities
+                .push(capture_entity(world, assets, prefabs, root)?);
+        }
+        scene.camera = Some(SceneCamera::from_camera(camera));
+        scene.assets = scene.collect_assets(assets);
+
+        Ok(scene)
+    }
+
+    // Lists the assets referenced by the entities, with their source paths
+    // when the `AssetsManager` knows them.
+    fn collect_assets(&self, assets: &AssetsManager) -> SceneAssets {
+        let (meshes, shaders, textures) = references(&self.entities);
+
+        SceneAssets {
+            meshes: meshes.into_iter().collect(),
+            shaders: shaders
+                .into_iter()
+                .map(|name| {
+                    let source = assets.shader_source(name.as_str());
+                    ShaderAsset {
+                        vertex: source.map(|(vertex, _)| vertex.to_string()),
+                        fragment: source.map(|(_, fragment)| fragment.to_string()),
+                        name,
+                    }
+                })
+                .collect(),
+            textures: textures
+                .into_iter()
+                .map(|name| TextureAsset {
+                    path: assets.texture_source(name.as_str()).map(str::to_string),
+                    name,
+                })
+                .collect(),
+        }
+    }
+
+    // The format is picked from the extension: `.ron` or `.json`.
+    pub fn save(&self, path: &str) -> Result<(), SceneError> {
+        write_file(path, self)
+    }
+
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        read_file::<Scene>(path)?.upgrade()
+    }
+
+    // Brings a scene of an older version to `SCENE_VERSION`.
+    fn upgrade(mut self) -> Result<Self, SceneError> {
+        if self.version > SCENE_VERSION {
+            return Err(SceneError::UnsupportedVersion(self.version));
+        }
+        if self.version < 2 {
+            if let Some(camera) = &mut self.camera {
+                camera.upgrade_v1();
+            }
+        }
+        self.version = SCENE_VERSION;
+        Ok(self)
+    }
+
+    // Loads the missing assets, then spawns the entities through
+    // `EntityBuilder` and applies the camera. Every reference is checked
+    // before anything is spawned, so a failing scene leaves the world as is.
+    // Returns the root entities.
+    pub fn instantiate(&self, ctx: &mut Context) -> Result<Vec<EntityId>, SceneError> {
+        self.assets.load(&mut ctx.assets)?;
+        check_references(&self.entities, &ctx.assets)?;
+        check_names(&self.entities, &ctx.world)?;
+
+        let mut stack: Vec<&SceneEntity> = self.entities.iter().collect();
+        while let Some(entity) = stack.pop() {
+            if let Some(prefab) = &entity.prefab {
+                prefab::prepare_ref(prefab, &mut ctx.prefabs, &mut ctx.assets)?;
+            }
+            stack.extend(entity.children.iter());
+        }
+
+        let mut roots = Vec::new();
+        for entity in self.entities.iter() {
+            roots.push(spawn(ctx, entity, None)?);
+        }
+
+        if let Some(camera) = &self.camera {
+            camera.apply(&mut ctx.camera);
+        }
+        Ok(roots)
+    }
+}
+
+fn spawn(
+    ctx: &mut Context,
+    entity: &SceneEntity,
+    parent: Option<EntityId>,
+) -> Result<EntityId, SceneError> {
+    let id = match &entity.prefab {
+        Some(prefab) => {
+            let mut builder = PrefabBuilder::new(&prefab.name).with_transform(entity.transform);
+            if let Some(name) = &entity.name {
+                builder = builder.with_name(name);
+            }
+            for tag in entity.tags.iter() {
+                builder = builder.with_tag(tag);
+            }
+            for prefab_override in prefab.overrides.iter() {
+                builder = builder.with_override(prefab_override.clone());
+            }
+            if let Some(parent) = parent {
+                builder = builder.with_parent(parent);
+            }
+            builder.build(ctx)?
+        }
+        None => {
+            let mut builder = entity_builder(entity);
+            if let Some(parent) = parent {
+                builder = builder.with_parent(parent);
+            }
+            builder.build(&ctx.assets, &mut ctx.world)?
+        }
+    };
+
+    for child in entity.children.iter() {
+        spawn(ctx, child, Some(id))?;
+    }
+    Ok(id)
+}
+
+// Builder for a single scene entity, its children are not included.
+fn entity_builder(entity: &SceneEntity) -> EntityBuilder {
+    let mut builder = EntityBuilder::new()
+        .with_transform(entity.transform)
+        .with_material(entity.material.clone());
+
+    if let Some(mesh) = &entity.mesh {
+        builder = builder.with_mesh(mesh);
+    }
+    if let Some(shader) = &entity.shader {
+        builder = builder.with_shader(shader);
+    }
+    if let Some(light) = &entity.light {
+        builder = builder.with_component(light.clone());
+    }
+    if let Some(name) = &entity.name {
+        builder = builder.with_name(name);
+    }
+    for tag in entity.tags.iter() {
+        builder = builder.with_tag(tag);
+    }
+    builder
+}
+
+fn references(entities: &[SceneEntity]) -> (BTreeSet<String>, BTreeSet<String>, BTreeSet<String>) {
+    let mut meshes = BTreeSet::new();
+    let mut shaders = BTreeSet::new();
+    let mut textures = BTreeSet::new();
+
+    let mut stack: Vec<&SceneEntity> = entities.iter().collect();
+    while let Some(entity) = stack.pop() {
+        meshes.extend(entity.mesh.clone());
+        shaders.extend(entity.shader.clone());
+
+        let material = &entity.material;
+        textures.extend(
+            [
+                material.base_color.texture(),
+                material.metallic.texture(),
+                material.roughness.texture(),
+                material.ambient_occlusion.texture(),
+                material.emissive.texture(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(str::to_string),
+        );
+
+        stack.extend(entity.children.iter());
+    }
+
+    (meshes, shaders, textures)
+}
+
+// Entities may reference assets that aren't listed in the file, as long as
+// the application registered them.
+fn check_references(entities: &[SceneEntity], assets: &AssetsManager) -> Result<(), SceneError> {
+    let (meshes, shaders, textures) = references(entities);
+
+    if let Some(mesh) = meshes.into_iter().find(|m| assets.mesh_handle(m).is_none()) {
+        return Err(SceneError::MissingAsset(AssetKind::Mesh, mesh));
+    }
+    if let Some(shader) = shaders
+        .into_iter()
+        .find(|s| assets.shader_handle(s).is_none())
+    {
+        return Err(SceneError::MissingAsset(AssetKind::Shader, shader));
+    }
+    if let Some(texture) = textures
+        .into_iter()
+        .find(|t| assets.get_texture(t).is_none())
+    {
+        return Err(SceneError::MissingAsset(AssetKind::Texture, texture));
+    }
+
+    Ok(())
+}
+
+// Names must be unique in the world, so instantiating the same scene twice
+// fails here rather than halfway through spawning.
+fn check_names(entities: &[SceneEntity], world: &World) -> Result<(), SceneError> {
+    let mut names = HashSet::new();
+    let mut stack: Vec<&SceneEntity> = entities.iter().collect();
+    while let Some(entity) = stack.pop() {
+        if let Some(name) = &entity.name {
+            if !names.insert(name.as_str()) || world.find_by_name(name).is_some() {
+                return Err(EntityError::NameTaken(name.clone()).into());
+            }
+        }
+        stack.extend(entity.children.iter());
+    }
+    Ok(())
+}
+
+pub(crate) fn write_file<T: Serialize>(path: &str, value: &T) -> Result<(), SceneError> {
+    let data = match SceneFormat::from_path(path)? {
+        SceneFormat::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+            .map_err(|err| SceneError::SerializationFailed(err.to_string()))?,
+        SceneFormat::Json => serde_json::to_string_pretty(value)
+            .map_err(|err| SceneError::SerializationFailed(err.to_string()))?,
+    };
+
+    std::fs::write(path, data)
+        .map_err(|err| SceneError::SerializationFailed(format!("{}: {}", path, err)))
+}
+
+pub(crate) fn read_file<T: DeserializeOwned>(path: &str) -> Result<T, SceneError> {
+    let format = SceneFormat::from_path(path)?;
+    let data = std::fs::read_to_string(path)
+        .map_err(|_| SceneError::SceneFileNotFound(path.to_string()))?;
+
+    match format {
+        SceneFormat::Ron => ron::from_str(&data)
+            .map_err(|err| SceneError::ParsingFailed(format!("{}: {}", path, err))),
+        SceneFormat::Json => serde_json::from_str(&data)
+            .map_err(|err| SceneError::ParsingFailed(format!("{}: {}", path, err))),
+    }
+}
+
+// Captures `entity` and, depth first, its children that have a `Transform`.
+// Prefab instances are recorded as a reference to the prefab, with their
+// overrides, instead of the entities they expanded to.
+fn capture_entity(
+    world: &World,
+    assets: &AssetsManager,
+    prefabs: &Prefabs,
+    entity: EntityId,
+) -> Result<SceneEntity, SceneError> {
+    if let Some(instance) = world.get::<PrefabInstance>(entity) {
+        let mut scene_entity = SceneEntity {
+            name: world.name(entity).map(str::to_string),
+            tags: world.tags(entity).map(str::to_string).collect(),
+            transform: world
+                .get::<Transform>(entity)
+                .map(|transform| *transform)
+                .unwrap_or_default(),
+            prefab: Some(PrefabRef {
+                name: instance.prefab.clone(),
+                path: prefabs
+                    .get(&instance.prefab)
+                    .and_then(|prefab| prefab.source().map(str::to_string)),
+                overrides: instance.overrides.clone(),
+            }),
+            ..SceneEntity::default()
+        };
+
+        for child in world.children(entity) {
+            if world.has::<Transform>(child) && !instance.contains(child) {
+                scene_entity
+                    .children
+                    .push(capture_entity(world, assets, prefabs, child)?);
+            }
+        }
+        return Ok(scene_entity);
+    }
+
+    let mut scene_entity = SceneEntity {
+        name: world.name(entity).map(str::to_string),
+        tags: world.tags(entity).map(str::to_string).collect(),
+        transform: world
+            .get::<Transform>(entity)
+            .map(|transform| *transform)
+            .unwrap_or_default(),
+        light: world.get::<Light>(entity).map(|light| light.clone()),
+        ..SceneEntity::default()
+    };
+
+    if let Some(renderable) = world.get::<Renderable>(entity) {
+        let mesh = assets
+            .mesh_name(renderable.mesh)
+            .ok_or(EntityError::MeshNotFound)?;
+        let shader = assets
+            .shader_name(renderable.shader)
+            .ok_or(EntityError::ShaderNotFound)?;
+
+        scene_entity.mesh = Some(mesh.to_string());
+        scene_entity.shader = Some(shader.to_string());
+        scene_entity.material = renderable.material.clone();
+    }
+
+    for child in world.children(entity) {
+        if world.has::<Transform>(child) {
+            scene_entity
+                .children
+                .push(capture_entity(world, assets, prefabs, child)?);
+        }
+    }
+    Ok(scene_entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_camera_becomes_a_perspective() {
+        let data = r#"{
+            "version": 1,
+            "camera": {
+                "position": [0.0, 1.0, 3.0],
+                "yaw": -90.0,
+                "pitch": 0.0,
+                "fov": 60.0,
+                "near": 0.5,
+                "far": 50.0
+            }
+        }"#;
+        let scene = serde_json::from_str::<Scene>(data)
+            .unwrap()
+            .upgrade()
+            .unwrap();
+        assert_eq!(scene.version, SCENE_VERSION);
+
+        let camera = scene.camera.unwrap();
+        assert_eq!(
+            camera.projection,
+            Projection::perspective(Fov::Degrees(60.0), 0.5, 50.0)
+        );
+        assert_eq!((camera.fov, camera.near, camera.far), (None, None, None));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let data = format!(r#"{{ "version": {} }}"#, SCENE_VERSION + 1);
+        let scene = serde_json::from_str::<Scene>(&data).unwrap();
+        assert!(matches!(
+            scene.upgrade(),
+            Err(SceneError::UnsupportedVersion(_))
+        ));
+    }
+}
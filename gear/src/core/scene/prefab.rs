@@ -0,0 +1,740 @@
+use std::{collections::HashMap, mem};
+
+use log::warn;
+use nalgebra::{UnitQuaternion, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    check_references, entity_builder, read_file, write_file, AssetKind, PrefabRef, SceneAssets,
+    SceneEntity, SceneError, SCENE_VERSION,
+};
+use crate::core::{
+    application::context::Context,
+    assets::AssetsManager,
+    ecs::{EntityId, World},
+    entity::{EntityBuilder, EntityError, Light, Material, MaterialInput, Renderable, Transform},
+};
+
+// Prefabs by name. They are scene data rather than GPU resources, so they
+// live next to the `AssetsManager` on the `Context` instead of in it.
+pub struct Prefabs {
+    assets_root: &'static str,
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    pub fn new(assets_root: &'static str) -> Self {
+        Self {
+            assets_root,
+            prefabs: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, name: &str, path: &str) -> Result<bool, SceneError> {
+        let source = format!("{}/{}", self.assets_root, path);
+
+        if self.prefabs.contains_key(name) {
+            warn!(target: "GEAR", "Prefab with name '{}' already exists", name);
+            return Ok(false);
+        }
+
+        let mut prefab = Prefab::load(source.as_str())?;
+        prefab.set_source(Some(path.to_string()));
+        self.prefabs.insert(name.to_string(), prefab);
+        Ok(true)
+    }
+
+    // Registering a prefab doesn't touch existing instances, see
+    // `update_prefab`.
+    pub fn put(&mut self, name: &str, prefab: Prefab) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+}
+
+// A reusable template of one or more entities, stored in the same format as
+// the scene entities. Instances are spawned under a root entity that carries
+// the instance transform and a `PrefabInstance`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prefab {
+    pub version: u32,
+    #[serde(default)]
+    pub assets: SceneAssets,
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+
+    // path, relative to the assets root, of a prefab loaded from a file
+    #[serde(skip)]
+    source: Option<String>,
+}
+
+impl Prefab {
+    pub fn new(entities: Vec<SceneEntity>) -> Self {
+        Self {
+            version: SCENE_VERSION,
+            assets: SceneAssets::default(),
+            entities,
+            source: None,
+        }
+    }
+
+    pub fn with_assets(mut self, assets: SceneAssets) -> Self {
+        self.assets = assets;
+        self
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SceneError> {
+        write_file(path, self)
+    }
+
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        let prefab: Prefab = read_file(path)?;
+        if prefab.version > SCENE_VERSION {
+            return Err(SceneError::UnsupportedVersion(prefab.version));
+        }
+        Ok(prefab)
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub(crate) fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    // The entity tree with the overrides applied. Overrides pointing to an
+    // entity the prefab doesn't have are skipped.
+    pub fn resolve(&self, overrides: &[PrefabOverride]) -> Vec<SceneEntity> {
+        let mut entities = self.entities.clone();
+        for prefab_override in overrides.iter() {
+            match entity_at(&mut entities, &prefab_override.path) {
+                Some(entity) => prefab_override.field.apply(entity),
+                None => warn!(
+                    target: "GEAR",
+                    "Prefab override path {:?} doesn't exist",
+                    prefab_override.path
+                ),
+            }
+        }
+        entities
+    }
+}
+
+fn entity_at<'a>(entities: &'a mut [SceneEntity], path: &[usize]) -> Option<&'a mut SceneEntity> {
+    let (first, rest) = path.split_first()?;
+    let mut entity = entities.get_mut(*first)?;
+    for index in rest {
+        entity = entity.children.get_mut(*index)?;
+    }
+    Some(entity)
+}
+
+// A field of a prefab entity replaced on a single instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OverrideField {
+    Position(Vector3<f32>),
+    Rotation(UnitQuaternion<f32>),
+    Scale(Vector3<f32>),
+    Material(Material),
+    BaseColor([f32; 4]),
+    Metallic(f32),
+    Roughness(f32),
+    LightColor(Vector3<f32>),
+    LightIntensity(f32),
+}
+
+impl OverrideField {
+    pub fn apply(&self, entity: &mut SceneEntity) {
+        match self {
+            OverrideField::Position(position) => entity.transform.position = *position,
+            OverrideField::Rotation(rotation) => entity.transform.rotation = *rotation,
+            OverrideField::Scale(scale) => entity.transform.scale = *scale,
+            OverrideField::Material(material) => entity.material = material.clone(),
+            OverrideField::BaseColor(_)
+            | OverrideField::Metallic(_)
+            | OverrideField::Roughness(_) => self.apply_material(&mut entity.material),
+            OverrideField::LightColor(color) => {
+                if let Some(light) = &mut entity.light {
+                    light.color = *color;
+                }
+            }
+            OverrideField::LightIntensity(intensity) => {
+                if let Some(light) = &mut entity.light {
+                    light.intensity = *intensity;
+                }
+            }
+        }
+    }
+
+    fn apply_material(&self, material: &mut Material) {
+        match self {
+            OverrideField::BaseColor(color) => {
+                material.base_color = MaterialInput::Constant(Vector4::from(*color))
+            }
+            OverrideField::Metallic(metallic) => {
+                material.metallic = MaterialInput::Constant(*metallic)
+            }
+            OverrideField::Roughness(roughness) => {
+                material.roughness = MaterialInput::Constant(*roughness)
+            }
+            _ => {}
+        }
+    }
+
+    // Whether `self` replaces an `other` override. A whole material replaces
+    // its base color, metallic and roughness too, not the other way around.
+    fn replaces(&self, other: &OverrideField) -> bool {
+        match self {
+            OverrideField::Material(_) => other.is_material(),
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+
+    fn is_material(&self) -> bool {
+        matches!(
+            self,
+            OverrideField::Material(_)
+                | OverrideField::BaseColor(_)
+                | OverrideField::Metallic(_)
+                | OverrideField::Roughness(_)
+        )
+    }
+}
+
+// `path` is relative to the instance root: `[i]` is the i-th entity of the
+// prefab, `[i, j]` the j-th child of that entity, and so on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabOverride {
+    pub path: Vec<usize>,
+    pub field: OverrideField,
+}
+
+impl PrefabOverride {
+    pub fn new(path: &[usize], field: OverrideField) -> Self {
+        Self {
+            path: path.to_vec(),
+            field,
+        }
+    }
+}
+
+// Component on the root of every prefab instance.
+#[derive(Debug, Clone)]
+pub struct PrefabInstance {
+    pub prefab: String,
+    pub overrides: Vec<PrefabOverride>,
+    entities: Vec<(Vec<usize>, EntityId)>,
+}
+
+impl PrefabInstance {
+    // The entity spawned for the prefab entity at `path`.
+    pub fn entity(&self, path: &[usize]) -> Option<EntityId> {
+        self.entities
+            .iter()
+            .find(|(p, _)| p.as_slice() == path)
+            .map(|(_, entity)| *entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.iter().map(|(_, entity)| *entity)
+    }
+
+    // Whether `entity` was spawned from the prefab, as opposed to added under
+    // the instance afterwards.
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.entities.iter().any(|(_, e)| *e == entity)
+    }
+}
+
+pub struct PrefabBuilder {
+    prefab: String,
+    transform: Transform,
+    parent: Option<EntityId>,
+    name: Option<String>,
+    tags: Vec<String>,
+    overrides: Vec<PrefabOverride>,
+}
+
+impl PrefabBuilder {
+    pub fn new(prefab: &str) -> Self {
+        Self {
+            prefab: prefab.to_string(),
+            transform: Transform::new(),
+            parent: None,
+            name: None,
+            tags: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_position(mut self, position: Vector3<f32>) -> Self {
+        self.transform.position = position;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
+        self.transform.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.transform.scale = scale;
+        self
+    }
+
+    pub fn with_parent(mut self, parent: EntityId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    // Name of the instance root. Names of the prefab entities are ignored,
+    // they would clash between instances.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn with_override(mut self, prefab_override: PrefabOverride) -> Self {
+        set_field(&mut self.overrides, prefab_override);
+        self
+    }
+
+    // Spawns the instance and returns its root. The prefab must be registered
+    // in `Context::prefabs`.
+    pub fn build(self, ctx: &mut Context) -> Result<EntityId, SceneError> {
+        let prefab = ctx
+            .prefabs
+            .get(&self.prefab)
+            .ok_or_else(|| SceneError::MissingAsset(AssetKind::Prefab, self.prefab.clone()))?;
+        let entities = prepare(&self.prefab, prefab, &self.overrides, &mut ctx.assets)?;
+
+        let mut builder = EntityBuilder::new().with_transform(self.transform);
+        if let Some(parent) = self.parent {
+            builder = builder.with_parent(parent);
+        }
+        if let Some(name) = &self.name {
+            builder = builder.with_name(name);
+        }
+        for tag in self.tags.iter() {
+            builder = builder.with_tag(tag);
+        }
+        let root = builder.build(&ctx.assets, &mut ctx.world)?;
+
+        let mut instance = PrefabInstance {
+            prefab: self.prefab,
+            overrides: self.overrides,
+            entities: Vec::new(),
+        };
+        sync(ctx, root, &entities, &mut instance)?;
+        ctx.world.insert(root, instance)?;
+        Ok(root)
+    }
+}
+
+// Overrides a field of one instance, replacing a previous override of the
+// same field.
+pub fn set_override(
+    ctx: &mut Context,
+    instance: EntityId,
+    prefab_override: PrefabOverride,
+) -> Result<(), SceneError> {
+    let mut prefab_instance = instance_of(&ctx.world, instance)?;
+    set_field(&mut prefab_instance.overrides, prefab_override);
+    refresh(ctx, instance, prefab_instance)
+}
+
+// Drops the overrides of the entity at `path`, it follows the prefab again.
+pub fn clear_overrides(
+    ctx: &mut Context,
+    instance: EntityId,
+    path: &[usize],
+) -> Result<(), SceneError> {
+    let mut prefab_instance = instance_of(&ctx.world, instance)?;
+    prefab_instance
+        .overrides
+        .retain(|prefab_override| prefab_override.path != path);
+    refresh(ctx, instance, prefab_instance)
+}
+
+// Replaces the prefab and updates all its instances. Fields an instance
+// overrides keep their value, entities added to or removed from the prefab
+// are spawned or despawned.
+pub fn update_prefab(ctx: &mut Context, name: &str, prefab: Prefab) -> Result<(), SceneError> {
+    check_nested(name, &prefab.entities)?;
+    ctx.prefabs.put(name, prefab);
+
+    let mut instances = Vec::new();
+    ctx.world
+        .query::<&PrefabInstance>()
+        .for_each(|entity, instance| {
+            if instance.prefab == name {
+                instances.push((entity, instance.clone()));
+            }
+        });
+
+    for (entity, instance) in instances {
+        refresh(ctx, entity, instance)?;
+    }
+    Ok(())
+}
+
+// Reads a prefab loaded with `Prefabs::load` again and updates its
+// instances.
+pub fn reload_prefab(ctx: &mut Context, name: &str) -> Result<(), SceneError> {
+    let source = ctx
+        .prefabs
+        .get(name)
+        .and_then(|prefab| prefab.source().map(str::to_string))
+        .ok_or_else(|| SceneError::MissingAsset(AssetKind::Prefab, name.to_string()))?;
+
+    let mut prefab = Prefab::load(&format!("{}/{}", ctx.prefabs.assets_root, source))?;
+    prefab.set_source(Some(source));
+    update_prefab(ctx, name, prefab)
+}
+
+// Makes sure a prefab referenced by a scene can be instantiated.
+pub(super) fn prepare_ref(
+    prefab_ref: &PrefabRef,
+    prefabs: &mut Prefabs,
+    assets: &mut AssetsManager,
+) -> Result<(), SceneError> {
+    if prefabs.get(&prefab_ref.name).is_none() {
+        match &prefab_ref.path {
+            Some(path) => {
+                prefabs.load(&prefab_ref.name, path)?;
+            }
+            None => {
+                return Err(SceneError::MissingAsset(
+                    AssetKind::Prefab,
+                    prefab_ref.name.clone(),
+                ))
+            }
+        }
+    }
+
+    let prefab = prefabs
+        .get(&prefab_ref.name)
+        .ok_or_else(|| SceneError::MissingAsset(AssetKind::Prefab, prefab_ref.name.clone()))?;
+    prepare(&prefab_ref.name, prefab, &prefab_ref.overrides, assets)?;
+    Ok(())
+}
+
+// A base color, metallic or roughness set over a whole material override is
+// folded into it, so the rest of the material stays overridden.
+fn set_field(overrides: &mut Vec<PrefabOverride>, prefab_override: PrefabOverride) {
+    let folds = prefab_override.field.is_material()
+        && !matches!(prefab_override.field, OverrideField::Material(_));
+    if folds {
+        for o in overrides.iter_mut() {
+            if let OverrideField::Material(material) = &mut o.field {
+                if o.path == prefab_override.path {
+                    prefab_override.field.apply_material(material);
+                    return;
+                }
+            }
+        }
+    }
+
+    overrides
+        .retain(|o| !(o.path == prefab_override.path && prefab_override.field.replaces(&o.field)));
+    overrides.push(prefab_override);
+}
+
+fn instance_of(world: &World, entity: EntityId) -> Result<PrefabInstance, SceneError> {
+    world
+        .get::<PrefabInstance>(entity)
+        .map(|instance| instance.clone())
+        .ok_or(SceneError::Entity(EntityError::EntityNotFound))
+}
+
+// Loads the prefab assets and checks every reference before anything is
+// spawned.
+fn prepare(
+    name: &str,
+    prefab: &Prefab,
+    overrides: &[PrefabOverride],
+    assets: &mut AssetsManager,
+) -> Result<Vec<SceneEntity>, SceneError> {
+    check_nested(name, &prefab.entities)?;
+    prefab.assets.load(assets)?;
+    let entities = prefab.resolve(overrides);
+    check_references(&entities, assets)?;
+    Ok(entities)
+}
+
+// Instances are matched to the prefab entities by path, which a prefab inside
+// a prefab would break, so they aren't supported.
+fn check_nested(name: &str, entities: &[SceneEntity]) -> Result<(), SceneError> {
+    let mut stack: Vec<&SceneEntity> = entities.iter().collect();
+    while let Some(entity) = stack.pop() {
+        if entity.prefab.is_some() {
+            return Err(SceneError::NestedPrefab(name.to_string()));
+        }
+        stack.extend(entity.children.iter());
+    }
+    Ok(())
+}
+
+fn refresh(
+    ctx: &mut Context,
+    root: EntityId,
+    mut instance: PrefabInstance,
+) -> Result<(), SceneError> {
+    let prefab = ctx
+        .prefabs
+        .get(&instance.prefab)
+        .ok_or_else(|| SceneError::MissingAsset(AssetKind::Prefab, instance.prefab.clone()))?;
+    let entities = prepare(
+        &instance.prefab,
+        prefab,
+        &instance.overrides,
+        &mut ctx.assets,
+    )?;
+
+    sync(ctx, root, &entities, &mut instance)?;
+    ctx.world.insert(root, instance)?;
+    Ok(())
+}
+
+// Brings the entities of an instance in line with `entities`: existing ones
+// are updated in place, so their ids stay valid, missing ones are spawned and
+// the ones the prefab no longer has are despawned with their children.
+fn sync(
+    ctx: &mut Context,
+    root: EntityId,
+    entities: &[SceneEntity],
+    instance: &mut PrefabInstance,
+) -> Result<(), SceneError> {
+    let mut previous: HashMap<Vec<usize>, EntityId> = instance.entities.drain(..).collect();
+
+    let mut stack: Vec<(Vec<usize>, &SceneEntity, EntityId)> = entities
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, entity)| (vec![i], entity, root))
+        .collect();
+
+    while let Some((path, entity, parent)) = stack.pop() {
+        let id = match previous.remove(&path).filter(|id| ctx.world.is_alive(*id)) {
+            Some(id) => {
+                update_entity(ctx, id, entity)?;
+                id
+            }
+            None => {
+                let mut builder = entity_builder(entity).with_parent(parent);
+                builder.name = None;
+                builder.build(&ctx.assets, &mut ctx.world)?
+            }
+        };
+
+        for (i, child) in entity.children.iter().enumerate().rev() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            stack.push((child_path, child, id));
+        }
+        instance.entities.push((path, id));
+    }
+
+    for (_, id) in previous {
+        if ctx.world.is_alive(id) {
+            ctx.world.despawn_recursive(id)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_entity(ctx: &mut Context, id: EntityId, entity: &SceneEntity) -> Result<(), SceneError> {
+    ctx.world.insert(id, entity.transform)?;
+
+    match (&entity.mesh, &entity.shader) {
+        (Some(mesh), Some(shader)) => {
+            let renderable = Renderable {
+                mesh: ctx
+                    .assets
+                    .mesh_handle(mesh)
+                    .ok_or(EntityError::MeshNotFound)?,
+                material: entity.material.clone(),
+                shader: ctx
+                    .assets
+                    .shader_handle(shader)
+                    .ok_or(EntityError::ShaderNotFound)?,
+            };
+            ctx.world.insert(id, renderable)?;
+        }
+        (Some(_), None) => return Err(EntityError::ShaderNotFound.into()),
+        _ => {
+            ctx.world.remove::<Renderable>(id);
+        }
+    }
+
+    match &entity.light {
+        Some(light) => ctx.world.insert(id, light.clone())?,
+        None => {
+            ctx.world.remove::<Light>(id);
+        }
+    }
+
+    // tags added by the game are left alone
+    for tag in entity.tags.iter() {
+        ctx.world.add_tag(id, tag)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(position: Vector3<f32>, children: Vec<SceneEntity>) -> SceneEntity {
+        SceneEntity {
+            transform: Transform::new().with_position(position),
+            children,
+            ..SceneEntity::default()
+        }
+    }
+
+    fn constant<T: Copy>(input: &MaterialInput<T>) -> T {
+        match input {
+            MaterialInput::Constant(value) => *value,
+            MaterialInput::Texture(texture) => panic!("texture {}", texture),
+        }
+    }
+
+    fn material_of(overrides: &[PrefabOverride]) -> &Material {
+        match &overrides[0].field {
+            OverrideField::Material(material) => material,
+            field => panic!("expected a material, got {:?}", field),
+        }
+    }
+
+    #[test]
+    fn resolve_applies_overrides_by_path() {
+        let prefab = Prefab::new(vec![entity(
+            Vector3::zeros(),
+            vec![entity(Vector3::x(), vec![])],
+        )]);
+        let overrides = [
+            PrefabOverride::new(&[0, 0], OverrideField::Position(Vector3::y())),
+            PrefabOverride::new(&[0], OverrideField::Metallic(0.5)),
+            PrefabOverride::new(&[0, 3], OverrideField::Position(Vector3::z())),
+        ];
+
+        let entities = prefab.resolve(&overrides);
+        assert_eq!(entities[0].transform.position, Vector3::zeros());
+        assert_eq!(constant(&entities[0].material.metallic), 0.5);
+        assert_eq!(entities[0].children[0].transform.position, Vector3::y());
+        assert_eq!(entities[0].children.len(), 1);
+
+        // the prefab itself is untouched
+        assert_eq!(
+            prefab.entities[0].children[0].transform.position,
+            Vector3::x()
+        );
+    }
+
+    #[test]
+    fn same_field_replaces_the_override() {
+        let mut overrides = Vec::new();
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Scale(Vector3::x())),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[1], OverrideField::Scale(Vector3::y())),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Scale(Vector3::z())),
+        );
+
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].path, vec![1]);
+        assert!(matches!(overrides[1].field, OverrideField::Scale(scale) if scale == Vector3::z()));
+    }
+
+    #[test]
+    fn field_is_folded_into_a_material_override() {
+        let material = Material::new([1.0, 0.0, 0.0, 1.0]).with_metallic(0.25);
+        let mut overrides = Vec::new();
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Material(material)),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Roughness(0.75)),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Roughness(0.5)),
+        );
+
+        assert_eq!(overrides.len(), 1);
+        let material = material_of(&overrides);
+        assert_eq!(
+            constant(&material.base_color),
+            Vector4::new(1.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(constant(&material.metallic), 0.25);
+        assert_eq!(constant(&material.roughness), 0.5);
+    }
+
+    #[test]
+    fn material_override_replaces_the_fields() {
+        let mut overrides = Vec::new();
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Metallic(1.0)),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Position(Vector3::x())),
+        );
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Material(Material::default())),
+        );
+
+        assert_eq!(overrides.len(), 2);
+        assert!(matches!(overrides[0].field, OverrideField::Position(_)));
+        assert!(matches!(overrides[1].field, OverrideField::Material(_)));
+    }
+
+    #[test]
+    fn updated_prefab_keeps_overridden_fields() {
+        let mut overrides = Vec::new();
+        set_field(
+            &mut overrides,
+            PrefabOverride::new(&[0], OverrideField::Scale(Vector3::x())),
+        );
+
+        // `update_prefab` resolves the new prefab with the instance overrides
+        let mut updated = entity(Vector3::y(), vec![]);
+        updated.transform.scale = Vector3::z();
+        let entities = Prefab::new(vec![updated, entity(Vector3::z(), vec![])]).resolve(&overrides);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].transform.position, Vector3::y());
+        assert_eq!(entities[0].transform.scale, Vector3::x());
+        assert_eq!(entities[1].transform.position, Vector3::z());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::core::event::{Event, EventDispatcher, GamepadAxis, GamepadButton, GamepadEvent};
+
+// Joystick slots of GLFW, a gamepad's index is its slot.
+const SLOTS: [glfw::JoystickId; 16] = [
+    glfw::JoystickId::Joystick1,
+    glfw::JoystickId::Joystick2,
+    glfw::JoystickId::Joystick3,
+    glfw::JoystickId::Joystick4,
+    glfw::JoystickId::Joystick5,
+    glfw::JoystickId::Joystick6,
+    glfw::JoystickId::Joystick7,
+    glfw::JoystickId::Joystick8,
+    glfw::JoystickId::Joystick9,
+    glfw::JoystickId::Joystick10,
+    glfw::JoystickId::Joystick11,
+    glfw::JoystickId::Joystick12,
+    glfw::JoystickId::Joystick13,
+    glfw::JoystickId::Joystick14,
+    glfw::JoystickId::Joystick15,
+    glfw::JoystickId::Joystick16,
+];
+
+// Smaller axis changes are noise and not reported.
+const AXIS_EPSILON: f32 = 1e-3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Snapshot {
+    buttons: [bool; 15],
+    axes: [f32; 6],
+}
+
+impl Snapshot {
+    fn read(state: &glfw::GamepadState) -> Self {
+        let mut snapshot = Snapshot {
+            buttons: [false; 15],
+            axes: [0.0; 6],
+        };
+        for (index, button) in GamepadButton::ALL.iter().enumerate() {
+            snapshot.buttons[index] =
+                state.get_button_state(gamepad_button_to_glfw(*button)) == glfw::Action::Press;
+        }
+        for (index, axis) in GamepadAxis::ALL.iter().enumerate() {
+            let value = state.get_axis(gamepad_axis_to_glfw(*axis));
+            // GLFW triggers go from -1 at rest to 1, moved to [0, 1]
+            snapshot.axes[index] = match axis {
+                GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => (value + 1.0) * 0.5,
+                _ => value,
+            };
+        }
+        snapshot
+    }
+
+    // At rest, what a newly connected gamepad is compared against.
+    fn released() -> Self {
+        Snapshot {
+            buttons: [false; 15],
+            axes: [0.0; 6],
+        }
+    }
+}
+
+// Joysticks with a standard gamepad mapping, polled once per frame. GLFW has
+// no joystick events of its own on every platform, connections are found by
+// comparing with the previous frame.
+pub(super) struct Gamepads {
+    slots: [Option<Snapshot>; 16],
+}
+
+impl Gamepads {
+    pub(super) fn new() -> Self {
+        Self { slots: [None; 16] }
+    }
+
+    pub(super) fn is_connected(&self, index: usize) -> bool {
+        self.slots.get(index).is_some_and(|slot| slot.is_some())
+    }
+
+    pub(super) fn poll(&mut self, glfw: &glfw::Glfw, dispatcher: &mut dyn EventDispatcher) {
+        for (index, id) in SLOTS.iter().enumerate() {
+            let joystick = glfw.get_joystick(*id);
+            let state = joystick
+                .is_gamepad()
+                .then(|| joystick.get_gamepad_state())
+                .flatten();
+
+            self.update(index, state.as_ref().map(Snapshot::read), dispatcher);
+        }
+    }
+
+    fn update(
+        &mut self,
+        index: usize,
+        current: Option<Snapshot>,
+        dispatcher: &mut dyn EventDispatcher,
+    ) {
+        let previous = self.slots[index];
+        self.slots[index] = current;
+
+        let (previous, current) = match (previous, current) {
+            (None, None) => return,
+            (Some(previous), None) => {
+                // releases first, so nothing stays held
+                diff(index, &previous, &Snapshot::released(), dispatcher);
+                dispatcher.dispatch(Event::Gamepad(GamepadEvent::Disconnected(index)));
+                return;
+            }
+            (None, Some(current)) => {
+                dispatcher.dispatch(Event::Gamepad(GamepadEvent::Connected(index)));
+                (Snapshot::released(), current)
+            }
+            (Some(previous), Some(current)) => (previous, current),
+        };
+        diff(index, &previous, &current, dispatcher);
+    }
+}
+
+fn diff(
+    index: usize,
+    previous: &Snapshot,
+    current: &Snapshot,
+    dispatcher: &mut dyn EventDispatcher,
+) {
+    for (i, button) in GamepadButton::ALL.iter().enumerate() {
+        match (previous.buttons[i], current.buttons[i]) {
+            (false, true) => {
+                dispatcher.dispatch(Event::Gamepad(GamepadEvent::Press(index, *button)))
+            }
+            (true, false) => {
+                dispatcher.dispatch(Event::Gamepad(GamepadEvent::Release(index, *button)))
+            }
+            _ => {}
+        }
+    }
+    for (i, axis) in GamepadAxis::ALL.iter().enumerate() {
+        if (current.axes[i] - previous.axes[i]).abs() > AXIS_EPSILON {
+            dispatcher.dispatch(Event::Gamepad(GamepadEvent::Axis(
+                index,
+                *axis,
+                current.axes[i],
+            )));
+        }
+    }
+}
+
+fn gamepad_button_to_glfw(button: GamepadButton) -> glfw::GamepadButton {
+    match button {
+        GamepadButton::A => glfw::GamepadButton::ButtonA,
+        GamepadButton::B => glfw::GamepadButton::ButtonB,
+        GamepadButton::X => glfw::GamepadButton::ButtonX,
+        GamepadButton::Y => glfw::GamepadButton::ButtonY,
+        GamepadButton::LeftBumper => glfw::GamepadButton::ButtonLeftBumper,
+        GamepadButton::RightBumper => glfw::GamepadButton::ButtonRightBumper,
+        GamepadButton::Back => glfw::GamepadButton::ButtonBack,
+        GamepadButton::Start => glfw::GamepadButton::ButtonStart,
+        GamepadButton::Guide => glfw::GamepadButton::ButtonGuide,
+        GamepadButton::LeftThumb => glfw::GamepadButton::ButtonLeftThumb,
+        GamepadButton::RightThumb => glfw::GamepadButton::ButtonRightThumb,
+        GamepadButton::DpadUp => glfw::GamepadButton::ButtonDpadUp,
+        GamepadButton::DpadRight => glfw::GamepadButton::ButtonDpadRight,
+        GamepadButton::DpadDown => glfw::GamepadButton::ButtonDpadDown,
+        GamepadButton::DpadLeft => glfw::GamepadButton::ButtonDpadLeft,
+    }
+}
+
+fn gamepad_axis_to_glfw(axis: GamepadAxis) -> glfw::GamepadAxis {
+    match axis {
+        GamepadAxis::LeftX => glfw::GamepadAxis::AxisLeftX,
+        GamepadAxis::LeftY => glfw::GamepadAxis::AxisLeftY,
+        GamepadAxis::RightX => glfw::GamepadAxis::AxisRightX,
+        GamepadAxis::RightY => glfw::GamepadAxis::AxisRightY,
+        GamepadAxis::LeftTrigger => glfw::GamepadAxis::AxisLeftTrigger,
+        GamepadAxis::RightTrigger => glfw::GamepadAxis::AxisRightTrigger,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::GenericEventListener;
+
+    #[derive(Default)]
+    struct Recorder(Vec<GamepadEvent>);
+
+    impl EventDispatcher for Recorder {
+        fn dispatch(&mut self, event: Event) {
+            if let Event::Gamepad(event) = event {
+                self.0.push(event);
+            }
+        }
+
+        fn consume(&mut self, _listener: &mut dyn GenericEventListener) {}
+    }
+
+    fn snapshot(button: Option<GamepadButton>, trigger: f32) -> Snapshot {
+        let mut snapshot = Snapshot::released();
+        if let Some(button) = button {
+            let index = GamepadButton::ALL
+                .iter()
+                .position(|b| *b == button)
+                .unwrap();
+            snapshot.buttons[index] = true;
+        }
+        let trigger_index = GamepadAxis::ALL
+            .iter()
+            .position(|axis| *axis == GamepadAxis::LeftTrigger)
+            .unwrap();
+        snapshot.axes[trigger_index] = trigger;
+        snapshot
+    }
+
+    #[test]
+    fn connect_hold_and_disconnect() {
+        let mut gamepads = Gamepads::new();
+        let mut events = Recorder::default();
+
+        gamepads.update(2, Some(snapshot(Some(GamepadButton::A), 0.5)), &mut events);
+        assert!(gamepads.is_connected(2));
+        assert!(matches!(
+            events.0.as_slice(),
+            [
+                GamepadEvent::Connected(2),
+                GamepadEvent::Press(2, GamepadButton::A),
+                GamepadEvent::Axis(2, GamepadAxis::LeftTrigger, value),
+            ] if *value == 0.5
+        ));
+
+        // unchanged or within the noise, nothing to report
+        events.0.clear();
+        gamepads.update(
+            2,
+            Some(snapshot(Some(GamepadButton::A), 0.5005)),
+            &mut events,
+        );
+        gamepads.update(3, None, &mut events);
+        assert!(events.0.is_empty());
+
+        gamepads.update(2, None, &mut events);
+        assert!(!gamepads.is_connected(2));
+        assert!(matches!(
+            events.0.as_slice(),
+            [
+                GamepadEvent::Release(2, GamepadButton::A),
+                GamepadEvent::Axis(2, GamepadAxis::LeftTrigger, value),
+                GamepadEvent::Disconnected(2),
+            ] if *value == 0.0
+        ));
+    }
+}
@@ -1,8 +1,12 @@
+mod gamepad;
+
 use glfw::Context;
 use log::{debug, info};
 
 extern crate glfw;
 
+use self::gamepad::Gamepads;
+
 use super::event::{
     modifiers as app_modifiers, AppEvent, Event, EventDispatcher, Key, KeyboardEvent, MouseButton,
     MouseEvent, WindowEvent,
@@ -14,6 +18,9 @@ pub struct Window {
     events: std::sync::mpsc::Receiver<(f64, glfw::WindowEvent)>,
     vsync: bool,
     resizable: bool,
+    gamepads: Gamepads,
+    // one list per `FileDrop` event of the frame
+    dropped_files: Vec<Vec<std::path::PathBuf>>,
     pub should_close: bool,
 }
 
@@ -42,6 +49,8 @@ impl Window {
             events: events,
             vsync: false,
             resizable: true,
+            gamepads: Gamepads::new(),
+            dropped_files: Vec::new(),
             should_close: false,
         }
     }
@@ -50,6 +59,8 @@ impl Window {
         info!(target: "GEAR", "creating window");
 
         self.api.set_all_polling(true);
+        // each character also comes with its modifiers, only that one is sent
+        self.api.set_char_polling(false);
         self.api.make_current();
     }
 
@@ -59,58 +70,25 @@ impl Window {
     }
 
     fn dispatch(&mut self, dispatcher: &mut dyn EventDispatcher) {
-        for (_, event) in glfw::flush_messages(&self.events) {
-            match event {
-                // window
-                glfw::WindowEvent::Close => dispatcher.dispatch(Event::Window(WindowEvent::Close)),
-                glfw::WindowEvent::Size(width, height) => dispatcher.dispatch(Event::Window(
-                    WindowEvent::Resize(width as u32, height as u32),
-                )),
-
-                // keyboard
-                glfw::WindowEvent::Key(key, _, action, modifiers) => match action {
-                    glfw::Action::Press => dispatcher.dispatch(Event::Keyboard(
-                        KeyboardEvent::Press(glfw_to_key(key), glfw_to_modifier(modifiers)),
-                    )),
-
-                    glfw::Action::Release => dispatcher.dispatch(Event::Keyboard(
-                        KeyboardEvent::Release(glfw_to_key(key), glfw_to_modifier(modifiers)),
-                    )),
-                    glfw::Action::Repeat => dispatcher.dispatch(Event::Keyboard(
-                        KeyboardEvent::Repeat(glfw_to_key(key), glfw_to_modifier(modifiers)),
-                    )),
-                },
+        self.dropped_files.clear();
 
-                // mouse
-                glfw::WindowEvent::MouseButton(button, action, modifiers) => match action {
-                    glfw::Action::Press => dispatcher.dispatch(Event::Mouse(MouseEvent::Press(
-                        glfw_to_mouse_button(button),
-                        glfw_to_modifier(modifiers),
-                    ))),
-                    glfw::Action::Release => {
-                        dispatcher.dispatch(Event::Mouse(MouseEvent::Release(
-                            glfw_to_mouse_button(button),
-                            glfw_to_modifier(modifiers),
-                        )))
-                    }
-                    glfw::Action::Repeat => dispatcher.dispatch(Event::Mouse(MouseEvent::Repeat(
-                        glfw_to_mouse_button(button),
-                        glfw_to_modifier(modifiers),
-                    ))),
-                },
-                glfw::WindowEvent::CursorPos(x, y) => {
-                    dispatcher.dispatch(Event::Mouse(MouseEvent::Move(x, y)))
-                }
-                glfw::WindowEvent::Scroll(x, y) => {
-                    dispatcher.dispatch(Event::Mouse(MouseEvent::Scroll(x, y)))
-                }
-                glfw::WindowEvent::Focus(focus) => {
-                    info!(target: "GEAR", "focus: {}", focus);
-                    dispatcher.dispatch(Event::Window(WindowEvent::Focus(focus)))
+        for (_, event) in glfw::flush_messages(&self.events) {
+            let event = match event {
+                // events are copied around, the paths stay here for the frame
+                glfw::WindowEvent::FileDrop(paths) => {
+                    info!(target: "GEAR", "dropped {} file(s)", paths.len());
+                    self.dropped_files.push(paths);
+                    Event::Window(WindowEvent::FileDrop(self.dropped_files.len() - 1))
                 }
-                _ => {}
+                event => match glfw_to_event(event) {
+                    Some(event) => event,
+                    None => continue,
+                },
             };
+            dispatcher.dispatch(event);
         }
+
+        self.gamepads.poll(&self.glfw, dispatcher);
     }
 
     pub fn close(&mut self) {
@@ -149,6 +127,26 @@ impl Window {
         width as f32 / height as f32
     }
 
+    pub fn get_size(&self) -> (u32, u32) {
+        let (width, height) = self.api.get_size();
+        (width as u32, height as u32)
+    }
+
+    // In pixels, differs from `get_size` on high DPI screens.
+    pub fn get_framebuffer_size(&self) -> (u32, u32) {
+        let (width, height) = self.api.get_framebuffer_size();
+        (width as u32, height as u32)
+    }
+
+    // In window coordinates, the origin is the top left corner.
+    pub fn get_cursor_pos(&self) -> (f64, f64) {
+        self.api.get_cursor_pos()
+    }
+
+    pub fn is_mouse_locked(&self) -> bool {
+        self.api.get_cursor_mode() == glfw::CursorMode::Disabled
+    }
+
     pub fn set_mouse_lock(&mut self, lock: bool) {
         self.api.set_cursor_mode(if lock {
             glfw::CursorMode::Disabled
@@ -160,6 +158,101 @@ impl Window {
     pub fn has_focus(&self) -> bool {
         self.api.is_focused()
     }
+
+    pub fn is_iconified(&self) -> bool {
+        self.api.is_iconified()
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.api.is_maximized()
+    }
+
+    // Scale of the monitor relative to 96 dpi, for sizing UI.
+    pub fn get_content_scale(&self) -> (f32, f32) {
+        self.api.get_content_scale()
+    }
+
+    // Paths of the `FileDrop` event with that index, empty once the next
+    // frame's events are dispatched.
+    pub fn dropped_files(&self, drop: usize) -> &[std::path::PathBuf] {
+        self.dropped_files.get(drop).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_gamepad_connected(&self, index: usize) -> bool {
+        self.gamepads.is_connected(index)
+    }
+
+    // Name from the mapping database, e.g. "Xbox Controller".
+    pub fn gamepad_name(&self, index: usize) -> Option<String> {
+        if !self.is_gamepad_connected(index) {
+            return None;
+        }
+        let id = glfw::JoystickId::from_i32(index as i32)?;
+        self.glfw.get_joystick(id).get_gamepad_name()
+    }
+
+    // Adds SDL_GameControllerDB mappings, one per line, for controllers
+    // missing from the database GLFW ships with. Returns false if a line
+    // failed to parse.
+    pub fn update_gamepad_mappings(&mut self, mappings: &str) -> bool {
+        self.glfw.update_gamepad_mappings(mappings)
+    }
+
+    // Same from a file, e.g. a `gamecontrollerdb.txt`.
+    pub fn load_gamepad_mappings(&mut self, path: &str) -> std::io::Result<bool> {
+        let mappings = std::fs::read_to_string(path)?;
+        Ok(self.update_gamepad_mappings(&mappings))
+    }
+}
+
+// `None` for the events the application doesn't use. File drops carry their
+// paths and are handled by the window.
+fn glfw_to_event(event: glfw::WindowEvent) -> Option<Event> {
+    Some(match event {
+        // window
+        glfw::WindowEvent::Close => Event::Window(WindowEvent::Close),
+        glfw::WindowEvent::Size(width, height) => {
+            Event::Window(WindowEvent::Resize(width as u32, height as u32))
+        }
+        glfw::WindowEvent::FramebufferSize(width, height) => {
+            Event::Window(WindowEvent::FramebufferResize(width as u32, height as u32))
+        }
+        glfw::WindowEvent::Pos(x, y) => Event::Window(WindowEvent::Move(x, y)),
+        glfw::WindowEvent::Iconify(iconified) => Event::Window(WindowEvent::Iconify(iconified)),
+        glfw::WindowEvent::Maximize(maximized) => Event::Window(WindowEvent::Maximize(maximized)),
+        glfw::WindowEvent::ContentScale(x, y) => Event::Window(WindowEvent::ContentScale(x, y)),
+        glfw::WindowEvent::Focus(focus) => {
+            info!(target: "GEAR", "focus: {}", focus);
+            Event::Window(WindowEvent::Focus(focus))
+        }
+
+        // keyboard
+        glfw::WindowEvent::Key(key, _, action, modifiers) => {
+            let (key, modifiers) = (glfw_to_key(key), glfw_to_modifier(modifiers));
+            Event::Keyboard(match action {
+                glfw::Action::Press => KeyboardEvent::Press(key, modifiers),
+                glfw::Action::Release => KeyboardEvent::Release(key, modifiers),
+                glfw::Action::Repeat => KeyboardEvent::Repeat(key, modifiers),
+            })
+        }
+        glfw::WindowEvent::CharModifiers(character, modifiers) => {
+            Event::Keyboard(KeyboardEvent::Char(character, glfw_to_modifier(modifiers)))
+        }
+
+        // mouse
+        glfw::WindowEvent::MouseButton(button, action, modifiers) => {
+            let (button, modifiers) = (glfw_to_mouse_button(button), glfw_to_modifier(modifiers));
+            Event::Mouse(match action {
+                glfw::Action::Press => MouseEvent::Press(button, modifiers),
+                glfw::Action::Release => MouseEvent::Release(button, modifiers),
+                glfw::Action::Repeat => MouseEvent::Repeat(button, modifiers),
+            })
+        }
+        glfw::WindowEvent::CursorPos(x, y) => Event::Mouse(MouseEvent::Move(x, y)),
+        glfw::WindowEvent::Scroll(x, y) => Event::Mouse(MouseEvent::Scroll(x, y)),
+        glfw::WindowEvent::CursorEnter(entered) => Event::Mouse(MouseEvent::Enter(entered)),
+        _ => return None,
+    })
 }
 
 fn glfw_to_modifier(modifier: glfw::Modifiers) -> u8 {
@@ -301,3 +394,57 @@ fn glfw_to_mouse_button(button: glfw::MouseButton) -> MouseButton {
         glfw::MouseButton::Button8 => MouseButton::Button8,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_characters_keep_their_modifiers() {
+        let event = glfw_to_event(glfw::WindowEvent::CharModifiers(
+            'A',
+            glfw::Modifiers::Shift,
+        ));
+        assert!(matches!(
+            event,
+            Some(Event::Keyboard(KeyboardEvent::Char(
+                'A',
+                app_modifiers::SHIFT
+            )))
+        ));
+
+        // char polling is off, every character comes once with its modifiers
+        assert!(glfw_to_event(glfw::WindowEvent::Char('A')).is_none());
+    }
+
+    #[test]
+    fn window_state_events() {
+        let events = [
+            glfw::WindowEvent::Pos(10, -20),
+            glfw::WindowEvent::Iconify(true),
+            glfw::WindowEvent::Maximize(false),
+            glfw::WindowEvent::FramebufferSize(1600, 1200),
+            glfw::WindowEvent::ContentScale(2.0, 1.5),
+            glfw::WindowEvent::CursorEnter(false),
+        ]
+        .map(glfw_to_event);
+
+        assert!(matches!(
+            events,
+            [
+                Some(Event::Window(WindowEvent::Move(10, -20))),
+                Some(Event::Window(WindowEvent::Iconify(true))),
+                Some(Event::Window(WindowEvent::Maximize(false))),
+                Some(Event::Window(WindowEvent::FramebufferResize(1600, 1200))),
+                Some(Event::Window(WindowEvent::ContentScale(x, y))),
+                Some(Event::Mouse(MouseEvent::Enter(false))),
+            ] if x == 2.0 && y == 1.5
+        ));
+    }
+
+    #[test]
+    fn file_drops_are_left_to_the_window() {
+        let paths = vec![std::path::PathBuf::from("model.gltf")];
+        assert!(glfw_to_event(glfw::WindowEvent::FileDrop(paths)).is_none());
+    }
+}
@@ -1,8 +1,36 @@
-pub use super::core::application::{context::Context, Application, Gear};
-pub use super::core::assets::AssetsManager;
+pub use super::core::application::{context::Context, Application, Gear, Time};
+pub use super::core::assets::{AssetError, AssetsManager, Handle, MeshHandle, ShaderHandle};
+pub use super::core::audio::{
+    Audio, AudioError, Bus, Ducking, Listener, MusicStream, Sound, Source, SourceId,
+};
+pub use super::core::ecs::{EntityId, Query, Schedule, Stage, System, World};
 pub use super::core::entity::{
-    cube, square, EntityBuffer, EntityBuilder, EntityError, Light, LightBuilder, Material,
+    cube, player_input_system, square, Children, EntityBuilder, EntityError, EulerOrder,
+    GlobalTransform, Indices, Light, LightBuilder, Material, MaterialInput, Mesh, MeshError,
+    Parent, Player, PlayerControls, PlayerInput, RayHit, RenderLayers, Renderable, Topology,
+    Transform,
+};
+pub use super::core::event::{
+    EventListener, GamepadAxis, GamepadButton, Key, MouseButton, ParseNameError,
+};
+pub use super::core::input::{
+    ActionBinding, AxisBinding, AxisInput, ButtonInput, Input, InputError, InputMap, InputState,
+    ModifierKey,
 };
-pub use super::core::event::EventListener;
 pub use super::core::layer::LayerStack;
-pub use super::core::renderer::shader::ShaderError;
+pub use super::core::math::{Aabb, Bezier, CatmullRom, Easing, Ray, Sphere, Triangle};
+pub use super::core::physics::{BodyKind, Collider, ColliderShape, Physics, RigidBody};
+pub use super::core::renderer::buffer::{BufferUsage, RingBuffer};
+pub use super::core::renderer::camera::{
+    CameraController, CameraControllerConfig, FirstPersonController, FollowController, Fov,
+    FreeFlyController, OrbitController, Projection, Viewport,
+};
+pub use super::core::renderer::environment::{Environment, EnvironmentError};
+pub use super::core::renderer::picking::{PickResult, Picking};
+pub use super::core::renderer::shader::{ShaderError, PBR_SHADER};
+pub use super::core::renderer::target::RenderTarget;
+pub use super::core::renderer::texture::{Cubemap, TextureError};
+pub use super::core::scene::{
+    OverrideField, Prefab, PrefabBuilder, PrefabInstance, PrefabOverride, Prefabs, Scene,
+    SceneError,
+};